@@ -0,0 +1,49 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+include!("src/cli.rs");
+
+/// Renders a man page for `cmd` and each of its subcommands into `dir`, returning
+/// the page names (e.g. `kaput-files-list`).
+fn render_pages(cmd: &Command, page_name: &str, dir: &Path) -> io::Result<Vec<String>> {
+    let mut buffer: Vec<u8> = vec![];
+    clap_mangen::Man::new(cmd.clone())
+        .title(page_name)
+        .render(&mut buffer)?;
+    fs::write(dir.join(format!("{}.1", page_name)), buffer)?;
+
+    let mut pages = vec![page_name.to_string()];
+    for sub in cmd.get_subcommands() {
+        let sub_page = format!("{}-{}", page_name, sub.get_name());
+        pages.extend(render_pages(sub, &sub_page, dir)?);
+    }
+    Ok(pages)
+}
+
+fn main() -> io::Result<()> {
+    println!("cargo:rerun-if-changed=src/cli.rs");
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").expect("OUT_DIR is not set"));
+    let man_dir = out_dir.join("man");
+    fs::create_dir_all(&man_dir)?;
+
+    // Propagated so every subcommand's page has the version in its header too
+    let mut cmd = cli()
+        .version(env!("CARGO_PKG_VERSION"))
+        .propagate_version(true);
+    cmd.build();
+    let pages = render_pages(&cmd, "kaput", &man_dir)?;
+
+    // Embedded by the binary so `kaput help --man` works without the pages installed
+    let mut index = String::from("pub const MAN_PAGES: &[(&str, &str)] = &[\n");
+    for page in &pages {
+        index.push_str(&format!(
+            "    ({:?}, include_str!(concat!(env!(\"OUT_DIR\"), \"/man/{}.1\"))),\n",
+            page, page
+        ));
+    }
+    index.push_str("];\n");
+    fs::write(out_dir.join("man_pages.rs"), index)
+}
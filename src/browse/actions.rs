@@ -0,0 +1,751 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use super::app::{file_actions_for, AppState, BrowserApp, FilterForm, ModalState, PendingAction};
+use super::{snapshot, theme};
+use crate::{config, put};
+
+/// Everything that can be done from the file list. The keymap and the command
+/// palette both resolve to one of these, so a key and its palette entry always agree.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    PageUp,
+    PageDown,
+    GoToTop,
+    GoToBottom,
+    Open,
+    Back,
+    FileActions,
+    Find,
+    FindNext,
+    Search,
+    Filter,
+    FetchUrl,
+    CycleSort,
+    ReverseSort,
+    ToggleMark,
+    MarkAll,
+    InvertMarks,
+    MarkMatching,
+    ClearMarks,
+    Delete,
+    ShowCast,
+    ShowActivity,
+    TogglePrettyNames,
+    ToggleGrid,
+    CycleColors,
+    TogglePin,
+    SaveView,
+    CommandPalette,
+    OpenAccountTab,
+    NewTab,
+    CloseTab,
+    NextTab,
+    Quit,
+    /// Select the next name starting with a letter
+    JumpToLetter(char),
+    /// Bring the tab at this index to the front
+    SwitchTab(usize),
+}
+
+pub struct ActionInfo {
+    pub action: Action,
+    pub label: &'static str,
+    /// Key shown next to the label in the palette
+    pub keys: &'static str,
+    /// Shorter label for the help bar
+    pub hint: &'static str,
+}
+
+/// Registry of file list actions, in the order the palette lists them.
+pub const ACTIONS: &[ActionInfo] = &[
+    ActionInfo {
+        action: Action::Open,
+        label: "Open",
+        keys: "Enter",
+        hint: "Open",
+    },
+    ActionInfo {
+        action: Action::Back,
+        label: "Go back",
+        keys: "Bksp",
+        hint: "Back",
+    },
+    ActionInfo {
+        action: Action::FileActions,
+        label: "File actions",
+        keys: "^O",
+        hint: "Actions",
+    },
+    ActionInfo {
+        action: Action::Search,
+        label: "Search put.io",
+        keys: "^F",
+        hint: "Search",
+    },
+    ActionInfo {
+        action: Action::Find,
+        label: "Find in folder",
+        keys: "/",
+        hint: "Find",
+    },
+    ActionInfo {
+        action: Action::FindNext,
+        label: "Find next",
+        keys: "n",
+        hint: "Next match",
+    },
+    ActionInfo {
+        action: Action::Filter,
+        label: "Filter files",
+        keys: "F2",
+        hint: "Filter",
+    },
+    ActionInfo {
+        action: Action::FetchUrl,
+        label: "Fetch URL",
+        keys: "^V",
+        hint: "Fetch URL",
+    },
+    ActionInfo {
+        action: Action::CycleSort,
+        label: "Change sort field",
+        keys: "s",
+        hint: "Sort",
+    },
+    ActionInfo {
+        action: Action::ReverseSort,
+        label: "Reverse sort order",
+        keys: "r",
+        hint: "Reverse",
+    },
+    ActionInfo {
+        action: Action::ToggleMark,
+        label: "Mark/unmark",
+        keys: "Space",
+        hint: "Mark",
+    },
+    ActionInfo {
+        action: Action::MarkAll,
+        label: "Mark all",
+        keys: "A",
+        hint: "Mark all",
+    },
+    ActionInfo {
+        action: Action::InvertMarks,
+        label: "Invert marks",
+        keys: "I",
+        hint: "Invert",
+    },
+    ActionInfo {
+        action: Action::MarkMatching,
+        label: "Mark matching pattern",
+        keys: "*",
+        hint: "Mark pattern",
+    },
+    ActionInfo {
+        action: Action::ClearMarks,
+        label: "Clear marks",
+        keys: "Esc",
+        hint: "Unmark all",
+    },
+    ActionInfo {
+        action: Action::Delete,
+        label: "Delete",
+        keys: "x",
+        hint: "Delete",
+    },
+    ActionInfo {
+        action: Action::ShowCast,
+        label: "Show cast controls",
+        keys: "c",
+        hint: "Cast",
+    },
+    ActionInfo {
+        action: Action::ShowActivity,
+        label: "Activity log",
+        keys: "L",
+        hint: "Activity",
+    },
+    ActionInfo {
+        action: Action::TogglePrettyNames,
+        label: "Toggle release names",
+        keys: "P",
+        hint: "Release names",
+    },
+    ActionInfo {
+        action: Action::ToggleGrid,
+        label: "Toggle grid view for images",
+        keys: "V",
+        hint: "Grid",
+    },
+    ActionInfo {
+        action: Action::CycleColors,
+        label: "Cycle color presets",
+        keys: "C",
+        hint: "Colors",
+    },
+    ActionInfo {
+        action: Action::TogglePin,
+        label: "Pin/unpin to top",
+        keys: ".",
+        hint: "Pin",
+    },
+    ActionInfo {
+        action: Action::SaveView,
+        label: "Save listing to file",
+        keys: "E",
+        hint: "Save listing",
+    },
+    ActionInfo {
+        action: Action::MoveUp,
+        label: "Move up",
+        keys: "k/↑",
+        hint: "Up",
+    },
+    ActionInfo {
+        action: Action::MoveDown,
+        label: "Move down",
+        keys: "j/↓",
+        hint: "Down",
+    },
+    ActionInfo {
+        action: Action::MoveLeft,
+        label: "Move left",
+        keys: "h/←",
+        hint: "Left",
+    },
+    ActionInfo {
+        action: Action::MoveRight,
+        label: "Move right",
+        keys: "l/→",
+        hint: "Right",
+    },
+    ActionInfo {
+        action: Action::PageUp,
+        label: "Page up",
+        keys: "^U",
+        hint: "Page up",
+    },
+    ActionInfo {
+        action: Action::PageDown,
+        label: "Page down",
+        keys: "^D",
+        hint: "Page down",
+    },
+    ActionInfo {
+        action: Action::GoToTop,
+        label: "Go to top",
+        keys: "gg",
+        hint: "Top",
+    },
+    ActionInfo {
+        action: Action::GoToBottom,
+        label: "Go to bottom",
+        keys: "G",
+        hint: "Bottom",
+    },
+    ActionInfo {
+        action: Action::NewTab,
+        label: "New tab",
+        keys: "^T",
+        hint: "New tab",
+    },
+    ActionInfo {
+        action: Action::CloseTab,
+        label: "Close tab",
+        keys: "^W",
+        hint: "Close tab",
+    },
+    ActionInfo {
+        action: Action::NextTab,
+        label: "Next tab",
+        keys: "Tab",
+        hint: "Next tab",
+    },
+    ActionInfo {
+        action: Action::OpenAccountTab,
+        label: "Open tab for another account",
+        keys: "@",
+        hint: "Account tab",
+    },
+    ActionInfo {
+        action: Action::CommandPalette,
+        label: "Command palette",
+        keys: "^P",
+        hint: "Commands",
+    },
+    ActionInfo {
+        action: Action::Quit,
+        label: "Quit",
+        keys: "q",
+        hint: "Quit",
+    },
+];
+
+/// Help bar entries for browsing a folder, most useful first.
+const LIST_HINTS: &[Action] = &[
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::Open,
+    Action::FileActions,
+    Action::Back,
+    Action::CycleSort,
+    Action::ReverseSort,
+    Action::Find,
+    Action::Search,
+    Action::ToggleMark,
+    Action::Delete,
+    Action::CommandPalette,
+    Action::Quit,
+];
+
+/// Help bar entries for the grid view, which moves sideways too.
+const GRID_HINTS: &[Action] = &[
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::Open,
+    Action::FileActions,
+    Action::Back,
+    Action::ToggleGrid,
+    Action::ToggleMark,
+    Action::Delete,
+    Action::CommandPalette,
+];
+
+/// Help bar entries for search results. Going to a result's folder is added
+/// from the file actions.
+const SEARCH_HINTS: &[Action] = &[
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::Open,
+    Action::FileActions,
+    Action::Search,
+    Action::FindNext,
+    Action::Back,
+    Action::CommandPalette,
+];
+
+/// Help bar entries while files are marked, for working on all of them.
+const MARKED_HINTS: &[Action] = &[
+    Action::ToggleMark,
+    Action::MarkAll,
+    Action::InvertMarks,
+    Action::MarkMatching,
+    Action::Delete,
+    Action::ClearMarks,
+    Action::FileActions,
+    Action::CommandPalette,
+];
+
+fn info(action: Action) -> Option<&'static ActionInfo> {
+    ACTIONS.iter().find(|info| info.action == action)
+}
+
+/// Keys for the help bar as (keys, label), for whatever is in front: the open
+/// dialog, or the file list in its current state.
+pub fn help_entries(app: &BrowserApp) -> Vec<(String, String)> {
+    if !matches!(app.modal, ModalState::None) {
+        return modal_keys(&app.modal)
+            .iter()
+            .map(|(keys, label)| (keys.to_string(), label.to_string()))
+            .collect();
+    }
+
+    let hints = if !app.marked.is_empty() {
+        MARKED_HINTS
+    } else if app.is_search_results {
+        SEARCH_HINTS
+    } else if app.showing_grid() {
+        GRID_HINTS
+    } else {
+        LIST_HINTS
+    };
+    let mut entries: Vec<(String, String)> = hints
+        .iter()
+        .filter(|action| is_available(app, **action))
+        .filter_map(|action| info(*action))
+        .map(|info| {
+            let label = match info.action {
+                Action::CycleSort => format!("Sort: {}", app.sort_field.name()),
+                Action::Delete if !app.marked.is_empty() => "Delete marked".to_string(),
+                _ => info.hint.to_string(),
+            };
+            (info.keys.to_string(), label)
+        })
+        .collect();
+
+    if app.is_search_results && app.marked.is_empty() {
+        let go_to_folder = app.selected_file().and_then(|file| {
+            file_actions_for(&file.file_type, true, app.read_only, false)
+                .into_iter()
+                .find(|action| action.label == "Go to folder")
+        });
+        if let Some(action) = go_to_folder {
+            let at = entries.len().min(4);
+            entries.insert(at, (format!("^O {}", action.key), action.label.to_string()));
+        }
+    }
+    entries
+}
+
+/// Keys of each dialog, shown in the help bar while it is open.
+fn modal_keys(modal: &ModalState) -> &'static [(&'static str, &'static str)] {
+    match modal {
+        ModalState::None | ModalState::Loading => &[],
+        ModalState::Error(_) | ModalState::Success(_) => &[("any key", "Close")],
+        ModalState::TimedOut { .. } => &[("Enter/r", "Retry"), ("any key", "Close")],
+        ModalState::Welcome => &[("Enter", "Log in"), ("Esc", "Quit")],
+        ModalState::Login { .. } => &[("Esc", "Cancel")],
+        ModalState::SessionExpired => &[("L", "Log in again"), ("Esc", "Close")],
+        ModalState::ConfirmDelete { .. } | ModalState::ConfirmPruneTrash { .. } => {
+            &[("y", "Yes"), ("n/Esc", "No")]
+        }
+        ModalState::FileActions { .. } => &[
+            ("↑↓/jk", "Select"),
+            ("Enter", "Run"),
+            ("letter", "Run its action"),
+            ("Esc", "Close"),
+        ],
+        ModalState::PickProfile { .. }
+        | ModalState::OpenWith { .. }
+        | ModalState::CastDevices { .. } => {
+            &[("↑↓/jk", "Select"), ("Enter", "Choose"), ("Esc", "Cancel")]
+        }
+        ModalState::CopyTo(_) => &[
+            ("↑↓/jk", "Select"),
+            ("Enter/l", "Open folder"),
+            ("Bksp/h", "Parent folder"),
+            ("c", "Copy here"),
+            ("Esc", "Cancel"),
+        ],
+        ModalState::Find { .. } => &[("Enter", "Find"), ("Esc", "Cancel")],
+        ModalState::SearchInput { .. } => &[
+            ("Enter", "Search"),
+            ("↑↓", "History"),
+            ("^P", "Pin query"),
+            ("Alt+1-9", "Pinned search"),
+            ("Esc", "Cancel"),
+        ],
+        ModalState::GlobSelect { .. } => &[("Enter", "Mark"), ("Esc", "Cancel")],
+        ModalState::FetchUrl { .. } => &[("Enter", "Fetch"), ("Esc", "Cancel")],
+        ModalState::ShareLink { .. } => &[("Enter", "Create link"), ("Esc", "Cancel")],
+        ModalState::SaveView { .. } => &[
+            ("Enter", "Save"),
+            ("Tab", "Complete path"),
+            ("Esc", "Cancel"),
+        ],
+        ModalState::DownloadTo { .. } => &[
+            ("Enter", "Download"),
+            ("Tab", "Complete path"),
+            ("↑↓", "Recent folders"),
+            ("Esc", "Cancel"),
+        ],
+        ModalState::DownloadConflict { .. } => &[
+            ("o", "Overwrite"),
+            ("r", "Resume"),
+            ("n", "New name"),
+            ("s/Esc", "Skip"),
+        ],
+        ModalState::Casting => &[
+            ("Space", "Pause"),
+            ("←→", "Seek 30s"),
+            ("s", "Stop"),
+            ("Esc", "Hide"),
+        ],
+        ModalState::Filter(_) => &[
+            ("↑↓/Tab", "Field"),
+            ("←→", "File type"),
+            ("Enter", "Apply"),
+            ("Del", "Clear filters"),
+            ("Esc", "Cancel"),
+        ],
+        ModalState::Activity { .. } => &[("↑↓/jk", "Scroll"), ("Esc", "Close")],
+        ModalState::CommandPalette { .. } => {
+            &[("↑↓", "Select"), ("Enter", "Run"), ("Esc", "Close")]
+        }
+    }
+}
+
+/// Looks up the action bound to `key` in the file list.
+pub fn for_key(app: &BrowserApp, key: KeyEvent) -> Option<Action> {
+    let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+    let action = match key.code {
+        KeyCode::Char('q') => Action::Quit,
+        // Esc unwinds one step at a time: marks, then folders, then the app
+        KeyCode::Esc if !app.marked.is_empty() => Action::ClearMarks,
+        KeyCode::Esc if app.breadcrumbs.len() > 1 => Action::Back,
+        KeyCode::Esc => Action::Quit,
+        KeyCode::Up | KeyCode::Char('k') => Action::MoveUp,
+        KeyCode::Down | KeyCode::Char('j') => Action::MoveDown,
+        KeyCode::Home => Action::GoToTop,
+        KeyCode::End | KeyCode::Char('G') => Action::GoToBottom,
+        KeyCode::Char('u') if ctrl => Action::PageUp,
+        KeyCode::Char('d') if ctrl => Action::PageDown,
+        KeyCode::Char('o') if ctrl => Action::FileActions,
+        KeyCode::Char('f') if ctrl => Action::Search,
+        KeyCode::Char('v') if ctrl => Action::FetchUrl,
+        KeyCode::Char('p') if ctrl => Action::CommandPalette,
+        KeyCode::Char('t') if ctrl => Action::NewTab,
+        KeyCode::Char('w') if ctrl => Action::CloseTab,
+        KeyCode::Tab => Action::NextTab,
+        KeyCode::Enter if key.modifiers.contains(KeyModifiers::SHIFT) => Action::FileActions,
+        KeyCode::Enter => Action::Open,
+        // The grid moves sideways too, so arrows can't go back there
+        KeyCode::Left | KeyCode::Char('h') if app.showing_grid() => Action::MoveLeft,
+        KeyCode::Right | KeyCode::Char('l') if app.showing_grid() => Action::MoveRight,
+        KeyCode::Left | KeyCode::Backspace => Action::Back,
+        KeyCode::Char('/') => Action::Find,
+        KeyCode::Char('n') => Action::FindNext,
+        KeyCode::Char('c') => Action::ShowCast,
+        KeyCode::Char('L') => Action::ShowActivity,
+        KeyCode::Char('P') => Action::TogglePrettyNames,
+        KeyCode::Char('V') => Action::ToggleGrid,
+        KeyCode::Char('C') => Action::CycleColors,
+        KeyCode::Char('.') => Action::TogglePin,
+        KeyCode::Char('E') => Action::SaveView,
+        KeyCode::F(2) => Action::Filter,
+        KeyCode::Char('s') => Action::CycleSort,
+        KeyCode::Char('r') => Action::ReverseSort,
+        KeyCode::Char('x') => Action::Delete,
+        KeyCode::Char(' ') => Action::ToggleMark,
+        KeyCode::Char('A') => Action::MarkAll,
+        KeyCode::Char('I') => Action::InvertMarks,
+        KeyCode::Char('*') => Action::MarkMatching,
+        KeyCode::Char('@') => Action::OpenAccountTab,
+        KeyCode::Char(c @ '1'..='9') if key.modifiers.contains(KeyModifiers::ALT) => {
+            Action::SwitchTab(c as usize - '1' as usize)
+        }
+        KeyCode::Char(c)
+            if app.letter_jump
+                && c.is_alphabetic()
+                && !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+        {
+            Action::JumpToLetter(c)
+        }
+        _ => return None,
+    };
+    Some(action).filter(|action| is_available(app, *action))
+}
+
+/// Whether `action` makes sense in the app's current state.
+pub fn is_available(app: &BrowserApp, action: Action) -> bool {
+    match action {
+        Action::ShowCast => app.cast_session.is_some(),
+        Action::FetchUrl => !app.read_only && !app.is_search_results,
+        Action::Delete => !app.read_only,
+        Action::ClearMarks => !app.marked.is_empty(),
+        Action::TogglePin => !app.is_search_results,
+        Action::MoveLeft | Action::MoveRight => app.showing_grid(),
+        Action::OpenAccountTab => !app.profiles.is_empty(),
+        Action::CloseTab | Action::NextTab => app.tabs.len() > 1,
+        Action::SwitchTab(index) => index < app.tabs.len() && index != app.active_tab,
+        _ => true,
+    }
+}
+
+pub fn perform(app: &mut BrowserApp, action: Action) {
+    match action {
+        Action::MoveUp => app.move_up(),
+        Action::MoveDown => app.move_down(),
+        Action::MoveLeft => app.move_left(),
+        Action::MoveRight => app.move_right(),
+        Action::PageUp => app.move_page_up(),
+        Action::PageDown => app.move_page_down(),
+        Action::GoToTop => app.select_index(0),
+        Action::GoToBottom => app.select_index(usize::MAX),
+        Action::Open => {
+            if let Some(file) = app.selected_file() {
+                let file_id = file.id;
+                let file_name = file.name.clone();
+                let file_type = file.file_type.clone();
+                if file_type == "FOLDER" {
+                    app.enter_folder(file_id, file_name);
+                    app.needs_reload = true;
+                } else {
+                    app.modal = ModalState::FileActions {
+                        file_id,
+                        file_name,
+                        file_type,
+                        selected: 0,
+                    };
+                }
+            }
+        }
+        Action::Back => {
+            app.go_back();
+            app.needs_reload = true;
+        }
+        Action::FileActions => {
+            if let Some(file) = app.selected_file() {
+                app.modal = ModalState::FileActions {
+                    file_id: file.id,
+                    file_name: file.name.clone(),
+                    file_type: file.file_type.clone(),
+                    selected: 0,
+                };
+            }
+        }
+        Action::Find => {
+            app.modal = ModalState::Find {
+                query: String::new(),
+            };
+        }
+        Action::FindNext => {
+            app.find_next();
+        }
+        Action::Search => {
+            app.modal = ModalState::SearchInput {
+                query: String::new(),
+                history_index: None,
+            };
+        }
+        Action::Filter => {
+            app.modal = ModalState::Filter(FilterForm::from_filter(&app.filter));
+        }
+        Action::FetchUrl => {
+            // Start from the clipboard so a copied link only needs Enter
+            let url = arboard::Clipboard::new()
+                .and_then(|mut cb| cb.get_text())
+                .ok()
+                .filter(|text| put::transfers::is_fetchable_url(text))
+                .map(|text| text.trim().to_string())
+                .unwrap_or_default();
+            app.modal = ModalState::FetchUrl { url };
+        }
+        Action::CycleSort => app.cycle_sort_field(),
+        Action::ReverseSort => app.toggle_sort_direction(),
+        Action::ToggleMark => app.toggle_mark(),
+        Action::MarkAll => app.mark_all(),
+        Action::InvertMarks => app.invert_marks(),
+        Action::MarkMatching => {
+            app.modal = ModalState::GlobSelect {
+                pattern: String::new(),
+            };
+        }
+        Action::ClearMarks => app.marked.clear(),
+        Action::Delete => {
+            if !app.marked.is_empty() {
+                let file_ids: Vec<i64> = app.marked.iter().copied().collect();
+                let label = format!("{} marked items", file_ids.len());
+                app.request_delete(file_ids, label);
+            } else if let Some(file) = app.selected_file() {
+                let file_id = file.id;
+                let label = format!("\"{}\"", file.name);
+                app.request_delete(vec![file_id], label);
+            }
+        }
+        Action::ShowCast => app.modal = ModalState::Casting,
+        Action::ShowActivity => app.modal = ModalState::Activity { scroll: 0 },
+        Action::TogglePrettyNames => app.pretty_names = !app.pretty_names,
+        Action::ToggleGrid => app.grid_view = !app.grid_view,
+        Action::CycleColors => {
+            theme::set_preset(theme::preset().next());
+            app.colors_changed = Some(std::time::Instant::now());
+        }
+        Action::TogglePin => {
+            app.toggle_pin();
+            let mut pinned: Vec<i64> = app.pinned.iter().copied().collect();
+            pinned.sort_unstable();
+            if let Err(e) = config::store_pinned(&pinned) {
+                app.modal = ModalState::Error(format!("Saving pins failed: {}", e));
+            }
+        }
+        Action::SaveView => {
+            app.modal = ModalState::SaveView {
+                path: snapshot::default_file_name(app),
+            };
+        }
+        Action::CommandPalette => {
+            app.modal = ModalState::CommandPalette {
+                query: String::new(),
+                selected: 0,
+            };
+        }
+        Action::OpenAccountTab => app.modal = ModalState::PickProfile { selected: 0 },
+        Action::NewTab => app.pending_action = PendingAction::NewTab,
+        Action::CloseTab => app.pending_action = PendingAction::CloseTab,
+        Action::NextTab => app.switch_tab = Some((app.active_tab + 1) % app.tabs.len()),
+        Action::Quit => app.app_state = AppState::Quitting,
+        Action::JumpToLetter(c) => app.jump_to_letter(c),
+        Action::SwitchTab(index) => app.switch_tab = Some(index),
+    }
+}
+
+/// What a command palette entry runs.
+#[derive(Clone, Copy)]
+pub enum PaletteTarget {
+    Action(Action),
+    /// A file action for the selected file, by label
+    FileAction(&'static str),
+}
+
+pub struct PaletteEntry {
+    pub label: String,
+    pub keys: String,
+    pub target: PaletteTarget,
+}
+
+/// Lists the palette entries matching `query`, best matches first.
+pub fn palette_entries(app: &BrowserApp, query: &str) -> Vec<PaletteEntry> {
+    let mut entries: Vec<PaletteEntry> = ACTIONS
+        .iter()
+        .filter(|info| info.action != Action::CommandPalette && is_available(app, info.action))
+        .map(|info| PaletteEntry {
+            label: info.label.to_string(),
+            keys: info.keys.to_string(),
+            target: PaletteTarget::Action(info.action),
+        })
+        .collect();
+
+    if let Some(file) = app.selected_file() {
+        let actions = file_actions_for(
+            &file.file_type,
+            app.is_search_results,
+            app.read_only,
+            !app.open_with.is_empty(),
+        );
+        for action in actions {
+            entries.push(PaletteEntry {
+                label: format!("{}: {}", file.name, action.label),
+                keys: format!("^O {}", action.key),
+                target: PaletteTarget::FileAction(action.label),
+            });
+        }
+    }
+
+    let mut scored: Vec<(usize, PaletteEntry)> = entries
+        .into_iter()
+        .filter_map(|entry| Some((fuzzy_score(&entry.label, query)?, entry)))
+        .collect();
+    scored.sort_by_key(|(score, _)| *score);
+    scored.into_iter().map(|(_, entry)| entry).collect()
+}
+
+/// Matches `query` as a case-insensitive subsequence of `text`. Lower scores are
+/// better: the fewer characters skipped between matches, the better the match.
+fn fuzzy_score(text: &str, query: &str) -> Option<usize> {
+    let mut chars = text.chars().flat_map(char::to_lowercase);
+    let mut skipped = 0;
+    let mut started = false;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        loop {
+            let c = chars.next()?;
+            if c == q {
+                started = true;
+                break;
+            }
+            if started {
+                skipped += 1;
+            }
+        }
+    }
+    Some(skipped)
+}
@@ -1,4 +1,17 @@
-use crate::put::files::File;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use super::cast::CastDevice;
+use super::download_dirs::RecentDirs;
+use super::folders::FolderCache;
+use super::journal::Journal;
+use super::searches::SearchHistory;
+use super::status::StatusBoard;
+use super::thumbnails::{ThumbnailCell, Thumbnails};
+use crate::config::OpenWith;
+use crate::put::files::{ExistingFile, File};
+use crate::put::rate_limit::RateLimit;
+use crate::{ignore, natsort};
 
 #[derive(Clone, Copy, PartialEq)]
 pub enum SortField {
@@ -8,12 +21,24 @@ pub enum SortField {
     Modified,
 }
 
+impl SortField {
+    pub fn name(self) -> &'static str {
+        match self {
+            SortField::Name => "name",
+            SortField::Size => "size",
+            SortField::Date => "date",
+            SortField::Modified => "modified",
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum SortDirection {
     Asc,
     Desc,
 }
 
+#[derive(Clone)]
 pub struct BreadcrumbEntry {
     pub id: i64,
     pub name: String,
@@ -29,9 +54,36 @@ pub enum AppState {
 pub enum ModalState {
     None,
     Loading,
-    ConfirmDelete {
+    /// Choosing the profile a new tab is opened for
+    PickProfile {
+        selected: usize,
+    },
+    /// Choosing the folder a file is copied to
+    CopyTo(FolderPicker),
+    /// Asking how long a new public link should work
+    ShareLink {
         file_id: i64,
-        file_name: String,
+        expires: String,
+    },
+    /// Asking where to save the current listing
+    SaveView {
+        path: String,
+    },
+    /// A request took too long; Enter runs `retry`
+    TimedOut {
+        message: String,
+        retry: PendingAction,
+    },
+    ConfirmDelete {
+        file_ids: Vec<i64>,
+        /// What is being deleted, e.g. a quoted file name or "3 marked items"
+        label: String,
+    },
+    /// Offering to remove trash items older than `trash_max_age` at startup
+    ConfirmPruneTrash {
+        file_ids: Vec<i64>,
+        /// How many items and how large, e.g. "12 items, 3.2 GB"
+        label: String,
     },
     FileActions {
         file_id: i64,
@@ -44,11 +96,198 @@ pub enum ModalState {
     },
     SearchInput {
         query: String,
+        /// Position in the search history while stepping through it with ↑/↓
+        history_index: Option<usize>,
+    },
+    /// Prompt for a pattern such as `*.rar` to mark matching files
+    GlobSelect {
+        pattern: String,
+    },
+    /// Prompt for a URL for Put.io to fetch into the current folder
+    FetchUrl {
+        url: String,
+    },
+    /// Asks where to save a download
+    DownloadTo {
+        file_id: i64,
+        path: String,
+        /// Position in the recent directories while stepping through them with ↑/↓
+        recent_index: Option<usize>,
+    },
+    /// The download's target already exists locally
+    DownloadConflict {
+        file_id: i64,
+        path: String,
+        /// Name of the existing local file
+        file_name: String,
+    },
+    /// Picks one of the configured external programs to open a file with
+    OpenWith {
+        file_id: i64,
+        file_name: String,
+        selected: usize,
+    },
+    CastDevices {
+        file_id: i64,
+        file_name: String,
+        devices: Vec<CastDevice>,
+        selected: usize,
+    },
+    Casting,
+    /// Shown on first run when no account is linked yet
+    Welcome,
+    /// Waiting for the user to enter `code` at put.io/link
+    Login {
+        code: String,
+        last_check: Instant,
+    },
+    /// The API rejected the stored token
+    SessionExpired,
+    Filter(FilterForm),
+    /// Operations performed this session, newest first
+    Activity {
+        scroll: usize,
+    },
+    /// Fuzzy-searchable list of every action, opened with Ctrl+P
+    CommandPalette {
+        query: String,
+        selected: usize,
     },
     Error(String),
     Success(String),
 }
 
+/// File types that the filter panel cycles through, after "any".
+pub const FILTER_TYPES: [&str; 8] = [
+    "FOLDER", "VIDEO", "AUDIO", "IMAGE", "ARCHIVE", "PDF", "TEXT", "FILE",
+];
+
+/// Restricts which files of a listing are shown. Stays active across folders until cleared.
+#[derive(Clone, Default)]
+pub struct FileFilter {
+    pub file_type: Option<String>,
+    pub min_size: Option<u64>,
+    /// Inclusive `YYYY-MM-DD` bounds on the creation date
+    pub created_from: Option<String>,
+    pub created_until: Option<String>,
+}
+
+impl FileFilter {
+    pub fn is_active(&self) -> bool {
+        self.file_type.is_some()
+            || self.min_size.is_some()
+            || self.created_from.is_some()
+            || self.created_until.is_some()
+    }
+
+    pub fn matches(&self, file: &File) -> bool {
+        // Dates from the API start with YYYY-MM-DD, so comparing prefixes is enough
+        let created = file.created_at.get(..10).unwrap_or(&file.created_at);
+        self.file_type.as_ref().is_none_or(|t| &file.file_type == t)
+            && self.min_size.is_none_or(|min| file.size.0 >= min)
+            && self
+                .created_from
+                .as_deref()
+                .is_none_or(|from| created >= from)
+            && self
+                .created_until
+                .as_deref()
+                .is_none_or(|until| created <= until)
+    }
+
+    /// Short summary for the breadcrumb line, e.g. "VIDEO, ≥ 700 MB, from 2024-01-01".
+    pub fn describe(&self) -> String {
+        let mut parts = vec![];
+        if let Some(file_type) = &self.file_type {
+            parts.push(file_type.clone());
+        }
+        if let Some(min_size) = self.min_size {
+            parts.push(format!("≥ {}", bytefmt::format(min_size)));
+        }
+        if let Some(from) = &self.created_from {
+            parts.push(format!("from {}", from));
+        }
+        if let Some(until) = &self.created_until {
+            parts.push(format!("until {}", until));
+        }
+        parts.join(", ")
+    }
+}
+
+/// The filter panel's fields while it is being edited.
+pub struct FilterForm {
+    /// Focused row: 0 = type, 1 = minimum size, 2 = from, 3 = until
+    pub field: usize,
+    /// 0 means any type, otherwise an index into `FILTER_TYPES` plus one
+    pub type_index: usize,
+    pub min_size: String,
+    pub created_from: String,
+    pub created_until: String,
+}
+
+impl FilterForm {
+    pub const FIELDS: usize = 4;
+
+    pub fn from_filter(filter: &FileFilter) -> Self {
+        FilterForm {
+            field: 0,
+            type_index: filter
+                .file_type
+                .as_ref()
+                .and_then(|t| FILTER_TYPES.iter().position(|f| f == t))
+                .map_or(0, |i| i + 1),
+            min_size: filter.min_size.map(bytefmt::format).unwrap_or_default(),
+            created_from: filter.created_from.clone().unwrap_or_default(),
+            created_until: filter.created_until.clone().unwrap_or_default(),
+        }
+    }
+
+    /// The text field under focus, if the type selector is not focused.
+    pub fn focused_text(&mut self) -> Option<&mut String> {
+        match self.field {
+            1 => Some(&mut self.min_size),
+            2 => Some(&mut self.created_from),
+            3 => Some(&mut self.created_until),
+            _ => None,
+        }
+    }
+
+    /// Validates the fields. Empty fields leave that part of the filter unset.
+    pub fn to_filter(&self) -> Result<FileFilter, String> {
+        let min_size = match self.min_size.trim() {
+            "" => None,
+            size => Some(bytefmt::parse(size).map_err(|_| format!("Invalid size \"{}\"", size))?),
+        };
+        Ok(FileFilter {
+            file_type: self
+                .type_index
+                .checked_sub(1)
+                .map(|i| FILTER_TYPES[i].to_string()),
+            min_size,
+            created_from: parse_date(&self.created_from)?,
+            created_until: parse_date(&self.created_until)?,
+        })
+    }
+}
+
+/// Accepts an empty string or a `YYYY-MM-DD` date.
+fn parse_date(text: &str) -> Result<Option<String>, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    let valid = text.len() == 10
+        && text.char_indices().all(|(i, c)| match i {
+            4 | 7 => c == '-',
+            _ => c.is_ascii_digit(),
+        });
+    if valid {
+        Ok(Some(text.to_string()))
+    } else {
+        Err(format!("Invalid date \"{}\", use YYYY-MM-DD", text))
+    }
+}
+
 pub struct FileAction {
     pub label: &'static str,
     pub key: char,
@@ -56,13 +295,25 @@ pub struct FileAction {
 
 /// Returns the ordered list of actions available for a given file type.
 /// Used by both the event handler and the UI renderer.
-pub fn file_actions_for(file_type: &str, in_search_results: bool) -> Vec<FileAction> {
+///
+/// In read-only mode, actions that change the account are left out. "Open with..."
+/// is only offered for files when `open_with` programs are configured.
+pub fn file_actions_for(
+    file_type: &str,
+    in_search_results: bool,
+    read_only: bool,
+    open_with: bool,
+) -> Vec<FileAction> {
     let mut actions = if file_type == "FOLDER" {
         vec![
             FileAction {
                 label: "Download as zip",
                 key: 'z',
             },
+            FileAction {
+                label: "Play folder",
+                key: 'v',
+            },
             FileAction {
                 label: "Open in browser",
                 key: 'b',
@@ -78,6 +329,10 @@ pub fn file_actions_for(file_type: &str, in_search_results: bool) -> Vec<FileAct
         ]
     } else if file_type == "VIDEO" {
         vec![
+            FileAction {
+                label: "Play",
+                key: 'v',
+            },
             FileAction {
                 label: "Copy URL",
                 key: 'c',
@@ -86,6 +341,10 @@ pub fn file_actions_for(file_type: &str, in_search_results: bool) -> Vec<FileAct
                 label: "Copy Stream URL",
                 key: 's',
             },
+            FileAction {
+                label: "Cast to device",
+                key: 't',
+            },
             FileAction {
                 label: "Download",
                 key: 'd',
@@ -127,28 +386,167 @@ pub fn file_actions_for(file_type: &str, in_search_results: bool) -> Vec<FileAct
             },
         ]
     };
+    if file_type == "VIDEO" && !read_only {
+        // Watch status is a change to the account, so not in read-only mode
+        let at = actions
+            .iter()
+            .position(|a| a.label == "Download")
+            .unwrap_or(actions.len());
+        actions.insert(
+            at,
+            FileAction {
+                label: "Toggle watched",
+                key: 'w',
+            },
+        );
+    }
+    if file_type == "ARCHIVE" && !read_only {
+        actions.insert(
+            0,
+            FileAction {
+                label: "Extract",
+                key: 'e',
+            },
+        );
+    }
+    if !read_only {
+        actions.push(FileAction {
+            label: "Copy to...",
+            key: 'y',
+        });
+        actions.push(FileAction {
+            label: "Share link",
+            key: 'l',
+        });
+    }
+    if open_with && file_type != "FOLDER" {
+        actions.push(FileAction {
+            label: "Open with...",
+            key: 'o',
+        });
+    }
     if in_search_results {
         actions.push(FileAction {
             label: "Go to folder",
             key: 'g',
         });
     }
+    if !read_only {
+        actions.push(FileAction {
+            label: "Delete",
+            key: 'x',
+        });
+    }
     actions
 }
 
 pub enum PendingAction {
     None,
-    Download { file_id: i64 },
-    Search { query: String },
-    GoToFolder { parent_id: i64, file_id: i64 },
-    Delete { file_id: i64 },
-    CopyPath { file_name: String, parent_id: i64 },
+    /// List the current folder again
+    Reload,
+    /// Open a tab browsing the account of a profile
+    OpenTab {
+        profile: String,
+    },
+    /// Open a tab on the same account, starting in the current folder
+    NewTab,
+    CloseTab,
+    Download {
+        file_id: i64,
+        path: String,
+        existing: ExistingFile,
+    },
+    Search {
+        query: String,
+    },
+    GoToFolder {
+        parent_id: i64,
+        file_id: i64,
+    },
+    /// List the picker's current folder, then show the picker
+    PickFolder(FolderPicker),
+    Copy {
+        file_id: i64,
+        file_name: String,
+        parent_id: i64,
+        parent_name: String,
+    },
+    /// Show a folder, or select a file in its folder, by path
+    GoToPath {
+        path: String,
+    },
+    Delete {
+        file_ids: Vec<i64>,
+    },
+    CopyPath {
+        file_name: String,
+        parent_id: i64,
+    },
+    CastDiscover {
+        file_id: i64,
+        file_name: String,
+    },
+    Cast {
+        file_id: i64,
+        file_name: String,
+        device: CastDevice,
+    },
+    CastControl {
+        address: String,
+        command: Vec<String>,
+    },
+    LoginStart,
+    SetWatched {
+        file_ids: Vec<i64>,
+        watched: bool,
+    },
+    PlayFolder {
+        folder_id: i64,
+    },
+    /// Play a video in mpv and save where it stopped
+    Play {
+        file_id: i64,
+    },
+    Extract {
+        file_id: i64,
+    },
+    Share {
+        file_id: i64,
+        expires_in: Option<u64>,
+    },
+    /// Look for trash items deleted more than `max_age` seconds ago
+    FindOldTrash {
+        max_age: u64,
+    },
+    PruneTrash {
+        file_ids: Vec<i64>,
+    },
+    FetchUrl {
+        url: String,
+        parent_id: i64,
+    },
+    /// Run the `index`th configured "Open with" program on a file
+    OpenWith {
+        file_id: i64,
+        index: usize,
+    },
+}
+
+/// Playback currently running on a cast device, controlled from the cast overlay.
+pub struct CastSession {
+    pub device: CastDevice,
+    pub file_name: String,
+    pub paused: bool,
 }
 
 pub struct BrowserApp {
     pub current_folder_id: i64,
     pub breadcrumbs: Vec<BreadcrumbEntry>,
+    /// Files shown in the list, after filtering
     pub files: Vec<File>,
+    /// The full listing of the current folder or search
+    all_files: Vec<File>,
+    pub filter: FileFilter,
     pub selected_index: usize,
     pub app_state: AppState,
     pub modal: ModalState,
@@ -164,6 +562,97 @@ pub struct BrowserApp {
     pub last_search: Option<String>,
     pub is_search_results: bool,
     pub pending_select_id: Option<i64>,
+    pub cast_session: Option<CastSession>,
+    /// Hides every action that changes the account, e.g. for a shared TV box
+    pub read_only: bool,
+    /// Whether deletes are confirmed first, from the `confirm_delete` setting
+    pub confirm_delete: bool,
+    /// IDs of files marked for a batch operation in the current listing
+    pub marked: HashSet<i64>,
+    /// IDs of entries kept at the top of their folder regardless of sort
+    pub pinned: HashSet<i64>,
+    /// Action label run by Enter for each file type, from the `default_actions` setting
+    pub default_actions: HashMap<String, String>,
+    /// Compare numbers in names by value, from the `natural_sort` setting
+    pub natural_sort: bool,
+    pub search_history: SearchHistory,
+    pub open_with: Vec<OpenWith>,
+    /// Configured download directory, offered first in the download prompt
+    pub download_dir: Option<String>,
+    pub recent_dirs: RecentDirs,
+    pub folders: FolderCache,
+    /// Show cleaned-up release names instead of the raw file names
+    pub pretty_names: bool,
+    /// Letters without a binding jump to the next name starting with them
+    pub letter_jump: bool,
+    /// Count typed before a motion, e.g. the 5 of `5j`
+    pub pending_count: Option<usize>,
+    /// The first `g` of `gg` was pressed
+    pub pending_g: bool,
+    pub journal: Journal,
+    /// What changed the last time the current folder was listed again
+    pub changes: Option<ListingChanges>,
+    /// Operations in progress, shared by every tab
+    pub status: StatusBoard,
+    /// Labels of the open tabs, each with its own account, folders, sort and
+    /// marks, and the one shown
+    pub tabs: Vec<String>,
+    pub active_tab: usize,
+    /// Tab to bring to the front once the current key is handled
+    pub switch_tab: Option<usize>,
+    /// Profiles from the config that a tab can be opened for
+    pub profiles: Vec<String>,
+    /// When the color preset was last switched, to name the new one for a moment
+    pub colors_changed: Option<Instant>,
+    /// The last transfer that finished or failed and when, for the status bar
+    pub transfer_notice: Option<(String, Instant)>,
+    /// API call budget of the account shown, for the status bar
+    pub rate_limit: Option<RateLimit>,
+    /// Lay out folders that are mostly images as a grid
+    pub grid_view: bool,
+    /// Columns in the grid as last drawn, for moving up and down a row
+    pub grid_columns: usize,
+    /// First grid row shown
+    pub grid_scroll: usize,
+    /// Draws thumbnails in the grid, if the terminal can show images
+    pub thumbnails: Option<Thumbnails>,
+    /// Where thumbnails go in the grid as last drawn
+    pub thumbnail_cells: Vec<ThumbnailCell>,
+    /// Folder the listing was loaded from, `None` for search results
+    listed_folder: Option<i64>,
+}
+
+/// Browses folders one level at a time to choose where a file is copied.
+pub struct FolderPicker {
+    pub file_id: i64,
+    pub file_name: String,
+    /// Folders from the root down to the one being shown, as (id, name)
+    pub path: Vec<(i64, String)>,
+    /// Subfolders of the shown folder
+    pub folders: Vec<File>,
+    pub selected: usize,
+}
+
+impl FolderPicker {
+    pub fn folder_id(&self) -> i64 {
+        self.path.last().map_or(0, |(id, _)| *id)
+    }
+}
+
+/// Entries that appeared in or disappeared from a folder between two listings.
+pub struct ListingChanges {
+    pub added: HashSet<i64>,
+    pub removed: usize,
+    pub at: Instant,
+}
+
+impl ListingChanges {
+    /// How long new entries stay highlighted
+    const SHOWN_FOR: Duration = Duration::from_secs(10);
+
+    pub fn is_fresh(&self) -> bool {
+        self.at.elapsed() < Self::SHOWN_FOR
+    }
 }
 
 impl BrowserApp {
@@ -179,6 +668,8 @@ impl BrowserApp {
                 saved_offset: 0,
             }],
             files: vec![],
+            all_files: vec![],
+            filter: FileFilter::default(),
             selected_index: 0,
             app_state: AppState::Browsing,
             modal: ModalState::Loading,
@@ -194,6 +685,38 @@ impl BrowserApp {
             last_search: None,
             is_search_results: false,
             pending_select_id: None,
+            cast_session: None,
+            read_only: false,
+            confirm_delete: true,
+            marked: HashSet::new(),
+            pinned: HashSet::new(),
+            default_actions: HashMap::new(),
+            natural_sort: true,
+            search_history: SearchHistory::default(),
+            open_with: vec![],
+            download_dir: None,
+            recent_dirs: RecentDirs::default(),
+            folders: FolderCache::default(),
+            letter_jump: true,
+            pretty_names: false,
+            pending_count: None,
+            pending_g: false,
+            journal: Journal::default(),
+            changes: None,
+            status: StatusBoard::default(),
+            tabs: vec![],
+            active_tab: 0,
+            switch_tab: None,
+            profiles: vec![],
+            colors_changed: None,
+            transfer_notice: None,
+            rate_limit: None,
+            grid_view: false,
+            grid_columns: 1,
+            grid_scroll: 0,
+            thumbnails: None,
+            thumbnail_cells: vec![],
+            listed_folder: None,
         }
     }
 
@@ -231,9 +754,113 @@ impl BrowserApp {
         }
     }
 
+    /// Names files for messages: the quoted name of a single file, else a count.
+    pub fn describe_files(&self, file_ids: &[i64]) -> String {
+        match file_ids {
+            [file_id] => match self.files.iter().find(|f| f.id == *file_id) {
+                Some(file) => format!("\"{}\"", file.name),
+                None => format!("file {}", file_id),
+            },
+            _ => format!("{} items", file_ids.len()),
+        }
+    }
+
+    /// Deletes files, asking first unless confirmations are turned off.
+    pub fn request_delete(&mut self, file_ids: Vec<i64>, label: String) {
+        if self.confirm_delete {
+            self.modal = ModalState::ConfirmDelete { file_ids, label };
+        } else {
+            self.start_delete(file_ids);
+        }
+    }
+
+    pub fn start_delete(&mut self, file_ids: Vec<i64>) {
+        self.save_position_for_reload();
+        self.spinner_label = format!("Deleting {} item(s)...", file_ids.len());
+        self.pending_action = PendingAction::Delete { file_ids };
+        self.modal = ModalState::Loading;
+    }
+
+    /// Marks or unmarks the file under the cursor and moves to the next one.
+    pub fn toggle_mark(&mut self) {
+        if let Some(id) = self.selected_file().map(|f| f.id) {
+            if !self.marked.remove(&id) {
+                self.marked.insert(id);
+            }
+            self.move_down();
+        }
+    }
+
+    pub fn mark_all(&mut self) {
+        self.marked = self.files.iter().map(|f| f.id).collect();
+    }
+
+    pub fn invert_marks(&mut self) {
+        self.marked = self
+            .files
+            .iter()
+            .map(|f| f.id)
+            .filter(|id| !self.marked.contains(id))
+            .collect();
+    }
+
+    /// Marks every file whose name matches a glob such as `*.rar`, ignoring case.
+    /// Returns how many files matched.
+    pub fn mark_matching(&mut self, pattern: &str) -> usize {
+        let pattern = pattern.to_lowercase();
+        let matching: Vec<i64> = self
+            .files
+            .iter()
+            .filter(|f| ignore::glob_match(&pattern, &f.name.to_lowercase()))
+            .map(|f| f.id)
+            .collect();
+        let count = matching.len();
+        self.marked.extend(matching);
+        count
+    }
+
+    /// Shows an API error, or the re-login prompt if the token is no longer valid.
+    /// Like `show_api_error`, but a timed-out request gets a modal offering to
+    /// run `retry` again.
+    pub fn show_api_error_or_retry(
+        &mut self,
+        context: &str,
+        error: &reqwest::Error,
+        retry: PendingAction,
+    ) {
+        if error.is_timeout() {
+            self.modal = ModalState::TimedOut {
+                message: format!("{}: the request timed out.", context),
+                retry,
+            };
+        } else {
+            self.show_api_error(context, error);
+        }
+    }
+
+    pub fn show_api_error(&mut self, context: &str, error: &reqwest::Error) {
+        self.modal = if error.status() == Some(reqwest::StatusCode::UNAUTHORIZED) {
+            ModalState::SessionExpired
+        } else {
+            ModalState::Error(format!("{}: {}", context, error))
+        };
+    }
+
+    /// Shows a folder's listing. When the folder that is already shown is listed
+    /// again, the cursor stays on the same entry and the changes are recorded.
     pub fn set_files(&mut self, files: Vec<File>) {
-        self.files = files;
+        let reload = self.listed_folder == Some(self.current_folder_id);
+        let selected_id = self.selected_file().map(|f| f.id).filter(|_| reload);
+        let restore_index = self.restore_index.take();
+        self.track_changes(&files, reload);
+        self.listed_folder = Some(self.current_folder_id);
+
+        self.remember_folders(&files);
+        self.all_files = files;
+        self.files = self.filtered_files();
+        self.marked.clear();
         self.sort_files();
+        let kept = selected_id.and_then(|id| self.files.iter().position(|f| f.id == id));
         let (idx, apply_scroll) = if let Some(select_id) = self.pending_select_id.take() {
             let i = self
                 .files
@@ -241,10 +868,11 @@ impl BrowserApp {
                 .position(|f| f.id == select_id)
                 .unwrap_or(0);
             (i, false) // let ratatui auto-scroll to the selected item
+        } else if let Some(i) = kept {
+            (i, true)
         } else {
-            let i = self
-                .restore_index
-                .take()
+            // e.g. the selected file was deleted, so the next one moves up to its place
+            let i = restore_index
                 .unwrap_or(0)
                 .min(self.files.len().saturating_sub(1));
             (i, true)
@@ -261,6 +889,55 @@ impl BrowserApp {
         self.modal = ModalState::None;
     }
 
+    /// Replaces the current folder's listing after a background refresh, keeping
+    /// the cursor on the same entry, the scroll position and marks that still apply.
+    pub fn refresh_files(&mut self, files: Vec<File>) {
+        let selected_id = self.selected_file().map(|f| f.id);
+        let offset = *self.list_state.offset_mut();
+        self.track_changes(&files, true);
+        self.remember_folders(&files);
+        self.all_files = files;
+        self.files = self.filtered_files();
+        self.sort_files();
+
+        let ids: HashSet<i64> = self.files.iter().map(|f| f.id).collect();
+        self.marked.retain(|id| ids.contains(id));
+        let last = self.files.len().saturating_sub(1);
+        let idx = selected_id
+            .and_then(|id| self.files.iter().position(|f| f.id == id))
+            .unwrap_or(self.selected_index)
+            .min(last);
+        self.selected_index = idx;
+        self.list_state.select(Some(idx));
+        *self.list_state.offset_mut() = offset.min(last);
+    }
+
+    /// Compares a new listing of the shown folder with the current one.
+    fn track_changes(&mut self, files: &[File], reload: bool) {
+        if !reload {
+            self.changes = None;
+            return;
+        }
+        let old: HashSet<i64> = self.all_files.iter().map(|f| f.id).collect();
+        let new: HashSet<i64> = files.iter().map(|f| f.id).collect();
+        let added: HashSet<i64> = new.difference(&old).copied().collect();
+        let removed = old.difference(&new).count();
+        if !added.is_empty() || removed > 0 {
+            self.changes = Some(ListingChanges {
+                added,
+                removed,
+                at: Instant::now(),
+            });
+        }
+    }
+
+    /// Adds the folders of a listing to the folder cache for path lookups.
+    fn remember_folders(&self, files: &[File]) {
+        for file in files.iter().filter(|f| f.file_type == "FOLDER") {
+            self.folders.insert(file.id, &file.name, file.parent_id);
+        }
+    }
+
     /// Display search results. Pushes a virtual breadcrumb (id = -1).
     /// If already showing search results, replaces them in-place.
     pub fn enter_search_results(&mut self, query: &str, files: Vec<File>) {
@@ -283,7 +960,11 @@ impl BrowserApp {
             });
             self.is_search_results = true;
         }
-        self.files = files;
+        self.listed_folder = None;
+        self.changes = None;
+        self.all_files = files;
+        self.files = self.filtered_files();
+        self.marked.clear();
         self.selected_index = 0;
         self.list_state.select(Some(0));
         *self.list_state.offset_mut() = 0;
@@ -318,11 +999,42 @@ impl BrowserApp {
         self.modal = ModalState::Loading;
     }
 
+    fn filtered_files(&self) -> Vec<File> {
+        self.all_files
+            .iter()
+            .filter(|f| self.filter.matches(f))
+            .cloned()
+            .collect()
+    }
+
+    /// Replaces the filter and re-applies it to the current listing, keeping the sort order.
+    pub fn set_filter(&mut self, filter: FileFilter) {
+        self.filter = filter;
+        self.files = self.filtered_files();
+        let visible: HashSet<i64> = self.files.iter().map(|f| f.id).collect();
+        self.marked.retain(|id| visible.contains(id));
+        self.sort_files();
+        self.selected_index = 0;
+        self.list_state.select(Some(0));
+    }
+
     fn sort_files(&mut self) {
         let field = self.sort_field;
         let dir = self.sort_direction;
+        let natural = self.natural_sort;
+        // Search results aren't a folder, so pins don't apply there
+        let pinned = if self.is_search_results {
+            HashSet::new()
+        } else {
+            self.pinned.clone()
+        };
         self.files.sort_by(|a, b| {
+            let pins = pinned.contains(&b.id).cmp(&pinned.contains(&a.id));
+            if pins.is_ne() {
+                return pins;
+            }
             let ord = match field {
+                SortField::Name if natural => natsort::compare(&a.name, &b.name),
                 SortField::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
                 SortField::Size => a.size.0.cmp(&b.size.0),
                 SortField::Date => a.created_at.cmp(&b.created_at),
@@ -368,15 +1080,47 @@ impl BrowserApp {
         self.restore_offset = Some(*self.list_state.offset_mut());
     }
 
+    /// Whether the listing is drawn as a grid: the grid view is on and most of
+    /// the entries are images.
+    pub fn showing_grid(&self) -> bool {
+        let images = self.files.iter().filter(|f| f.file_type == "IMAGE").count();
+        self.grid_view && images * 2 > self.files.len()
+    }
+
+    /// How far up and down move: a row of the grid, or one entry.
+    fn row_step(&self) -> usize {
+        if self.showing_grid() {
+            self.grid_columns.max(1)
+        } else {
+            1
+        }
+    }
+
     pub fn move_up(&mut self) {
+        let step = self.row_step();
+        if self.selected_index >= step {
+            self.selected_index -= step;
+            self.list_state.select(Some(self.selected_index));
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        let step = self.row_step();
+        if self.selected_index + step < self.files.len() {
+            self.selected_index += step;
+            self.list_state.select(Some(self.selected_index));
+        }
+    }
+
+    pub fn move_left(&mut self) {
         if self.selected_index > 0 {
             self.selected_index -= 1;
             self.list_state.select(Some(self.selected_index));
         }
     }
 
-    pub fn move_down(&mut self) {
-        if !self.files.is_empty() && self.selected_index < self.files.len() - 1 {
+    pub fn move_right(&mut self) {
+        if self.selected_index + 1 < self.files.len() {
             self.selected_index += 1;
             self.list_state.select(Some(self.selected_index));
         }
@@ -401,6 +1145,20 @@ impl BrowserApp {
         false
     }
 
+    /// Jump to the next file whose name starts with `letter`, wrapping around.
+    pub fn jump_to_letter(&mut self, letter: char) {
+        let n = self.files.len();
+        for offset in 1..=n {
+            let i = (self.selected_index + offset) % n;
+            let first = self.files[i].name.chars().next();
+            if first.is_some_and(|c| c.to_lowercase().eq(letter.to_lowercase())) {
+                self.selected_index = i;
+                self.list_state.select(Some(i));
+                return;
+            }
+        }
+    }
+
     /// Repeat the last search.
     pub fn find_next(&mut self) -> bool {
         if let Some(query) = self.last_search.clone() {
@@ -411,14 +1169,36 @@ impl BrowserApp {
     }
 
     pub fn move_page_up(&mut self) {
-        self.selected_index = self.selected_index.saturating_sub(10);
+        self.selected_index = self.selected_index.saturating_sub(10 * self.row_step());
         self.list_state.select(Some(self.selected_index));
     }
 
     pub fn move_page_down(&mut self) {
         if !self.files.is_empty() {
             let last = self.files.len() - 1;
-            self.selected_index = (self.selected_index + 10).min(last);
+            self.selected_index = (self.selected_index + 10 * self.row_step()).min(last);
+            self.list_state.select(Some(self.selected_index));
+        }
+    }
+
+    /// Selects the entry at `index`, or the last one if there are fewer.
+    /// Pins or unpins the selected entry and re-sorts, keeping it selected.
+    pub fn toggle_pin(&mut self) {
+        let Some(id) = self.selected_file().map(|f| f.id) else {
+            return;
+        };
+        if !self.pinned.remove(&id) {
+            self.pinned.insert(id);
+        }
+        self.sort_files();
+        if let Some(index) = self.files.iter().position(|f| f.id == id) {
+            self.select_index(index);
+        }
+    }
+
+    pub fn select_index(&mut self, index: usize) {
+        if !self.files.is_empty() {
+            self.selected_index = index.min(self.files.len() - 1);
             self.list_state.select(Some(self.selected_index));
         }
     }
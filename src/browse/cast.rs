@@ -0,0 +1,67 @@
+use std::process::{Command, Stdio};
+
+/// A Chromecast found on the local network.
+#[derive(Clone)]
+pub struct CastDevice {
+    pub name: String,
+    pub address: String,
+}
+
+/// Discovers Chromecast devices via mDNS using `catt scan`.
+///
+/// If you do not have catt installed, visit https://github.com/skorokithakis/catt.
+pub fn discover() -> Result<Vec<CastDevice>, String> {
+    let output = Command::new("catt")
+        .arg("scan")
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Could not run catt (is it installed?): {}", e))?;
+
+    // Lines look like "192.168.1.20 - Living Room - Google Inc. Chromecast"
+    let devices: Vec<CastDevice> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, " - ");
+            let address = parts.next()?.trim();
+            let name = parts.next()?.trim();
+            if address.is_empty() || name.is_empty() {
+                return None;
+            }
+            Some(CastDevice {
+                name: name.to_string(),
+                address: address.to_string(),
+            })
+        })
+        .collect();
+
+    if devices.is_empty() {
+        return Err("No cast devices found on your network.".to_string());
+    }
+    Ok(devices)
+}
+
+/// Starts playback of `url` on the device. Returns once playback has begun.
+pub fn play(device: &str, url: &str) -> Result<(), String> {
+    run(device, &["cast", url])
+}
+
+/// Sends a playback control command (`pause`, `play`, `stop`, `ffwd 30`, ...).
+pub fn control(device: &str, command: &[&str]) -> Result<(), String> {
+    run(device, command)
+}
+
+fn run(device: &str, args: &[&str]) -> Result<(), String> {
+    let output = Command::new("catt")
+        .arg("-d")
+        .arg(device)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Could not run catt (is it installed?): {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
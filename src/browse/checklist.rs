@@ -0,0 +1,133 @@
+use std::io;
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::Modifier,
+    text::{Line, Span},
+    widgets::{List, ListItem, ListState, Paragraph},
+    Terminal,
+};
+use unicode_width::UnicodeWidthStr;
+
+use super::theme;
+
+pub struct ChecklistItem {
+    pub label: String,
+    /// Shown right-aligned next to the label, e.g. a file size
+    pub detail: String,
+    pub checked: bool,
+}
+
+/// Shows a full-screen checklist and returns the checked state of every item,
+/// or `None` if the user cancelled.
+pub fn run(title: &str, mut items: Vec<ChecklistItem>) -> io::Result<Option<Vec<bool>>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let mut list_state = ListState::default();
+    list_state.select(Some(0));
+
+    let result = loop {
+        terminal.draw(|f| {
+            let theme = theme::current();
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(2), // title
+                    Constraint::Min(0),    // items
+                    Constraint::Length(1), // help bar
+                ])
+                .split(f.size());
+
+            let checked = items.iter().filter(|i| i.checked).count();
+            f.render_widget(
+                Paragraph::new(format!(
+                    " {}  ({}/{} selected)",
+                    title,
+                    checked,
+                    items.len()
+                ))
+                .style(theme.text.add_modifier(Modifier::BOLD)),
+                chunks[0],
+            );
+
+            let width = chunks[1].width as usize;
+            let rows: Vec<ListItem> = items
+                .iter()
+                .map(|item| {
+                    let mark = if item.checked { "[x]" } else { "[ ]" };
+                    let style = if item.checked {
+                        theme.success
+                    } else {
+                        theme.dim
+                    };
+                    let label = format!(" {} {}", mark, item.label);
+                    let pad = width.saturating_sub(label.width() + item.detail.width() + 1);
+                    ListItem::new(Line::from(vec![
+                        Span::styled(label, style),
+                        Span::raw(" ".repeat(pad)),
+                        Span::styled(item.detail.clone(), theme.dim),
+                    ]))
+                })
+                .collect();
+            f.render_stateful_widget(
+                List::new(rows).highlight_style(theme.selected),
+                chunks[1],
+                &mut list_state,
+            );
+
+            f.render_widget(
+                Paragraph::new(
+                    "↑↓/jk Navigate    Space Toggle    a Toggle all    Enter Confirm    Esc Cancel",
+                )
+                .alignment(Alignment::Center)
+                .style(theme.dim),
+                chunks[2],
+            );
+        })?;
+
+        if let Event::Key(KeyEvent {
+            code, modifiers, ..
+        }) = event::read()?
+        {
+            let selected = list_state.selected().unwrap_or(0);
+            match code {
+                KeyCode::Char('c') if modifiers.contains(KeyModifiers::CONTROL) => break None,
+                KeyCode::Esc | KeyCode::Char('q') => break None,
+                KeyCode::Enter => break Some(items.iter().map(|i| i.checked).collect()),
+                KeyCode::Up | KeyCode::Char('k') => {
+                    list_state.select(Some(selected.saturating_sub(1)));
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    list_state.select(Some((selected + 1).min(items.len().saturating_sub(1))));
+                }
+                KeyCode::Char(' ') => {
+                    if let Some(item) = items.get_mut(selected) {
+                        item.checked = !item.checked;
+                    }
+                }
+                KeyCode::Char('a') => {
+                    let all_checked = items.iter().all(|i| i.checked);
+                    for item in items.iter_mut() {
+                        item.checked = !all_checked;
+                    }
+                }
+                _ => {}
+            }
+        }
+    };
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    Ok(result)
+}
@@ -0,0 +1,115 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+const DOWNLOAD_DIRS_FILE: &str = "download_dirs.json";
+
+/// Most recent download directories kept
+const MAX_RECENT: usize = 10;
+
+/// Directories files were downloaded to from the browser, most recent first.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentDirs {
+    pub recent: Vec<String>,
+}
+
+impl RecentDirs {
+    pub fn load() -> Self {
+        fs::read_to_string(config::data_file_path(DOWNLOAD_DIRS_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = config::data_file_path(DOWNLOAD_DIRS_FILE);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Moves `dir` to the front of the recent directories.
+    pub fn record(&mut self, dir: &str) {
+        self.recent.retain(|d| d != dir);
+        self.recent.insert(0, dir.to_string());
+        self.recent.truncate(MAX_RECENT);
+    }
+}
+
+/// Replaces a leading `~` with the home directory.
+pub fn expand_home(path: &str) -> PathBuf {
+    let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"));
+    match (path.strip_prefix('~'), home) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') => {
+            PathBuf::from(format!("{}{}", home.to_string_lossy(), rest))
+        }
+        _ => PathBuf::from(path),
+    }
+}
+
+/// Completes the last component of `input` to the local directories it is a prefix
+/// of, as far as they agree. A single match gets a trailing `/` so the next Tab
+/// descends into it.
+pub fn complete(input: &str) -> String {
+    let (dir, prefix) = match input.rfind('/') {
+        Some(i) => (&input[..=i], &input[i + 1..]),
+        None => ("", input),
+    };
+    let search_dir = if dir.is_empty() {
+        PathBuf::from(".")
+    } else {
+        expand_home(dir)
+    };
+
+    let mut matches: Vec<String> = match fs::read_dir(&search_dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            // Hidden directories only when asked for
+            .filter(|name| {
+                name.starts_with(prefix) && (prefix.starts_with('.') || !name.starts_with('.'))
+            })
+            .collect(),
+        Err(_) => return input.to_string(),
+    };
+    matches.sort();
+
+    match matches.as_slice() {
+        [] => input.to_string(),
+        [only] => format!("{}{}/", dir, only),
+        [first, rest @ ..] => {
+            let common = rest.iter().fold(first.as_str(), |common, name| {
+                let len = common
+                    .char_indices()
+                    .zip(name.chars())
+                    .take_while(|((_, a), b)| a == b)
+                    .last()
+                    .map(|((i, c), _)| i + c.len_utf8())
+                    .unwrap_or(0);
+                &common[..len]
+            });
+            format!("{}{}", dir, common)
+        }
+    }
+}
+
+/// Returns the directory shown when the download prompt opens: the configured one,
+/// else the last one used, else the working directory.
+pub fn initial(configured: Option<&str>, recent: &RecentDirs) -> String {
+    match configured.or(recent.recent.first().map(String::as_str)) {
+        Some(dir) => dir.to_string(),
+        None => std::env::current_dir()
+            .ok()
+            .as_deref()
+            .and_then(Path::to_str)
+            .map(String::from)
+            .unwrap_or_else(|| ".".to_string()),
+    }
+}
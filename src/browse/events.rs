@@ -1,8 +1,17 @@
+use std::path::Path;
+use std::time::Instant;
+
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use reqwest::blocking::Client;
 
-use super::app::{file_actions_for, AppState, BrowserApp, ModalState, PendingAction};
-use crate::put;
+use super::actions::{self, Action, PaletteTarget};
+use super::app::{
+    file_actions_for, AppState, BrowserApp, FileFilter, FilterForm, FolderPicker, ModalState,
+    PendingAction, FILTER_TYPES,
+};
+use super::{download_dirs, snapshot};
+use crate::put::files::ExistingFile;
+use crate::put::Client;
+use crate::{config, put};
 
 pub fn handle_key(app: &mut BrowserApp, key: KeyEvent, client: &Client, api_token: &String) {
     if key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('c') {
@@ -13,18 +22,66 @@ pub fn handle_key(app: &mut BrowserApp, key: KeyEvent, client: &Client, api_toke
     match &app.modal {
         ModalState::Loading => {}
 
+        ModalState::TimedOut { .. } => match key.code {
+            KeyCode::Enter | KeyCode::Char('r') => {
+                if let ModalState::TimedOut { retry, .. } =
+                    std::mem::replace(&mut app.modal, ModalState::Loading)
+                {
+                    app.spinner_label = "Retrying...".to_string();
+                    app.pending_action = retry;
+                }
+            }
+            _ => app.modal = ModalState::None,
+        },
+
         ModalState::Error(_) | ModalState::Success(_) => {
-            app.modal = ModalState::None;
+            // Without an account there is nothing to browse, so go back to the setup screen
+            app.modal = if api_token.is_empty() {
+                ModalState::Welcome
+            } else {
+                ModalState::None
+            };
         }
 
-        ModalState::ConfirmDelete { file_id, .. } => {
-            let file_id = *file_id;
+        ModalState::Welcome => match key.code {
+            KeyCode::Enter => {
+                app.pending_action = PendingAction::LoginStart;
+                app.spinner_label = "Requesting login code...".to_string();
+                app.modal = ModalState::Loading;
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.app_state = AppState::Quitting;
+            }
+            _ => {}
+        },
+
+        ModalState::SessionExpired => match key.code {
+            KeyCode::Char('l') | KeyCode::Char('L') => {
+                app.pending_action = PendingAction::LoginStart;
+                app.spinner_label = "Requesting login code...".to_string();
+                app.modal = ModalState::Loading;
+            }
+            KeyCode::Esc => {
+                app.modal = ModalState::None;
+            }
+            _ => {}
+        },
+
+        ModalState::Login { .. } => {
+            if key.code == KeyCode::Esc {
+                app.modal = if api_token.is_empty() {
+                    ModalState::Welcome
+                } else {
+                    ModalState::None
+                };
+            }
+        }
+
+        ModalState::ConfirmDelete { file_ids, .. } => {
+            let file_ids = file_ids.clone();
             match key.code {
                 KeyCode::Char('y') | KeyCode::Char('Y') => {
-                    app.save_position_for_reload();
-                    app.pending_action = PendingAction::Delete { file_id };
-                    app.spinner_label = "Deleting...".to_string();
-                    app.modal = ModalState::Loading;
+                    app.start_delete(file_ids);
                 }
                 KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                     app.modal = ModalState::None;
@@ -33,6 +90,20 @@ pub fn handle_key(app: &mut BrowserApp, key: KeyEvent, client: &Client, api_toke
             }
         }
 
+        ModalState::ConfirmPruneTrash { file_ids, .. } => match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                app.pending_action = PendingAction::PruneTrash {
+                    file_ids: file_ids.clone(),
+                };
+                app.spinner_label = "Emptying trash...".to_string();
+                app.modal = ModalState::Loading;
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                app.modal = ModalState::None;
+            }
+            _ => {}
+        },
+
         ModalState::FileActions {
             file_id,
             file_name,
@@ -45,7 +116,12 @@ pub fn handle_key(app: &mut BrowserApp, key: KeyEvent, client: &Client, api_toke
             let file_name = file_name.clone();
             let file_type = file_type.clone();
             let in_search = app.is_search_results;
-            let actions = file_actions_for(&file_type, in_search);
+            let actions = file_actions_for(
+                &file_type,
+                in_search,
+                app.read_only,
+                !app.open_with.is_empty(),
+            );
             let n = actions.len();
 
             match key.code {
@@ -85,29 +161,543 @@ pub fn handle_key(app: &mut BrowserApp, key: KeyEvent, client: &Client, api_toke
             }
         }
 
-        ModalState::SearchInput { query } => {
+        ModalState::DownloadTo {
+            file_id,
+            path,
+            recent_index,
+        } => {
+            let file_id = *file_id;
+            let path = path.clone();
+            let recent_index = *recent_index;
+            match key.code {
+                KeyCode::Esc => {
+                    app.modal = ModalState::None;
+                }
+                KeyCode::Enter if !path.trim().is_empty() => {
+                    app.modal = ModalState::None;
+                    queue_download(app, file_id, path.trim().to_string());
+                }
+                KeyCode::Tab => {
+                    app.modal = ModalState::DownloadTo {
+                        file_id,
+                        path: download_dirs::complete(&path),
+                        recent_index: None,
+                    };
+                }
+                KeyCode::Up | KeyCode::Down => {
+                    let recent = &app.recent_dirs.recent;
+                    let index = match (key.code, recent_index) {
+                        (KeyCode::Up, None) if !recent.is_empty() => Some(0),
+                        (KeyCode::Up, Some(i)) => Some((i + 1).min(recent.len() - 1)),
+                        (KeyCode::Down, Some(0)) => None,
+                        (KeyCode::Down, Some(i)) => Some(i - 1),
+                        (_, current) => current,
+                    };
+                    if index == recent_index {
+                        return;
+                    }
+                    let path = match index {
+                        Some(i) => recent[i].clone(),
+                        None => {
+                            download_dirs::initial(app.download_dir.as_deref(), &app.recent_dirs)
+                        }
+                    };
+                    app.modal = ModalState::DownloadTo {
+                        file_id,
+                        path,
+                        recent_index: index,
+                    };
+                }
+                KeyCode::Backspace => {
+                    let mut p = path;
+                    p.pop();
+                    app.modal = ModalState::DownloadTo {
+                        file_id,
+                        path: p,
+                        recent_index: None,
+                    };
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.modal = ModalState::DownloadTo {
+                        file_id,
+                        path: path + &c.to_string(),
+                        recent_index: None,
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        ModalState::DownloadConflict { file_id, path, .. } => {
+            let existing = match key.code {
+                KeyCode::Char('o') | KeyCode::Char('O') => Some(ExistingFile::Overwrite),
+                KeyCode::Char('r') | KeyCode::Char('R') => Some(ExistingFile::Resume),
+                KeyCode::Char('n') | KeyCode::Char('N') => Some(ExistingFile::Rename),
+                KeyCode::Char('s') | KeyCode::Char('S') | KeyCode::Esc => None,
+                _ => return,
+            };
+            if let Some(existing) = existing {
+                app.pending_action = PendingAction::Download {
+                    file_id: *file_id,
+                    path: path.clone(),
+                    existing,
+                };
+            }
+            app.modal = ModalState::None;
+        }
+
+        ModalState::PickProfile { selected } => {
+            let selected = *selected;
+            let n = app.profiles.len();
+
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    app.modal = ModalState::PickProfile {
+                        selected: if selected == 0 { n - 1 } else { selected - 1 },
+                    };
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    app.modal = ModalState::PickProfile {
+                        selected: (selected + 1) % n,
+                    };
+                }
+                KeyCode::Enter => {
+                    app.modal = ModalState::None;
+                    app.pending_action = PendingAction::OpenTab {
+                        profile: app.profiles[selected].clone(),
+                    };
+                }
+                KeyCode::Esc => {
+                    app.modal = ModalState::None;
+                }
+                _ => {}
+            }
+        }
+
+        ModalState::OpenWith {
+            file_id,
+            file_name,
+            selected,
+        } => {
+            let file_id = *file_id;
+            let selected = *selected;
+            let file_name = file_name.clone();
+            let n = app.open_with.len();
+
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    app.modal = ModalState::OpenWith {
+                        file_id,
+                        file_name,
+                        selected: if selected == 0 { n - 1 } else { selected - 1 },
+                    };
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    app.modal = ModalState::OpenWith {
+                        file_id,
+                        file_name,
+                        selected: (selected + 1) % n,
+                    };
+                }
+                KeyCode::Enter => {
+                    app.modal = ModalState::None;
+                    app.pending_action = PendingAction::OpenWith {
+                        file_id,
+                        index: selected,
+                    };
+                }
+                KeyCode::Esc => {
+                    app.modal = ModalState::None;
+                }
+                _ => {}
+            }
+        }
+
+        ModalState::CastDevices {
+            file_id,
+            file_name,
+            devices,
+            selected,
+        } => {
+            let file_id = *file_id;
+            let selected = *selected;
+            let file_name = file_name.clone();
+            let devices = devices.clone();
+            let n = devices.len();
+
+            match key.code {
+                KeyCode::Up | KeyCode::Char('k') => {
+                    app.modal = ModalState::CastDevices {
+                        file_id,
+                        file_name,
+                        devices,
+                        selected: if selected == 0 { n - 1 } else { selected - 1 },
+                    };
+                }
+                KeyCode::Down | KeyCode::Char('j') => {
+                    app.modal = ModalState::CastDevices {
+                        file_id,
+                        file_name,
+                        devices,
+                        selected: (selected + 1) % n,
+                    };
+                }
+                KeyCode::Enter => {
+                    let device = devices[selected].clone();
+                    app.spinner_label = format!("Casting to {}...", device.name);
+                    app.pending_action = PendingAction::Cast {
+                        file_id,
+                        file_name,
+                        device,
+                    };
+                    app.modal = ModalState::Loading;
+                }
+                KeyCode::Esc => {
+                    app.modal = ModalState::None;
+                }
+                _ => {}
+            }
+        }
+
+        ModalState::Casting => {
+            let Some(session) = app.cast_session.as_mut() else {
+                app.modal = ModalState::None;
+                return;
+            };
+            let address = session.device.address.clone();
+            let command: Option<Vec<&str>> = match key.code {
+                KeyCode::Char(' ') => {
+                    session.paused = !session.paused;
+                    Some(vec![if session.paused { "pause" } else { "play" }])
+                }
+                KeyCode::Left | KeyCode::Char('h') => Some(vec!["rewind", "30"]),
+                KeyCode::Right | KeyCode::Char('l') => Some(vec!["ffwd", "30"]),
+                KeyCode::Char('s') => {
+                    app.cast_session = None;
+                    Some(vec!["stop"])
+                }
+                KeyCode::Esc => {
+                    // Hide the overlay; playback keeps going and `c` brings it back
+                    app.modal = ModalState::None;
+                    None
+                }
+                _ => None,
+            };
+            if let Some(command) = command {
+                app.pending_action = PendingAction::CastControl {
+                    address,
+                    command: command.into_iter().map(String::from).collect(),
+                };
+                app.spinner_label = "Sending to cast device...".to_string();
+                app.modal = ModalState::Loading;
+            }
+        }
+
+        ModalState::SearchInput {
+            query,
+            history_index,
+        } => {
             let query = query.clone();
+            let history_index = *history_index;
             match key.code {
                 KeyCode::Esc => {
                     app.modal = ModalState::None;
                 }
                 KeyCode::Enter => {
                     if !query.is_empty() {
-                        app.pending_action = PendingAction::Search { query };
-                        app.spinner_label = "Searching...".to_string();
-                        app.modal = ModalState::Loading;
+                        start_search(app, query);
                     } else {
                         app.modal = ModalState::None;
                     }
                 }
+                KeyCode::Up | KeyCode::Down => {
+                    let entries = app.search_history.entries();
+                    let index = match (key.code, history_index) {
+                        (KeyCode::Up, None) if !entries.is_empty() => Some(0),
+                        (KeyCode::Up, Some(i)) => Some((i + 1).min(entries.len() - 1)),
+                        (KeyCode::Down, Some(0)) => None,
+                        (KeyCode::Down, Some(i)) => Some(i - 1),
+                        (_, current) => current,
+                    };
+                    if index == history_index {
+                        return;
+                    }
+                    let query = match index {
+                        Some(i) => entries[i].clone(),
+                        None => String::new(),
+                    };
+                    app.modal = ModalState::SearchInput {
+                        query,
+                        history_index: index,
+                    };
+                }
+                KeyCode::Char('p')
+                    if key.modifiers.contains(KeyModifiers::CONTROL) && !query.is_empty() =>
+                {
+                    app.search_history.toggle_pin(&query);
+                    app.search_history.save().ok();
+                }
+                KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::ALT) => {
+                    // Alt+1 to Alt+9 run a pinned search straight away
+                    let pinned = c
+                        .to_digit(10)
+                        .and_then(|n| (n as usize).checked_sub(1))
+                        .and_then(|i| app.search_history.pinned.get(i))
+                        .cloned();
+                    if let Some(query) = pinned {
+                        start_search(app, query);
+                    }
+                }
                 KeyCode::Backspace => {
                     let mut q = query;
                     q.pop();
-                    app.modal = ModalState::SearchInput { query: q };
+                    app.modal = ModalState::SearchInput {
+                        query: q,
+                        history_index: None,
+                    };
                 }
                 KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
                     app.modal = ModalState::SearchInput {
                         query: query + &c.to_string(),
+                        history_index: None,
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        ModalState::Filter(_) => {
+            let ModalState::Filter(mut form) = std::mem::replace(&mut app.modal, ModalState::None)
+            else {
+                return;
+            };
+            match key.code {
+                KeyCode::Esc => return,
+                KeyCode::Enter => {
+                    match form.to_filter() {
+                        Ok(filter) => app.set_filter(filter),
+                        Err(e) => app.modal = ModalState::Error(e),
+                    }
+                    return;
+                }
+                KeyCode::Delete => {
+                    app.set_filter(FileFilter::default());
+                    return;
+                }
+                KeyCode::Up | KeyCode::BackTab => {
+                    form.field = (form.field + FilterForm::FIELDS - 1) % FilterForm::FIELDS;
+                }
+                KeyCode::Down | KeyCode::Tab => {
+                    form.field = (form.field + 1) % FilterForm::FIELDS;
+                }
+                KeyCode::Left if form.field == 0 => {
+                    form.type_index =
+                        (form.type_index + FILTER_TYPES.len()) % (FILTER_TYPES.len() + 1);
+                }
+                KeyCode::Right if form.field == 0 => {
+                    form.type_index = (form.type_index + 1) % (FILTER_TYPES.len() + 1);
+                }
+                KeyCode::Backspace => {
+                    if let Some(text) = form.focused_text() {
+                        text.pop();
+                    }
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    if let Some(text) = form.focused_text() {
+                        text.push(c);
+                    }
+                }
+                _ => {}
+            }
+            app.modal = ModalState::Filter(form);
+        }
+
+        ModalState::FetchUrl { url } => {
+            let url = url.clone();
+            match key.code {
+                KeyCode::Esc => {
+                    app.modal = ModalState::None;
+                }
+                KeyCode::Enter => {
+                    if put::transfers::is_fetchable_url(&url) {
+                        app.pending_action = PendingAction::FetchUrl {
+                            url: url.trim().to_string(),
+                            parent_id: app.current_folder_id,
+                        };
+                        app.spinner_label = "Adding transfer...".to_string();
+                        app.modal = ModalState::Loading;
+                    } else {
+                        app.modal = ModalState::Error(
+                            "Only http://, https:// and ftp:// URLs can be fetched.".to_string(),
+                        );
+                    }
+                }
+                KeyCode::Backspace => {
+                    let mut u = url;
+                    u.pop();
+                    app.modal = ModalState::FetchUrl { url: u };
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.modal = ModalState::FetchUrl {
+                        url: url + &c.to_string(),
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        ModalState::CopyTo(_) => {
+            let ModalState::CopyTo(mut picker) =
+                std::mem::replace(&mut app.modal, ModalState::None)
+            else {
+                return;
+            };
+            let n = picker.folders.len();
+            match key.code {
+                KeyCode::Esc => return,
+                KeyCode::Up | KeyCode::Char('k') if n > 0 => {
+                    picker.selected = if picker.selected == 0 {
+                        n - 1
+                    } else {
+                        picker.selected - 1
+                    };
+                }
+                KeyCode::Down | KeyCode::Char('j') if n > 0 => {
+                    picker.selected = (picker.selected + 1) % n;
+                }
+                KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') if n > 0 => {
+                    let folder = &picker.folders[picker.selected];
+                    picker.path.push((folder.id, folder.name.clone()));
+                    app.pending_action = PendingAction::PickFolder(picker);
+                    app.spinner_label = "Loading folders...".to_string();
+                    app.modal = ModalState::Loading;
+                    return;
+                }
+                KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h')
+                    if picker.path.len() > 1 =>
+                {
+                    picker.path.pop();
+                    app.pending_action = PendingAction::PickFolder(picker);
+                    app.spinner_label = "Loading folders...".to_string();
+                    app.modal = ModalState::Loading;
+                    return;
+                }
+                KeyCode::Char('c') => {
+                    let (parent_id, parent_name) = picker
+                        .path
+                        .last()
+                        .cloned()
+                        .unwrap_or((0, "My Files".to_string()));
+                    app.pending_action = PendingAction::Copy {
+                        file_id: picker.file_id,
+                        file_name: picker.file_name,
+                        parent_id,
+                        parent_name,
+                    };
+                    app.spinner_label = "Copying...".to_string();
+                    app.modal = ModalState::Loading;
+                    return;
+                }
+                _ => {}
+            }
+            app.modal = ModalState::CopyTo(picker);
+        }
+
+        ModalState::ShareLink { file_id, expires } => {
+            let file_id = *file_id;
+            let expires = expires.clone();
+            match key.code {
+                KeyCode::Esc => {
+                    app.modal = ModalState::None;
+                }
+                KeyCode::Enter => {
+                    // Left empty, the link works until it's revoked
+                    let expires_in = match expires.trim() {
+                        "" => Ok(None),
+                        text => config::parse_duration(text).map(Some),
+                    };
+                    match expires_in {
+                        Ok(expires_in) => {
+                            app.pending_action = PendingAction::Share {
+                                file_id,
+                                expires_in,
+                            };
+                            app.spinner_label = "Creating link...".to_string();
+                            app.modal = ModalState::Loading;
+                        }
+                        Err(e) => app.modal = ModalState::Error(e),
+                    }
+                }
+                KeyCode::Backspace => {
+                    let mut e = expires;
+                    e.pop();
+                    app.modal = ModalState::ShareLink {
+                        file_id,
+                        expires: e,
+                    };
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.modal = ModalState::ShareLink {
+                        file_id,
+                        expires: expires + &c.to_string(),
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        ModalState::SaveView { path } => {
+            let path = path.clone();
+            match key.code {
+                KeyCode::Esc => {
+                    app.modal = ModalState::None;
+                }
+                KeyCode::Enter if !path.trim().is_empty() => {
+                    let target = download_dirs::expand_home(path.trim());
+                    app.modal = match snapshot::save(app, &target) {
+                        Ok(_) => ModalState::Success(format!("Saved to {}", target.display())),
+                        Err(e) => ModalState::Error(format!("Saving listing failed: {}", e)),
+                    };
+                }
+                KeyCode::Tab => {
+                    app.modal = ModalState::SaveView {
+                        path: download_dirs::complete(&path),
+                    };
+                }
+                KeyCode::Backspace => {
+                    let mut p = path;
+                    p.pop();
+                    app.modal = ModalState::SaveView { path: p };
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.modal = ModalState::SaveView {
+                        path: path + &c.to_string(),
+                    };
+                }
+                _ => {}
+            }
+        }
+
+        ModalState::GlobSelect { pattern } => {
+            let pattern = pattern.clone();
+            match key.code {
+                KeyCode::Esc => {
+                    app.modal = ModalState::None;
+                }
+                KeyCode::Enter => {
+                    app.modal = ModalState::None;
+                    if !pattern.is_empty() && app.mark_matching(&pattern) == 0 {
+                        app.modal = ModalState::Error(format!("No files match {}", pattern));
+                    }
+                }
+                KeyCode::Backspace => {
+                    let mut p = pattern;
+                    p.pop();
+                    app.modal = ModalState::GlobSelect { pattern: p };
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.modal = ModalState::GlobSelect {
+                        pattern: pattern + &c.to_string(),
                     };
                 }
                 _ => {}
@@ -141,85 +731,168 @@ pub fn handle_key(app: &mut BrowserApp, key: KeyEvent, client: &Client, api_toke
             }
         }
 
-        ModalState::None => match key.code {
-            KeyCode::Char('q') => {
-                app.app_state = AppState::Quitting;
-            }
-            KeyCode::Esc => {
-                if app.breadcrumbs.len() > 1 {
-                    app.go_back();
-                    app.needs_reload = true;
-                } else {
-                    app.app_state = AppState::Quitting;
+        ModalState::Activity { scroll } => {
+            let last = app.journal.entries.len().saturating_sub(1);
+            let scroll = match key.code {
+                KeyCode::Up | KeyCode::Char('k') => scroll.saturating_sub(1),
+                KeyCode::Down | KeyCode::Char('j') => (scroll + 1).min(last),
+                KeyCode::Esc | KeyCode::Char('L') | KeyCode::Char('q') => {
+                    app.modal = ModalState::None;
+                    return;
                 }
-            }
-            KeyCode::Up | KeyCode::Char('k') => app.move_up(),
-            KeyCode::Down | KeyCode::Char('j') => app.move_down(),
-            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                app.move_page_up()
-            }
-            KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                app.move_page_down()
-            }
-            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                if let Some(file) = app.selected_file() {
-                    app.modal = ModalState::FileActions {
-                        file_id: file.id,
-                        file_name: file.name.clone(),
-                        file_type: file.file_type.clone(),
-                        selected: 0,
+                _ => return,
+            };
+            app.modal = ModalState::Activity { scroll };
+        }
+
+        ModalState::CommandPalette { query, selected } => {
+            let query = query.clone();
+            let selected = *selected;
+            let entries = actions::palette_entries(app, &query);
+            let n = entries.len();
+            match key.code {
+                KeyCode::Esc => {
+                    app.modal = ModalState::None;
+                }
+                KeyCode::Up if n > 0 => {
+                    app.modal = ModalState::CommandPalette {
+                        query,
+                        selected: if selected == 0 { n - 1 } else { selected - 1 },
                     };
                 }
-            }
-            KeyCode::Enter => {
-                if let Some(file) = app.selected_file() {
-                    let file_id = file.id;
-                    let file_name = file.name.clone();
-                    let file_type = file.file_type.clone();
-                    if file_type == "FOLDER" {
-                        app.enter_folder(file_id, file_name);
-                        app.needs_reload = true;
-                    } else {
-                        app.modal = ModalState::FileActions {
-                            file_id,
-                            file_name,
-                            file_type,
-                            selected: 0,
-                        };
+                KeyCode::Down if n > 0 => {
+                    app.modal = ModalState::CommandPalette {
+                        query,
+                        selected: (selected + 1) % n,
+                    };
+                }
+                KeyCode::Enter => {
+                    app.modal = ModalState::None;
+                    match entries.get(selected).map(|entry| entry.target) {
+                        Some(PaletteTarget::Action(action)) => actions::perform(app, action),
+                        Some(PaletteTarget::FileAction(label)) => {
+                            if let Some(file) = app.selected_file() {
+                                let file_id = file.id;
+                                let file_type = file.file_type.clone();
+                                execute_file_action(
+                                    app, label, file_id, &file_type, api_token, client,
+                                );
+                            }
+                        }
+                        None => {}
                     }
                 }
+                KeyCode::Backspace => {
+                    let mut q = query;
+                    q.pop();
+                    app.modal = ModalState::CommandPalette {
+                        query: q,
+                        selected: 0,
+                    };
+                }
+                KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.modal = ModalState::CommandPalette {
+                        query: query + &c.to_string(),
+                        selected: 0,
+                    };
+                }
+                _ => {}
             }
-            KeyCode::Left | KeyCode::Backspace => {
-                app.go_back();
-                app.needs_reload = true;
-            }
-            KeyCode::Char('/') => {
-                app.modal = ModalState::Find {
-                    query: String::new(),
-                };
-            }
-            KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                app.modal = ModalState::SearchInput {
-                    query: String::new(),
-                };
-            }
-            KeyCode::Char('n') => {
-                app.find_next();
+        }
+
+        ModalState::None => handle_normal_key(app, key, client, api_token),
+    }
+}
+
+/// Handles a key in the file list, including vim-style counts (`5j`) and `gg`.
+fn handle_normal_key(app: &mut BrowserApp, key: KeyEvent, client: &Client, api_token: &String) {
+    let plain = !key
+        .modifiers
+        .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT);
+    let count = app.pending_count.take();
+    let pending_g = std::mem::take(&mut app.pending_g);
+
+    match key.code {
+        KeyCode::Char(c @ '0'..='9') if plain && (c != '0' || count.is_some()) => {
+            let digit = c.to_digit(10).unwrap_or(0) as usize;
+            let count = count.unwrap_or(0).saturating_mul(10).saturating_add(digit);
+            app.pending_count = Some(count.min(100_000));
+            return;
+        }
+        KeyCode::Char('g') if plain => {
+            if pending_g {
+                // Like vim, `5gg` goes to the 5th entry
+                app.select_index(count.map_or(0, |n| n.saturating_sub(1)));
+            } else {
+                app.pending_g = true;
+                app.pending_count = count;
             }
-            KeyCode::Char('s') => app.cycle_sort_field(),
-            KeyCode::Char('r') => app.toggle_sort_direction(),
-            KeyCode::Char('x') => {
-                if let Some(file) = app.selected_file() {
-                    let file_id = file.id;
-                    let file_name = file.name.clone();
-                    app.modal = ModalState::ConfirmDelete { file_id, file_name };
-                }
+            return;
+        }
+        // Only drops the half-typed motion
+        KeyCode::Esc if count.is_some() || pending_g => return,
+        _ => {}
+    }
+
+    let Some(action) = actions::for_key(app, key) else {
+        return;
+    };
+    match (action, count) {
+        (Action::GoToBottom, Some(n)) => app.select_index(n.saturating_sub(1)),
+        (
+            Action::MoveUp
+            | Action::MoveDown
+            | Action::MoveLeft
+            | Action::MoveRight
+            | Action::PageUp
+            | Action::PageDown,
+            Some(n),
+        ) => {
+            for _ in 0..n {
+                actions::perform(app, action);
             }
-            _ => {}
-        },
+        }
+        (Action::Open, _) if run_default_action(app, client, api_token) => {}
+        _ => actions::perform(app, action),
     }
 }
 
+/// Runs the configured default action for the selected file's type, if it has
+/// one that applies. Returns false if the file should be opened as usual.
+fn run_default_action(app: &mut BrowserApp, client: &Client, api_token: &String) -> bool {
+    let Some(file) = app.selected_file() else {
+        return false;
+    };
+    let (file_id, file_type) = (file.id, file.file_type.clone());
+    let Some(wanted) = app.default_actions.get(&file_type) else {
+        return false;
+    };
+    let actions = file_actions_for(
+        &file_type,
+        app.is_search_results,
+        app.read_only,
+        !app.open_with.is_empty(),
+    );
+    // Unknown labels and actions hidden here (e.g. Extract when read-only) fall back to the menu
+    let Some(action) = actions
+        .iter()
+        .find(|a| a.label.eq_ignore_ascii_case(wanted))
+    else {
+        return false;
+    };
+    execute_file_action(app, action.label, file_id, &file_type, api_token, client);
+    true
+}
+
+/// Runs a Put.io search and remembers the query for next time.
+fn start_search(app: &mut BrowserApp, query: String) {
+    app.search_history.record(&query);
+    app.search_history.save().ok();
+    app.pending_action = PendingAction::Search { query };
+    app.spinner_label = "Searching...".to_string();
+    app.modal = ModalState::Loading;
+}
+
 fn execute_file_action(
     app: &mut BrowserApp,
     action: &str,
@@ -231,17 +904,90 @@ fn execute_file_action(
     match action {
         "Copy URL" => match put::files::url(client, api_token, file_id) {
             Ok(r) => copy_to_clipboard(app, &r.url, "URL copied!"),
-            Err(e) => app.modal = ModalState::Error(format!("Failed to get URL: {}", e)),
+            Err(e) => app.show_api_error("Failed to get URL", &e),
         },
         "Copy Stream URL" => {
-            let url = format!(
-                "https://api.put.io/v2/files/{}/stream?oauth_token={}",
+            let url = client.api_url(&format!(
+                "/files/{}/stream?oauth_token={}",
                 file_id, api_token
-            );
+            ));
             copy_to_clipboard(app, &url, "Stream URL copied!");
         }
-        "Download" => {
-            app.pending_action = PendingAction::Download { file_id };
+        "Cast to device" => {
+            let file_name = app
+                .files
+                .iter()
+                .find(|f| f.id == file_id)
+                .map(|f| f.name.clone())
+                .unwrap_or_default();
+            app.pending_action = PendingAction::CastDiscover { file_id, file_name };
+            app.spinner_label = "Looking for cast devices...".to_string();
+            app.modal = ModalState::Loading;
+        }
+        "Toggle watched" => {
+            // Applies to all marked videos if the selected one is among them
+            let watched = !app.files.iter().any(|f| f.id == file_id && f.is_watched);
+            let file_ids = if app.marked.contains(&file_id) {
+                app.files
+                    .iter()
+                    .filter(|f| app.marked.contains(&f.id) && f.file_type == "VIDEO")
+                    .map(|f| f.id)
+                    .collect()
+            } else {
+                vec![file_id]
+            };
+            app.save_position_for_reload();
+            app.pending_action = PendingAction::SetWatched { file_ids, watched };
+            app.spinner_label = "Updating watch status...".to_string();
+            app.modal = ModalState::Loading;
+        }
+        "Download" | "Download as zip" => {
+            let path = download_dirs::initial(app.download_dir.as_deref(), &app.recent_dirs);
+            app.modal = ModalState::DownloadTo {
+                file_id,
+                path,
+                recent_index: None,
+            };
+        }
+        "Play" => {
+            app.pending_action = PendingAction::Play { file_id };
+        }
+        "Extract" => {
+            app.pending_action = PendingAction::Extract { file_id };
+            app.spinner_label = "Starting extraction...".to_string();
+            app.modal = ModalState::Loading;
+        }
+        "Copy to..." => {
+            let file_name = app
+                .files
+                .iter()
+                .find(|f| f.id == file_id)
+                .map(|f| f.name.clone())
+                .unwrap_or_default();
+            // Start in the folder being browsed; search results start at the root
+            let path = if app.is_search_results {
+                vec![(0, "My Files".to_string())]
+            } else {
+                app.breadcrumbs
+                    .iter()
+                    .map(|b| (b.id, b.name.clone()))
+                    .collect()
+            };
+            app.pending_action = PendingAction::PickFolder(FolderPicker {
+                file_id,
+                file_name,
+                path,
+                folders: vec![],
+                selected: 0,
+            });
+            app.spinner_label = "Loading folders...".to_string();
+            app.modal = ModalState::Loading;
+        }
+        "Share link" => {
+            app.modal = ModalState::ShareLink {
+                file_id,
+                expires: String::new(),
+            };
         }
         "Open in browser" => {
             open_in_browser(app, &format!("https://app.put.io/files/{}", file_id));
@@ -264,12 +1010,31 @@ fn execute_file_action(
             app.spinner_label = "Copying path...".to_string();
             app.modal = ModalState::Loading;
         }
-        "Download as zip" => {
-            app.pending_action = PendingAction::Download { file_id };
+        "Open with..." => {
+            let file_name = app
+                .files
+                .iter()
+                .find(|f| f.id == file_id)
+                .map(|f| f.name.clone())
+                .unwrap_or_default();
+            app.modal = ModalState::OpenWith {
+                file_id,
+                file_name,
+                selected: 0,
+            };
+        }
+        "Play folder" => {
+            app.pending_action = PendingAction::PlayFolder { folder_id: file_id };
         }
         "Copy folder ID" => {
             copy_to_clipboard(app, &file_id.to_string(), "Folder ID copied!");
         }
+        "Delete" => {
+            if let Some(file) = app.files.iter().find(|f| f.id == file_id) {
+                let label = format!("\"{}\"", file.name);
+                app.request_delete(vec![file_id], label);
+            }
+        }
         "Go to folder" => {
             let parent_id = app
                 .files
@@ -284,42 +1049,6 @@ fn execute_file_action(
     let _ = file_type;
 }
 
-pub(super) fn build_path_parts(
-    client: &Client,
-    api_token: &String,
-    mut parent_id: i64,
-) -> Result<Vec<String>, String> {
-    if parent_id < 0 {
-        return Err("Path lookup failed: invalid parent id.".to_string());
-    }
-
-    let mut parts = Vec::new();
-    let mut depth = 0;
-    while parent_id != 0 {
-        depth += 1;
-        if depth > 256 {
-            return Err("Path lookup failed: path too deep.".to_string());
-        }
-
-        let response = put::files::list(client, api_token, parent_id)
-            .map_err(|e| format!("Path lookup failed: {}", e))?;
-        let folder = response.parent;
-
-        if folder.name.is_empty() {
-            return Err("Path lookup failed: missing folder name.".to_string());
-        }
-        if folder.parent_id == parent_id {
-            return Err("Path lookup failed: parent loop detected.".to_string());
-        }
-
-        parts.push(folder.name);
-        parent_id = folder.parent_id;
-    }
-
-    parts.reverse();
-    Ok(parts)
-}
-
 fn open_in_browser(app: &mut BrowserApp, url: &str) {
     let mut command = if cfg!(target_os = "macos") {
         let mut cmd = std::process::Command::new("open");
@@ -344,12 +1073,57 @@ fn open_in_browser(app: &mut BrowserApp, url: &str) {
     }
 }
 
-pub(super) fn copy_to_clipboard(app: &mut BrowserApp, text: &str, success_msg: &str) {
+/// Downloads a file to `path`, first asking what to do if it is in the current
+/// listing and already exists there.
+pub(super) fn queue_download(app: &mut BrowserApp, file_id: i64, path: String) {
+    // Folders are downloaded as a zip
+    let file_name = app.files.iter().find(|f| f.id == file_id).map(|f| {
+        if f.file_type == "FOLDER" {
+            format!("{}.zip", f.name)
+        } else {
+            f.name.clone()
+        }
+    });
+    let dir = download_dirs::expand_home(&path);
+    let target = file_name
+        .as_deref()
+        .map(|name| put::files::output_path(&dir.to_string_lossy(), name, false));
+    match (file_name, target) {
+        (Some(file_name), Some(target)) if Path::new(&target).exists() => {
+            app.modal = ModalState::DownloadConflict {
+                file_id,
+                path,
+                file_name,
+            };
+        }
+        _ => {
+            app.pending_action = PendingAction::Download {
+                file_id,
+                path,
+                existing: ExistingFile::Resume,
+            };
+        }
+    }
+}
+
+pub(super) fn set_clipboard(text: &str) -> Result<(), String> {
     match arboard::Clipboard::new() {
         Ok(mut cb) => match cb.set_text(text) {
-            Ok(_) => app.modal = ModalState::Success(success_msg.to_string()),
-            Err(e) => app.modal = ModalState::Error(format!("Clipboard error: {}", e)),
+            Ok(_) => Ok(()),
+            Err(e) => Err(format!("Clipboard error: {}", e)),
         },
-        Err(e) => app.modal = ModalState::Error(format!("Clipboard unavailable: {}", e)),
+        Err(e) => Err(format!("Clipboard unavailable: {}", e)),
     }
 }
+
+pub(super) fn copy_to_clipboard(app: &mut BrowserApp, text: &str, success_msg: &str) {
+    let started = Instant::now();
+    let outcome = set_clipboard(text);
+    app.modal = match &outcome {
+        Ok(_) => ModalState::Success(success_msg.to_string()),
+        Err(e) => ModalState::Error(e.clone()),
+    };
+    // The copied text may contain the API token, so only the message is kept
+    app.journal
+        .record(success_msg.trim_end_matches('!'), started, outcome);
+}
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use super::status::OperationGuard;
+use crate::put::{self, cancel::CancelToken, Client};
+
+/// Maximum number of folder paths looked up at the same time
+const MAX_CONCURRENT_LOOKUPS: usize = 4;
+
+/// Folders seen during the session, so paths only need to be looked up once.
+/// Clones share the same cache, which lets background threads fill it.
+#[derive(Clone, Default)]
+pub struct FolderCache {
+    folders: Arc<Mutex<HashMap<i64, (String, i64)>>>,
+}
+
+impl FolderCache {
+    pub fn insert(&self, folder_id: i64, name: &str, parent_id: i64) {
+        if !name.is_empty() {
+            self.folders
+                .lock()
+                .unwrap()
+                .insert(folder_id, (name.to_string(), parent_id));
+        }
+    }
+
+    fn get(&self, folder_id: i64) -> Option<(String, i64)> {
+        self.folders.lock().unwrap().get(&folder_id).cloned()
+    }
+
+    /// Returns the path of `folder_id` such as `/Movies/Action` if all of its
+    /// ancestors are cached, without making any requests.
+    pub fn cached_path(&self, mut folder_id: i64) -> Option<String> {
+        let folders = self.folders.lock().unwrap();
+        let mut parts = Vec::new();
+        while folder_id > 0 && parts.len() <= 256 {
+            let (name, parent_id) = folders.get(&folder_id)?;
+            parts.push(name.as_str());
+            folder_id = *parent_id;
+        }
+        parts.reverse();
+        Some(format!("/{}", parts.join("/")))
+    }
+
+    /// Returns the names of the folders from the root down to `folder_id`, listing
+    /// only the ancestors that are not cached yet. Stops before the next listing
+    /// once `cancel` is cancelled.
+    pub fn path_parts(
+        &self,
+        client: &Client,
+        api_token: &String,
+        mut folder_id: i64,
+        cancel: &CancelToken,
+    ) -> Result<Vec<String>, String> {
+        if folder_id < 0 {
+            return Err("Path lookup failed: invalid parent id.".to_string());
+        }
+
+        let mut parts = Vec::new();
+        let mut depth = 0;
+        while folder_id != 0 {
+            depth += 1;
+            if depth > 256 {
+                return Err("Path lookup failed: path too deep.".to_string());
+            }
+
+            let (name, parent_id) = match self.get(folder_id) {
+                Some(cached) => cached,
+                None if cancel.is_cancelled() => return Err("Path lookup cancelled.".to_string()),
+                None => {
+                    let response = put::files::list(client, api_token, folder_id)
+                        .map_err(|e| format!("Path lookup failed: {}", e))?;
+                    let folder = response.parent;
+                    if folder.name.is_empty() {
+                        return Err("Path lookup failed: missing folder name.".to_string());
+                    }
+                    self.insert(folder_id, &folder.name, folder.parent_id);
+                    (folder.name, folder.parent_id)
+                }
+            };
+            if parent_id == folder_id {
+                return Err("Path lookup failed: parent loop detected.".to_string());
+            }
+
+            parts.push(name);
+            folder_id = parent_id;
+        }
+
+        parts.reverse();
+        Ok(parts)
+    }
+
+    /// Looks up the paths of several folders in parallel so later lookups are
+    /// answered from the cache, reporting how far it got on `operation`.
+    /// Failures are left for the next lookup to report.
+    pub fn prefetch(
+        &self,
+        client: &Client,
+        api_token: &str,
+        folder_ids: Vec<i64>,
+        operation: &OperationGuard,
+    ) {
+        let total = folder_ids.len().max(1);
+        let done = Arc::new(Mutex::new(0));
+        let queue = Arc::new(Mutex::new(folder_ids));
+        let workers: Vec<thread::JoinHandle<()>> = (0..MAX_CONCURRENT_LOOKUPS)
+            .map(|_| {
+                let cache = self.clone();
+                let client = client.clone();
+                let api_token = api_token.to_string();
+                let queue = Arc::clone(&queue);
+                let done = Arc::clone(&done);
+                let cancel = CancelToken::default();
+                thread::spawn(move || loop {
+                    let next = queue.lock().unwrap().pop();
+                    let Some(folder_id) = next else {
+                        break;
+                    };
+                    cache
+                        .path_parts(&client, &api_token, folder_id, &cancel)
+                        .ok();
+                    *done.lock().unwrap() += 1;
+                })
+            })
+            .collect();
+        while workers.iter().any(|worker| !worker.is_finished()) {
+            operation.set_progress((*done.lock().unwrap() * 100 / total) as u8);
+            thread::sleep(Duration::from_millis(100));
+        }
+        for worker in workers {
+            worker.join().ok();
+        }
+    }
+}
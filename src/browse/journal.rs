@@ -0,0 +1,43 @@
+use std::time::{Duration, Instant};
+
+/// One operation performed in the browser.
+pub struct JournalEntry {
+    /// When it started, relative to the start of the session
+    pub at: Duration,
+    pub operation: String,
+    /// The error message if it failed
+    pub outcome: Result<(), String>,
+    pub took: Duration,
+}
+
+/// Everything done during a browser session, for the activity pane.
+pub struct Journal {
+    session_start: Instant,
+    pub entries: Vec<JournalEntry>,
+}
+
+impl Default for Journal {
+    fn default() -> Self {
+        Journal {
+            session_start: Instant::now(),
+            entries: vec![],
+        }
+    }
+}
+
+impl Journal {
+    /// Records an operation that started at `started` and has just finished.
+    pub fn record(
+        &mut self,
+        operation: impl Into<String>,
+        started: Instant,
+        outcome: Result<(), String>,
+    ) {
+        self.entries.push(JournalEntry {
+            at: started.saturating_duration_since(self.session_start),
+            operation: operation.into(),
+            outcome,
+            took: started.elapsed(),
+        });
+    }
+}
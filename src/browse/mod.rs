@@ -1,23 +1,119 @@
+mod actions;
 mod app;
+mod cast;
+pub mod checklist;
+pub mod download_dirs;
 mod events;
+mod folders;
+mod journal;
+mod refresh;
+pub mod remote;
+pub mod searches;
+mod snapshot;
+mod status;
+pub mod theme;
+mod thumbnails;
 mod ui;
 
-use std::io;
+use std::io::{self, Write};
 use std::sync::mpsc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crossterm::{
-    event::{self, Event},
+    event::{self, Event, KeyCode, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use reqwest::blocking::Client;
 
-use crate::put;
-use app::{AppState, BrowserApp, ModalState, PendingAction};
+use crate::config::{self, ConfigFile};
+use crate::put::cancel::CancelToken;
+use crate::put::files::ExistingFile;
+use crate::put::Client;
+use crate::{mpv, natsort, playlist, put};
+use app::{AppState, BrowserApp, CastSession, ModalState, PendingAction};
 
-pub fn run(client: &Client, api_token: &String) -> io::Result<()> {
+/// How often a pending login code is checked
+const LOGIN_POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// A tab that isn't in front. The main loop keeps the front tab's state in its
+/// own variables and swaps it with a slot here when tabs are switched, so the
+/// front tab's slot holds whatever was swapped out last.
+struct Tab {
+    /// `None` for the default account
+    profile: Option<String>,
+    client: Client,
+    api_token: String,
+    app: BrowserApp,
+}
+
+impl Tab {
+    fn exchange(
+        &mut self,
+        profile: &mut Option<String>,
+        client: &mut Client,
+        api_token: &mut String,
+        app: &mut BrowserApp,
+    ) {
+        std::mem::swap(&mut self.profile, profile);
+        std::mem::swap(&mut self.client, client);
+        std::mem::swap(&mut self.api_token, api_token);
+        std::mem::swap(&mut self.app, app);
+    }
+}
+
+/// Sets up the browser state for one account.
+fn new_app(
+    config: &ConfigFile,
+    read_only: bool,
+    client: &Client,
+    api_token: &String,
+    status: &status::StatusBoard,
+) -> BrowserApp {
+    let mut app = BrowserApp::new();
+    app.status = status.clone();
+    app.read_only = read_only;
+    app.natural_sort = config.natural_sort;
+    app.letter_jump = config.letter_jump;
+    app.pretty_names = config.pretty_names;
+    app.grid_view = config.grid_view;
+    app.thumbnails = (config.thumbnails && thumbnails::terminal_supports_images())
+        .then(thumbnails::Thumbnails::default);
+    app.pinned = config.pinned.iter().copied().collect();
+    app.default_actions = config.default_actions.clone();
+    app.search_history = searches::SearchHistory::load();
+    app.open_with = config.open_with.clone();
+    app.download_dir = config.download_dir.clone();
+    app.recent_dirs = download_dirs::RecentDirs::load();
+    app.profiles = config.profiles.keys().cloned().collect();
+    app.confirm_delete = api_token.is_empty()
+        || config.confirm_delete.required(|| {
+            put::account::settings(client, api_token)
+                .map(|r| r.settings.trash_enabled)
+                .unwrap_or(false)
+        });
+    if api_token.is_empty() {
+        app.modal = ModalState::Welcome;
+        app.needs_reload = false;
+    }
+    app
+}
+
+fn auto_refresh_for(
+    config: &ConfigFile,
+    client: &Client,
+    api_token: &str,
+) -> Option<refresh::AutoRefresh> {
+    let every = config.auto_refresh.as_deref()?;
+    let seconds = config::parse_duration(every).ok()?;
+    Some(refresh::AutoRefresh::new(
+        Duration::from_secs(seconds),
+        client,
+        api_token,
+    ))
+}
+
+pub fn run(client: &Client, config: &ConfigFile, read_only: bool) -> io::Result<()> {
     // Restore terminal on panic
     std::panic::set_hook(Box::new(|info| {
         let _ = disable_raw_mode();
@@ -25,17 +121,49 @@ pub fn run(client: &Client, api_token: &String) -> io::Result<()> {
         eprintln!("{info}");
     }));
 
+    if let Some(Err(e)) = config.auto_refresh.as_deref().map(config::parse_duration) {
+        eprintln!("Warning: not refreshing automatically: {}", e);
+    }
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = BrowserApp::new();
+    // The front tab's account and state. The token is replaced once the user
+    // logs in from inside the browser
+    let mut profile: Option<String> = None;
+    let mut client = client.clone();
+    let mut api_token = config.api_token.clone();
+    let status = status::StatusBoard::default();
+    let mut app = new_app(config, read_only, &client, &api_token, &status);
+    app.tabs = vec![String::new()];
+    let mut tabs = vec![Tab {
+        profile: None,
+        client: client.clone(),
+        api_token: String::new(),
+        app: BrowserApp::new(),
+    }];
+
+    if config.trash_prune_on_start && !read_only && !api_token.is_empty() {
+        if let Some(Ok(max_age)) = config.trash_max_age.as_deref().map(config::parse_duration) {
+            app.pending_action = PendingAction::FindOldTrash { max_age };
+        }
+    }
+
+    let remote_commands = remote::listen();
+    let mut auto_refresh = auto_refresh_for(config, &client, &api_token);
 
     loop {
         app.tick = app.tick.wrapping_add(1);
+        let label = tab_label(profile.as_deref(), &app);
+        if let Some(name) = app.tabs.get_mut(app.active_tab) {
+            *name = label;
+        }
+        app.rate_limit = put::rate_limit::current(&api_token);
         terminal.draw(|f| ui::draw(f, &mut app))?;
+        draw_thumbnails(&mut terminal, &mut app, &client)?;
 
         if matches!(app.app_state, AppState::Quitting) {
             break;
@@ -43,10 +171,9 @@ pub fn run(client: &Client, api_token: &String) -> io::Result<()> {
 
         if app.needs_reload {
             app.needs_reload = false;
-            if !matches!(app.modal, ModalState::Loading) {
-                app.spinner_label = "Loading...".to_string();
-                app.modal = ModalState::Loading;
-            }
+            let folder_name = app.breadcrumbs.last().map_or("", |c| c.name.as_str());
+            app.spinner_label = format!("Listing {}...", folder_name);
+            app.modal = ModalState::Loading;
             let client2 = client.clone();
             let token2 = api_token.clone();
             let folder_id = app.current_folder_id;
@@ -62,27 +189,249 @@ pub fn run(client: &Client, api_token: &String) -> io::Result<()> {
                             }
                         }
                     }
+                    app.folders
+                        .insert(r.parent.id, &r.parent.name, r.parent.parent_id);
                     app.set_files(r.files);
+                    if let Some(auto_refresh) = &mut auto_refresh {
+                        auto_refresh.listed();
+                    }
                 }
-                Err(e) => app.modal = ModalState::Error(e.to_string()),
+                Err(e) => app.show_api_error_or_retry("Loading failed", &e, PendingAction::Reload),
             }
             continue;
         }
 
+        if let ModalState::Login { code, last_check } = &mut app.modal {
+            if last_check.elapsed() >= LOGIN_POLL_INTERVAL {
+                *last_check = Instant::now();
+                // Errors are expected while the code is unlinked, so keep polling
+                if let Ok(token) = put::oob::check(&client, code) {
+                    if !token.is_empty() {
+                        match config::store_api_token(profile.as_deref(), &token) {
+                            Ok(_) => {
+                                api_token = token;
+                                app.needs_reload = true;
+                            }
+                            Err(e) => {
+                                app.modal = ModalState::Error(format!("Saving login failed: {}", e))
+                            }
+                        }
+                        continue;
+                    }
+                }
+            }
+        }
+
         let pending = std::mem::replace(&mut app.pending_action, PendingAction::None);
         match pending {
             PendingAction::None => {}
 
+            PendingAction::Reload => {
+                app.spinner_label = "Loading...".to_string();
+                app.modal = ModalState::Loading;
+                app.needs_reload = true;
+            }
+
+            PendingAction::OpenTab { profile: name } => {
+                let token = config
+                    .profiles
+                    .get(&name)
+                    .map(|p| p.api_token.clone())
+                    .unwrap_or_default();
+                if token.is_empty() {
+                    app.modal = ModalState::Error(format!(
+                        "Profile '{}' has no API token. Run: kaput --profile {} login",
+                        name, name
+                    ));
+                    continue;
+                }
+                match put::client(&config.http) {
+                    Ok(tab_client) => {
+                        let tab_app = new_app(config, read_only, &tab_client, &token, &status);
+                        tabs.push(Tab {
+                            profile: Some(name.clone()),
+                            client: tab_client,
+                            api_token: token,
+                            app: tab_app,
+                        });
+                        app.tabs.push(String::new());
+                        app.switch_tab = Some(tabs.len() - 1);
+                    }
+                    Err(e) => app.modal = ModalState::Error(format!("Opening tab failed: {}", e)),
+                }
+            }
+
+            PendingAction::NewTab => {
+                let mut tab_app = new_app(config, read_only, &client, &api_token, &status);
+                tab_app.confirm_delete = app.confirm_delete;
+                tab_app.breadcrumbs = app
+                    .breadcrumbs
+                    .iter()
+                    .filter(|crumb| crumb.id >= 0)
+                    .cloned()
+                    .collect();
+                tab_app.current_folder_id = app.current_folder_id;
+                tab_app.sort_field = app.sort_field;
+                tab_app.sort_direction = app.sort_direction;
+                tabs.push(Tab {
+                    profile: profile.clone(),
+                    client: client.clone(),
+                    api_token: api_token.clone(),
+                    app: tab_app,
+                });
+                app.tabs.push(String::new());
+                app.switch_tab = Some(tabs.len() - 1);
+            }
+
+            PendingAction::CloseTab => {
+                if tabs.len() > 1 {
+                    let mut names = std::mem::take(&mut app.tabs);
+                    let closed = app.active_tab;
+                    tabs.remove(closed);
+                    names.remove(closed);
+                    // The closed tab's state is dropped into the slot of the one brought forward
+                    let index = closed.min(tabs.len() - 1);
+                    tabs[index].exchange(&mut profile, &mut client, &mut api_token, &mut app);
+                    app.tabs = names;
+                    app.active_tab = index;
+                    auto_refresh = auto_refresh_for(config, &client, &api_token);
+                    continue;
+                }
+            }
+
             PendingAction::Search { query } => {
                 let client2 = client.clone();
                 let token2 = api_token.clone();
                 let query2 = query.clone();
-                let result = spin_while(&mut terminal, &mut app, move || {
+                let result = spin_while_cancellable(&mut terminal, &mut app, move |_| {
                     put::files::search(&client2, &token2, &query2)
                 })?;
                 match result {
-                    Ok(r) => app.enter_search_results(&query, r.files),
-                    Err(e) => app.modal = ModalState::Error(format!("Search failed: {}", e)),
+                    None => app.modal = ModalState::None,
+                    Some(Ok(r)) => {
+                        // Look up the results' folders in the background so copying
+                        // their paths is instant
+                        let mut parent_ids: Vec<i64> =
+                            r.files.iter().map(|f| f.parent_id).collect();
+                        parent_ids.sort_unstable();
+                        parent_ids.dedup();
+                        let folders = app.folders.clone();
+                        let client2 = client.clone();
+                        let token2 = api_token.clone();
+                        let operation = app
+                            .status
+                            .start(format!("Looking up {} folders...", parent_ids.len()));
+                        std::thread::spawn(move || {
+                            folders.prefetch(&client2, &token2, parent_ids, &operation)
+                        });
+
+                        app.enter_search_results(&query, r.files);
+                    }
+                    Some(Err(e)) => app.show_api_error_or_retry(
+                        "Search failed",
+                        &e,
+                        PendingAction::Search { query },
+                    ),
+                }
+            }
+
+            PendingAction::PickFolder(mut picker) => {
+                let client2 = client.clone();
+                let token2 = api_token.clone();
+                let folder_id = picker.folder_id();
+                let result = spin_while(&mut terminal, &mut app, move || {
+                    put::files::list(&client2, &token2, folder_id)
+                })?;
+                match result {
+                    Ok(r) => {
+                        let mut folders: Vec<put::files::File> = r
+                            .files
+                            .into_iter()
+                            .filter(|f| f.file_type == "FOLDER")
+                            .collect();
+                        folders.sort_by(|a, b| natsort::compare(&a.name, &b.name));
+                        picker.folders = folders;
+                        picker.selected = 0;
+                        app.modal = ModalState::CopyTo(picker);
+                    }
+                    Err(e) => app.show_api_error("Loading folders failed", &e),
+                }
+            }
+
+            PendingAction::Copy {
+                file_id,
+                file_name,
+                parent_id,
+                parent_name,
+            } => {
+                let operation = format!("Copy \"{}\" to {}", file_name, parent_name);
+                let started = Instant::now();
+                let client2 = client.clone();
+                let token2 = api_token.clone();
+                let result = spin_while(&mut terminal, &mut app, move || {
+                    put::files::copy(&client2, &token2, file_id, parent_id)
+                })?;
+                let outcome = match result {
+                    Ok(true) => Ok(()),
+                    Ok(false) => {
+                        // No server-side copy, so move the bytes through this machine
+                        disable_raw_mode()?;
+                        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+                        terminal.show_cursor()?;
+
+                        eprintln!("Warning: Put.io can't copy this on its side, so it will be downloaded and uploaded again.\n");
+                        let outcome =
+                            put::files::copy_via_local(&client, &api_token, file_id, parent_id);
+                        if let Err(e) = &outcome {
+                            eprintln!("Copying failed: {}", e);
+                        }
+                        println!("\nPress Enter to return to the file browser...");
+                        let mut input = String::new();
+                        io::stdin().read_line(&mut input).ok();
+
+                        enable_raw_mode()?;
+                        execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+                        terminal.clear()?;
+                        outcome
+                    }
+                    Err(e) => Err(e.to_string()),
+                };
+                app.journal.record(operation, started, outcome.clone());
+                match outcome {
+                    Ok(_) => {
+                        app.modal = ModalState::Success(format!("Copied to {}", parent_name));
+                        if parent_id == app.current_folder_id && !app.is_search_results {
+                            app.save_position_for_reload();
+                            app.needs_reload = true;
+                        }
+                    }
+                    Err(e) => app.modal = ModalState::Error(format!("Copying failed: {}", e)),
+                }
+            }
+
+            PendingAction::GoToPath { path } => {
+                let client2 = client.clone();
+                let token2 = api_token.clone();
+                let path2 = path.clone();
+                let result = spin_while_cancellable(&mut terminal, &mut app, move |_| {
+                    let id = put::files::resolve_path(&client2, &token2, &path2)?;
+                    put::files::list(&client2, &token2, id)
+                        .map(|r| r.parent)
+                        .map_err(|e| e.to_string())
+                })?;
+                match result {
+                    None => app.modal = ModalState::None,
+                    Some(Ok(file)) if file.file_type == "FOLDER" => {
+                        app.navigate_to_folder(file.id, 0);
+                        app.needs_reload = true;
+                    }
+                    Some(Ok(file)) => {
+                        app.navigate_to_folder(file.parent_id, file.id);
+                        app.needs_reload = true;
+                    }
+                    Some(Err(e)) => {
+                        app.modal = ModalState::Error(format!("Could not open {}: {}", path, e))
+                    }
                 }
             }
 
@@ -97,45 +446,407 @@ pub fn run(client: &Client, api_token: &String) -> io::Result<()> {
             } => {
                 let client2 = client.clone();
                 let token2 = api_token.clone();
+                let folders = app.folders.clone();
+                let started = Instant::now();
+                let operation = format!("Copy path of \"{}\"", file_name);
+                let result = spin_while_cancellable(&mut terminal, &mut app, move |cancel| {
+                    folders.path_parts(&client2, &token2, parent_id, &cancel)
+                })?;
+                let cancelled = result.is_none();
+                let outcome = match result {
+                    None => Err("Cancelled".to_string()),
+                    Some(Ok(mut parts)) => {
+                        parts.push(file_name);
+                        events::set_clipboard(&parts.join("/"))
+                    }
+                    Some(Err(e)) => Err(e),
+                };
+                app.journal.record(operation, started, outcome.clone());
+                app.modal = match outcome {
+                    Ok(_) => ModalState::Success("Path copied!".to_string()),
+                    Err(_) if cancelled => ModalState::None,
+                    Err(e) => ModalState::Error(e),
+                };
+            }
+
+            PendingAction::Delete { file_ids } => {
+                let operation = format!("Delete {}", app.describe_files(&file_ids));
+                let started = Instant::now();
+                let client2 = client.clone();
+                let token2 = api_token.clone();
+                let file_id_str = file_ids
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",");
                 let result = spin_while(&mut terminal, &mut app, move || {
-                    events::build_path_parts(&client2, &token2, parent_id)
+                    put::files::delete(&client2, &token2, &file_id_str)
                 })?;
+                app.journal
+                    .record(operation, started, result_status(&result));
                 match result {
-                    Ok(mut parts) => {
-                        parts.push(file_name);
-                        let path = parts.join("/");
-                        events::copy_to_clipboard(&mut app, &path, "Path copied!");
+                    Ok(_) => {
+                        app.spinner_label = "Loading...".to_string();
+                        app.needs_reload = true;
                     }
-                    Err(e) => app.modal = ModalState::Error(e),
+                    Err(e) => app.show_api_error("Delete failed", &e),
                 }
             }
 
-            PendingAction::Delete { file_id } => {
+            PendingAction::SetWatched { file_ids, watched } => {
+                let operation = format!(
+                    "Mark {} as {}",
+                    app.describe_files(&file_ids),
+                    if watched { "watched" } else { "unwatched" }
+                );
+                let started = Instant::now();
                 let client2 = client.clone();
                 let token2 = api_token.clone();
-                let file_id_str = file_id.to_string();
+                let file_id_str = file_ids
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",");
                 let result = spin_while(&mut terminal, &mut app, move || {
-                    put::files::delete(&client2, &token2, &file_id_str)
+                    put::files::set_watch_status(&client2, &token2, &file_id_str, watched)
                 })?;
+                app.journal
+                    .record(operation, started, result_status(&result));
                 match result {
                     Ok(_) => {
                         app.spinner_label = "Loading...".to_string();
                         app.needs_reload = true;
                     }
-                    Err(e) => app.modal = ModalState::Error(format!("Delete failed: {}", e)),
+                    Err(e) => app.show_api_error("Updating watch status failed", &e),
+                }
+            }
+
+            PendingAction::CastDiscover { file_id, file_name } => {
+                let result = spin_while(&mut terminal, &mut app, cast::discover)?;
+                match result {
+                    Ok(devices) => {
+                        app.modal = ModalState::CastDevices {
+                            file_id,
+                            file_name,
+                            devices,
+                            selected: 0,
+                        }
+                    }
+                    Err(e) => app.modal = ModalState::Error(e),
+                }
+            }
+
+            PendingAction::Cast {
+                file_id,
+                file_name,
+                device,
+            } => {
+                // HLS is used rather than the raw stream since Chromecasts can't play most containers
+                let url = client.api_url(&format!(
+                    "/files/{}/hls/media.m3u8?subtitle_key=all&oauth_token={}",
+                    file_id, api_token
+                ));
+                let address = device.address.clone();
+                let started = Instant::now();
+                let result =
+                    spin_while(&mut terminal, &mut app, move || cast::play(&address, &url))?;
+                app.journal.record(
+                    format!("Cast \"{}\" to {}", file_name, device.name),
+                    started,
+                    result_status(&result),
+                );
+                match result {
+                    Ok(_) => {
+                        app.cast_session = Some(CastSession {
+                            device,
+                            file_name,
+                            paused: false,
+                        });
+                        app.modal = ModalState::Casting;
+                    }
+                    Err(e) => app.modal = ModalState::Error(format!("Cast failed: {}", e)),
+                }
+            }
+
+            PendingAction::CastControl { address, command } => {
+                let result = spin_while(&mut terminal, &mut app, move || {
+                    let args: Vec<&str> = command.iter().map(String::as_str).collect();
+                    cast::control(&address, &args)
+                })?;
+                match result {
+                    Ok(_) if app.cast_session.is_some() => app.modal = ModalState::Casting,
+                    Ok(_) => app.modal = ModalState::None,
+                    Err(e) => app.modal = ModalState::Error(format!("Cast control failed: {}", e)),
+                }
+            }
+
+            PendingAction::LoginStart => {
+                let client2 = client.clone();
+                let result = spin_while(&mut terminal, &mut app, move || put::oob::get(&client2))?;
+                match result {
+                    Ok(code) => {
+                        app.modal = ModalState::Login {
+                            code,
+                            last_check: Instant::now(),
+                        }
+                    }
+                    Err(e) => app.modal = ModalState::Error(format!("Login failed: {}", e)),
+                }
+            }
+
+            PendingAction::FetchUrl { url, parent_id } => {
+                let operation = format!("Fetch {}", url);
+                let started = Instant::now();
+                let client2 = client.clone();
+                let token2 = api_token.clone();
+                let callback_url = config.transfer_callback_url.clone();
+                let result = spin_while(&mut terminal, &mut app, move || {
+                    put::transfers::add(
+                        &client2,
+                        &token2,
+                        &url,
+                        Some(&parent_id),
+                        callback_url.as_ref(),
+                    )
+                })?;
+                app.journal
+                    .record(operation, started, result_status(&result));
+                match result {
+                    Ok(_) => {
+                        app.modal = ModalState::Success(
+                            "Transfer added, the file will appear here when done.".to_string(),
+                        )
+                    }
+                    Err(e) => app.show_api_error("Adding transfer failed", &e),
                 }
             }
 
-            PendingAction::Download { file_id } => {
+            PendingAction::PlayFolder { folder_id } => {
                 disable_raw_mode()?;
                 execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
                 terminal.show_cursor()?;
 
-                match put::files::download(client, api_token, file_id, false, None, false) {
-                    Ok(_) => {}
-                    Err(e) => eprintln!("Download error: {}", e),
+                let operation = format!("Play folder {}", app.describe_files(&[folder_id]));
+                let started = Instant::now();
+                let result = play_folder(&client, &api_token, folder_id);
+                app.journal.record(operation, started, result.clone());
+                if let Err(e) = result {
+                    eprintln!("Playback error: {}", e);
+                    println!("\nPress Enter to return to the file browser...");
+                    let mut input = String::new();
+                    io::stdin().read_line(&mut input).ok();
                 }
 
+                enable_raw_mode()?;
+                execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+                terminal.clear()?;
+            }
+
+            PendingAction::Play { file_id } => {
+                let start_from = app
+                    .files
+                    .iter()
+                    .find(|f| f.id == file_id)
+                    .map_or(0, |f| f.start_from);
+
+                disable_raw_mode()?;
+                execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+                terminal.show_cursor()?;
+
+                let operation = format!("Play {}", app.describe_files(&[file_id]));
+                let started = Instant::now();
+                let result = play_video(&client, &api_token, file_id, start_from);
+                app.journal.record(operation, started, result.clone());
+                if let Err(e) = result {
+                    eprintln!("Playback error: {}", e);
+                    println!("\nPress Enter to return to the file browser...");
+                    let mut input = String::new();
+                    io::stdin().read_line(&mut input).ok();
+                }
+
+                enable_raw_mode()?;
+                execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+                terminal.clear()?;
+
+                // Pick up the new watch status or position
+                app.save_position_for_reload();
+                app.needs_reload = true;
+            }
+
+            PendingAction::Extract { file_id } => {
+                let operation = format!("Extract {}", app.describe_files(&[file_id]));
+                let started = Instant::now();
+                let client2 = client.clone();
+                let token2 = api_token.clone();
+                let result = spin_while(&mut terminal, &mut app, move || {
+                    put::files::extract(&client2, &token2, file_id)
+                })?;
+                app.journal
+                    .record(operation, started, result_status(&result));
+                match result {
+                    Ok(_) => app.modal = ModalState::Success("Extraction started!".to_string()),
+                    Err(e) => app.show_api_error("Extraction failed", &e),
+                }
+            }
+
+            PendingAction::Share {
+                file_id,
+                expires_in,
+            } => {
+                let operation = format!("Share {}", app.describe_files(&[file_id]));
+                let started = Instant::now();
+                let client2 = client.clone();
+                let token2 = api_token.clone();
+                let result = spin_while(&mut terminal, &mut app, move || {
+                    put::shares::create(&client2, &token2, file_id, expires_in)
+                })?;
+                app.journal
+                    .record(operation, started, result_status(&result));
+                match result {
+                    Ok(share) => events::copy_to_clipboard(&mut app, &share.url, "Link copied!"),
+                    Err(e) => app.show_api_error("Creating link failed", &e),
+                }
+            }
+
+            PendingAction::FindOldTrash { max_age } => {
+                app.spinner_label = "Checking trash...".to_string();
+                app.modal = ModalState::Loading;
+                let client2 = client.clone();
+                let token2 = api_token.clone();
+                let result = spin_while(&mut terminal, &mut app, move || {
+                    put::trash::list(&client2, &token2)
+                })?;
+                // Only worth interrupting for when there's something to remove
+                let old = put::trash::older_than(result.unwrap_or_default(), max_age);
+                app.modal = if old.is_empty() {
+                    ModalState::None
+                } else {
+                    let size: u64 = old.iter().map(|f| f.size.0).sum();
+                    ModalState::ConfirmPruneTrash {
+                        file_ids: old.iter().map(|f| f.id).collect(),
+                        label: format!("{} item(s), {}", old.len(), bytefmt::format(size)),
+                    }
+                };
+            }
+
+            PendingAction::PruneTrash { file_ids } => {
+                let operation = format!("Remove {} item(s) from the trash", file_ids.len());
+                let started = Instant::now();
+                let client2 = client.clone();
+                let token2 = api_token.clone();
+                let file_id_str = file_ids
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<String>>()
+                    .join(",");
+                let result = spin_while(&mut terminal, &mut app, move || {
+                    put::trash::delete(&client2, &token2, &file_id_str)
+                })?;
+                app.journal
+                    .record(operation, started, result_status(&result));
+                match result {
+                    Ok(_) => {
+                        app.modal = ModalState::Success(format!(
+                            "Removed {} item(s) from the trash",
+                            file_ids.len()
+                        ))
+                    }
+                    Err(e) => app.show_api_error("Emptying trash failed", &e),
+                }
+            }
+
+            PendingAction::OpenWith { file_id, index } => {
+                let Some(file) = app.files.iter().find(|f| f.id == file_id).cloned() else {
+                    continue;
+                };
+                let program = app.open_with[index].clone();
+
+                // Players can start on the stream, anything else gets the file itself
+                let url = if file.file_type == "VIDEO" || file.file_type == "AUDIO" {
+                    playlist::stream_url(&client, file_id, &api_token)
+                } else {
+                    match put::files::url(&client, &api_token, file_id) {
+                        Ok(r) => r.url,
+                        Err(e) => {
+                            app.show_api_error("Failed to get URL", &e);
+                            continue;
+                        }
+                    }
+                };
+                let args = program.args_for(&url, file_id, &file.name);
+
+                disable_raw_mode()?;
+                execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+                terminal.show_cursor()?;
+
+                let started = Instant::now();
+                let result = match args.split_first() {
+                    Some((cmd, rest)) => std::process::Command::new(cmd).args(rest).status(),
+                    None => Err(io::Error::new(io::ErrorKind::InvalidInput, "empty command")),
+                };
+                let outcome = match result {
+                    Ok(status) if status.success() => Ok(()),
+                    Ok(status) => Err(format!("{} exited with {}", program.name, status)),
+                    Err(e) => {
+                        eprintln!("Could not run {}: {}", program.name, e);
+                        Err(e.to_string())
+                    }
+                };
+                let failed = outcome.is_err();
+                app.journal.record(
+                    format!("Open \"{}\" with {}", file.name, program.name),
+                    started,
+                    outcome,
+                );
+                if program.pause || failed {
+                    println!("\nPress Enter to return to the file browser...");
+                    let mut input = String::new();
+                    io::stdin().read_line(&mut input).ok();
+                }
+
+                enable_raw_mode()?;
+                execute!(terminal.backend_mut(), EnterAlternateScreen)?;
+                terminal.clear()?;
+            }
+
+            PendingAction::Download {
+                file_id,
+                path,
+                existing,
+            } => {
+                app.recent_dirs.record(&path);
+                app.recent_dirs.save().ok();
+
+                disable_raw_mode()?;
+                execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+                terminal.show_cursor()?;
+
+                let dir = download_dirs::expand_home(&path)
+                    .to_string_lossy()
+                    .into_owned();
+                let options = put::files::DownloadOptions {
+                    path: Some(&dir),
+                    existing,
+                    long_paths: config.windows_long_paths,
+                    post_download: &config.post_download,
+                    profile: profile.as_deref(),
+                    ..Default::default()
+                };
+                let operation = format!("Download {} to {}", app.describe_files(&[file_id]), dir);
+                let started = Instant::now();
+                let outcome = match std::fs::create_dir_all(&dir) {
+                    Ok(_) if !has_space_for(&client, &api_token, file_id, &dir, existing) => {
+                        Err("Cancelled, not enough free space".to_string())
+                    }
+                    Ok(_) => put::files::download(&client, &api_token, file_id, &options)
+                        .map_err(|e| format!("Download error: {}", e)),
+                    Err(e) => Err(format!("Could not create {}: {}", dir, e)),
+                };
+                if let Err(e) = &outcome {
+                    eprintln!("{}", e);
+                }
+                app.journal.record(operation, started, outcome);
+
                 println!("\nPress Enter to return to the file browser...");
                 let mut input = String::new();
                 io::stdin().read_line(&mut input).ok();
@@ -150,13 +861,44 @@ pub fn run(client: &Client, api_token: &String) -> io::Result<()> {
             break;
         }
 
+        if let Some(index) = app.switch_tab.take().filter(|i| *i < tabs.len()) {
+            let names = std::mem::take(&mut app.tabs);
+            let front = app.active_tab;
+            tabs[front].exchange(&mut profile, &mut client, &mut api_token, &mut app);
+            tabs[index].exchange(&mut profile, &mut client, &mut api_token, &mut app);
+            app.tabs = names;
+            app.active_tab = index;
+            auto_refresh = auto_refresh_for(config, &client, &api_token);
+            continue;
+        }
+
+        if let Some(auto_refresh) = &mut auto_refresh {
+            auto_refresh.poll(&mut app, &client, &api_token);
+        }
+
+        // Remote commands wait until the user is done with any open dialog
+        if let Some(commands) = &remote_commands {
+            if matches!(app.modal, ModalState::None)
+                && matches!(app.pending_action, PendingAction::None)
+            {
+                if let Ok(command) = commands.try_recv() {
+                    handle_remote_command(&mut app, command);
+                    continue;
+                }
+            }
+        }
+
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
-                events::handle_key(&mut app, key, client, api_token);
+                events::handle_key(&mut app, key, &client, &api_token);
             }
         }
     }
 
+    if remote_commands.is_some() {
+        remote::close();
+    }
+
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
@@ -164,8 +906,196 @@ pub fn run(client: &Client, api_token: &String) -> io::Result<()> {
     Ok(())
 }
 
+/// Draws the grid's thumbnails over the frame just drawn. They are left alone
+/// while a dialog or the status board may cover them, and drawn again after.
+fn draw_thumbnails(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut BrowserApp,
+    client: &Client,
+) -> io::Result<()> {
+    let covered = !matches!(app.modal, ModalState::None) || !app.status.snapshot().is_empty();
+    let needs_clear = match &mut app.thumbnails {
+        None => return Ok(()),
+        Some(thumbnails) if covered => {
+            thumbnails.invalidate();
+            return Ok(());
+        }
+        Some(thumbnails) => thumbnails.needs_clear(&app.thumbnail_cells),
+    };
+    if needs_clear {
+        terminal.clear()?;
+        terminal.draw(|f| ui::draw(f, app))?;
+    }
+    if let Some(thumbnails) = &mut app.thumbnails {
+        thumbnails.show(terminal.backend_mut(), &app.thumbnail_cells, client)?;
+    }
+    Ok(())
+}
+
+/// Names a tab after the folder it shows, and the profile it browses if any.
+fn tab_label(profile: Option<&str>, app: &BrowserApp) -> String {
+    let folder = app
+        .breadcrumbs
+        .last()
+        .map_or("", |crumb| crumb.name.as_str());
+    match profile {
+        Some(profile) => format!("{}: {}", profile, folder),
+        None => folder.to_string(),
+    }
+}
+
+/// Plays every video in a folder in episode order by handing mpv a playlist.
+fn play_folder(client: &Client, api_token: &str, folder_id: i64) -> Result<(), String> {
+    let listing = put::files::list(client, &api_token.to_string(), folder_id)
+        .map_err(|e| format!("Listing folder failed: {}", e))?;
+    let entries = playlist::entries(listing.files);
+    if entries.is_empty() {
+        return Err("No videos or audio files in this folder.".to_string());
+    }
+
+    let path = playlist::write_private(
+        &playlist::render(client, &entries, api_token, "m3u8"),
+        "m3u8",
+    )
+    .map_err(|e| format!("Writing playlist failed: {}", e))?;
+    let status = std::process::Command::new("mpv")
+        .arg(format!("--playlist={}", path.display()))
+        .status();
+    if let Some(dir) = path.parent() {
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    match status {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("Could not run mpv (is it installed?): {}", e)),
+    }
+}
+
+/// Like `spin_while`, but pressing Esc gives up on the work and returns `None`.
+/// The work is handed a token that is cancelled at that point, so operations
+/// that make several requests can stop early; a request already sent is
+/// finished in the background and its result dropped. Only use this for work
+/// that is safe to abandon, i.e. nothing that changes files.
+fn spin_while_cancellable<T, F>(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut BrowserApp,
+    work: F,
+) -> io::Result<Option<T>>
+where
+    T: Send + 'static,
+    F: FnOnce(CancelToken) -> T + Send + 'static,
+{
+    let cancel = CancelToken::default();
+    let (tx, rx) = mpsc::channel();
+    let worker_cancel = cancel.clone();
+    std::thread::spawn(move || {
+        tx.send(work(worker_cancel)).ok();
+    });
+    let label = std::mem::take(&mut app.spinner_label);
+    app.spinner_label = format!("{} (Esc to cancel)", label);
+    let _operation = app.status.start(app.spinner_label.clone());
+    let outcome = loop {
+        app.tick = app.tick.wrapping_add(1);
+        terminal.draw(|f| ui::draw(f, app))?;
+        match rx.try_recv() {
+            Ok(result) => break Some(result),
+            Err(mpsc::TryRecvError::Disconnected) => {
+                app.spinner_label = label;
+                return Err(io::Error::other("worker thread panicked"));
+            }
+            Err(mpsc::TryRecvError::Empty) => {}
+        }
+        if event::poll(Duration::from_millis(80))? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && key.code == KeyCode::Esc {
+                    cancel.cancel();
+                    break None;
+                }
+            }
+        }
+    };
+    app.spinner_label = label;
+    Ok(outcome)
+}
+
+/// Checks the destination has room for a download, asking on the terminal
+/// whether to go ahead if it doesn't. A failed check doesn't stop the download.
+fn has_space_for(
+    client: &Client,
+    api_token: &String,
+    file_id: i64,
+    dir: &str,
+    existing: ExistingFile,
+) -> bool {
+    let missing = match put::files::space_shortfall(client, api_token, file_id, dir, existing) {
+        Ok(Some(missing)) => missing,
+        Ok(None) => return true,
+        Err(e) => {
+            eprintln!("Warning: checking free space failed: {}", e);
+            return true;
+        }
+    };
+    print!(
+        "Not enough free space in {}: {} more is needed. Download anyway? [y/N] ",
+        dir,
+        bytefmt::format(missing)
+    );
+    io::stdout().flush().ok();
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).ok();
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Queues a command received from `kaput remote`.
+fn handle_remote_command(app: &mut BrowserApp, command: remote::RemoteCommand) {
+    match command {
+        remote::RemoteCommand::GoTo(path) => {
+            app.pending_action = PendingAction::GoToPath { path };
+            app.spinner_label = "Opening...".to_string();
+            app.modal = ModalState::Loading;
+        }
+        remote::RemoteCommand::Download(file_id) => {
+            let path = download_dirs::initial(app.download_dir.as_deref(), &app.recent_dirs);
+            events::queue_download(app, file_id, path);
+        }
+        remote::RemoteCommand::Refresh => {
+            app.save_position_for_reload();
+            app.spinner_label = "Loading...".to_string();
+            app.modal = ModalState::Loading;
+            app.needs_reload = true;
+        }
+    }
+}
+
+/// Plays a video in mpv from `start_from` seconds and saves where it stopped.
+fn play_video(
+    client: &Client,
+    api_token: &String,
+    file_id: i64,
+    start_from: u64,
+) -> Result<(), String> {
+    let url = put::files::url(client, api_token, file_id)
+        .map_err(|e| format!("Failed to get URL: {}", e))?
+        .url;
+    let position = mpv::play(&url, start_from)
+        .map_err(|e| format!("Could not run mpv (is it installed?): {}", e))?;
+    if let Some(position) = position {
+        mpv::save_position(client, api_token, file_id, &position)
+            .map_err(|e| format!("Saving playback position failed: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Reduces an operation's result to what the activity journal keeps.
+fn result_status<T, E: std::fmt::Display>(result: &Result<T, E>) -> Result<(), String> {
+    match result {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
 /// Runs a blocking closure on a background thread while keeping the TUI draw
-/// loop alive so the spinner actually animates.
+/// loop alive, showing `app.spinner_label` on the status board until it is done.
 fn spin_while<T, F>(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
     app: &mut BrowserApp,
@@ -179,6 +1109,7 @@ where
     std::thread::spawn(move || {
         tx.send(work()).ok();
     });
+    let _operation = app.status.start(app.spinner_label.clone());
     loop {
         app.tick = app.tick.wrapping_add(1);
         terminal.draw(|f| ui::draw(f, app))?;
@@ -0,0 +1,101 @@
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+use super::app::{BrowserApp, ModalState};
+use crate::put::{self, events::Subscription, files::File, Client};
+
+type Listing = Result<Vec<File>, reqwest::Error>;
+
+/// Re-lists the current folder in the background every `interval`, or as soon
+/// as the account's event feed reports new files, so files dropped in by a
+/// finishing transfer show up without pressing anything. Finished transfers are
+/// also announced in the status bar.
+pub struct AutoRefresh {
+    interval: Duration,
+    last: Instant,
+    /// The event feed, checked every `interval`, and the token it was opened with
+    events: Subscription,
+    api_token: String,
+    /// An event arrived since the last refresh
+    due: bool,
+    /// The folder being listed and where its listing will arrive
+    pending: Option<(i64, Receiver<Listing>)>,
+}
+
+impl AutoRefresh {
+    pub fn new(interval: Duration, client: &Client, api_token: &str) -> Self {
+        AutoRefresh {
+            interval,
+            last: Instant::now(),
+            events: put::events::subscribe(client, api_token, interval),
+            api_token: api_token.to_string(),
+            due: false,
+            pending: None,
+        }
+    }
+
+    /// Restarts the wait after the folder was listed some other way.
+    pub fn listed(&mut self) {
+        self.last = Instant::now();
+    }
+
+    /// Starts a refresh when one is due and applies a finished one. Folders are
+    /// only refreshed while nothing else is going on, and listings that arrive
+    /// after the user moved on are dropped.
+    pub fn poll(&mut self, app: &mut BrowserApp, client: &Client, api_token: &str) {
+        let idle = matches!(app.modal, ModalState::None) && !app.is_search_results;
+        // After logging in or switching to another account's tab
+        if api_token != self.api_token {
+            self.events = put::events::subscribe(client, api_token, self.interval);
+            self.api_token = api_token.to_string();
+        }
+        while let Some(event) = self.events.try_recv() {
+            self.due |= event.adds_files();
+            if event.ends_transfer() {
+                let name = event.transfer_name.as_deref().unwrap_or("a transfer");
+                let notice = if event.event_type == "transfer_error" {
+                    format!("Transfer failed: {}", name)
+                } else {
+                    format!("Transfer finished: {}", name)
+                };
+                app.transfer_notice = Some((notice, Instant::now()));
+            }
+        }
+
+        let Some((folder_id, rx)) = &self.pending else {
+            if idle && (self.due || self.last.elapsed() >= self.interval) {
+                self.due = false;
+                let (tx, rx) = mpsc::channel();
+                let client = client.clone();
+                let api_token = api_token.to_string();
+                let folder_id = app.current_folder_id;
+                let folder_name = app.breadcrumbs.last().map_or("", |c| c.name.as_str());
+                let operation = app.status.start(format!("Refreshing {}...", folder_name));
+                std::thread::spawn(move || {
+                    tx.send(put::files::list(&client, &api_token, folder_id).map(|r| r.files))
+                        .ok();
+                    drop(operation);
+                });
+                self.pending = Some((folder_id, rx));
+            }
+            return;
+        };
+
+        let result = match rx.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.pending = None;
+                return;
+            }
+        };
+        // Failures aren't worth interrupting for; the next refresh tries again
+        if let Ok(files) = result {
+            if idle && *folder_id == app.current_folder_id {
+                app.refresh_files(files);
+            }
+        }
+        self.pending = None;
+        self.last = Instant::now();
+    }
+}
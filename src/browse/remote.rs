@@ -0,0 +1,122 @@
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::Receiver;
+
+use crate::config;
+
+/// How long a `kaput remote` connection may take to send its command or read
+/// the reply
+#[cfg(unix)]
+const CLIENT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// A request from `kaput remote` to the running browser.
+pub enum RemoteCommand {
+    /// Show a folder, or the folder containing a file, by path
+    GoTo(String),
+    /// Download a file or folder to the default download folder
+    Download(i64),
+    Refresh,
+}
+
+impl RemoteCommand {
+    pub fn parse(line: &str) -> Result<RemoteCommand, String> {
+        let line = line.trim();
+        let (name, argument) = line.split_once(' ').unwrap_or((line, ""));
+        let argument = argument.trim();
+        match name {
+            "goto" if !argument.is_empty() => Ok(RemoteCommand::GoTo(argument.to_string())),
+            "download" => argument
+                .parse()
+                .map(RemoteCommand::Download)
+                .map_err(|_| format!("invalid file ID '{}'", argument)),
+            "refresh" => Ok(RemoteCommand::Refresh),
+            _ => Err(format!(
+                "unknown command '{}' (expected goto <path>, download <id> or refresh)",
+                line
+            )),
+        }
+    }
+}
+
+/// Where the running browser listens for commands.
+pub fn socket_path() -> PathBuf {
+    config::data_file_path("browse.sock")
+}
+
+/// Sends a command line to the running browser and returns its reply.
+#[cfg(unix)]
+pub fn send(command: &str) -> io::Result<String> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket_path())?;
+    writeln!(stream, "{}", command)?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply.trim_end().to_string())
+}
+
+#[cfg(not(unix))]
+pub fn send(_command: &str) -> io::Result<String> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "remote control needs Unix sockets",
+    ))
+}
+
+/// Starts accepting commands on the control socket. Every valid command is
+/// acknowledged right away and passed on through the returned channel.
+///
+/// Returns `None` if the socket can't be used, e.g. because another browser
+/// already listens on it.
+#[cfg(unix)]
+pub fn listen() -> Option<Receiver<RemoteCommand>> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::mpsc;
+
+    let path = socket_path();
+    if path.exists() {
+        if UnixStream::connect(&path).is_ok() {
+            return None;
+        }
+        // Left behind by a browser that didn't exit cleanly
+        std::fs::remove_file(&path).ok()?;
+    }
+    let listener = UnixListener::bind(&path).ok()?;
+
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // Connections are served one at a time, so one that never sends a
+            // line mustn't hold up the others
+            stream.set_read_timeout(Some(CLIENT_TIMEOUT)).ok();
+            stream.set_write_timeout(Some(CLIENT_TIMEOUT)).ok();
+            let mut line = String::new();
+            if BufReader::new(&stream).read_line(&mut line).is_err() {
+                continue;
+            }
+            let reply = match RemoteCommand::parse(&line) {
+                Ok(command) => {
+                    if tx.send(command).is_err() {
+                        break;
+                    }
+                    "ok".to_string()
+                }
+                Err(e) => format!("error: {}", e),
+            };
+            writeln!(&stream, "{}", reply).ok();
+        }
+    });
+    Some(rx)
+}
+
+#[cfg(not(unix))]
+pub fn listen() -> Option<Receiver<RemoteCommand>> {
+    None
+}
+
+/// Removes the control socket when the browser that listened on it exits.
+pub fn close() {
+    std::fs::remove_file(socket_path()).ok();
+}
@@ -0,0 +1,62 @@
+use std::fs;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+const SEARCHES_FILE: &str = "searches.json";
+
+/// Most recent searches kept, not counting pinned ones
+const MAX_RECENT: usize = 50;
+
+/// Queries entered in the search prompt, kept between sessions.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SearchHistory {
+    /// Favorite queries, reachable with Alt+1 to Alt+9
+    pub pinned: Vec<String>,
+    /// Most recent first
+    pub recent: Vec<String>,
+}
+
+impl SearchHistory {
+    pub fn load() -> Self {
+        fs::read_to_string(config::data_file_path(SEARCHES_FILE))
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = config::data_file_path(SEARCHES_FILE);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Moves `query` to the front of the recent searches.
+    pub fn record(&mut self, query: &str) {
+        self.recent.retain(|q| q != query);
+        self.recent.insert(0, query.to_string());
+        self.recent.truncate(MAX_RECENT);
+    }
+
+    /// Pins `query`, or unpins it if it already is.
+    pub fn toggle_pin(&mut self, query: &str) {
+        if self.pinned.iter().any(|q| q == query) {
+            self.pinned.retain(|q| q != query);
+        } else {
+            self.pinned.push(query.to_string());
+        }
+    }
+
+    /// Returns the queries to step through with ↑/↓: pinned ones first, then recent ones.
+    pub fn entries(&self) -> Vec<&String> {
+        self.pinned
+            .iter()
+            .chain(self.recent.iter().filter(|q| !self.pinned.contains(q)))
+            .collect()
+    }
+}
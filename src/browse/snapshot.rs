@@ -0,0 +1,129 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use tabled::{builder::Builder, settings::Style};
+
+use super::app::{BrowserApp, SortDirection};
+
+/// Describes what is on screen: where, in what order and with which filter.
+fn summary(app: &BrowserApp) -> (String, String) {
+    let location = app
+        .breadcrumbs
+        .iter()
+        .map(|b| b.name.as_str())
+        .collect::<Vec<&str>>()
+        .join(" › ");
+    let field = app.sort_field.name();
+    let direction = match app.sort_direction {
+        SortDirection::Asc => "ascending",
+        SortDirection::Desc => "descending",
+    };
+    let mut details = format!("Sorted by {}, {}", field, direction);
+    if app.filter.is_active() {
+        details.push_str(&format!(" · filter: {}", app.filter.describe()));
+    }
+    details.push_str(&format!(" · {} entries", app.files.len()));
+    (location, details)
+}
+
+/// One row per entry as (name, type, size, created). Folders get a trailing `/`.
+fn rows(app: &BrowserApp) -> Vec<[String; 4]> {
+    app.files
+        .iter()
+        .map(|file| {
+            let is_folder = file.file_type == "FOLDER";
+            [
+                if is_folder {
+                    format!("{}/", file.name)
+                } else {
+                    file.name.clone()
+                },
+                file.file_type.clone(),
+                if is_folder {
+                    String::new()
+                } else {
+                    file.size.to_string()
+                },
+                file.created_at.chars().take(10).collect(),
+            ]
+        })
+        .collect()
+}
+
+const HEADERS: [&str; 4] = ["Name", "Type", "Size", "Created"];
+
+pub fn render_text(app: &BrowserApp) -> String {
+    let (location, details) = summary(app);
+    let mut builder = Builder::default();
+    builder.push_record(HEADERS);
+    for row in rows(app) {
+        builder.push_record(row);
+    }
+    let table = builder.build().with(Style::markdown()).to_string();
+    format!("{}\n{}\n\n{}\n", location, details, table)
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn render_html(app: &BrowserApp) -> String {
+    let (location, details) = summary(app);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+        <style>body{{font-family:sans-serif}}td,th{{padding:2px 12px;text-align:left}}</style>\n\
+        </head>\n<body>\n<h1>{title}</h1>\n<p>{details}</p>\n<table>\n<tr>",
+        title = html_escape(&location),
+        details = html_escape(&details),
+    );
+    for header in HEADERS {
+        html.push_str(&format!("<th>{}</th>", header));
+    }
+    html.push_str("</tr>\n");
+    for row in rows(app) {
+        html.push_str("<tr>");
+        for cell in row {
+            html.push_str(&format!("<td>{}</td>", html_escape(&cell)));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n</body>\n</html>\n");
+    html
+}
+
+/// Writes the current listing to `path`, as HTML if it ends in `.html` or `.htm`
+/// and as a plain text table otherwise.
+pub fn save(app: &BrowserApp, path: &Path) -> io::Result<()> {
+    let html = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("html") || ext.eq_ignore_ascii_case("htm"));
+    let contents = if html {
+        render_html(app)
+    } else {
+        render_text(app)
+    };
+    fs::write(path, contents)
+}
+
+/// Suggests a file name for saving the current listing, e.g. `kaput-Movies.txt`.
+pub fn default_file_name(app: &BrowserApp) -> String {
+    let name: String = app
+        .breadcrumbs
+        .last()
+        .map_or("files", |b| b.name.as_str())
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("kaput-{}.txt", name)
+}
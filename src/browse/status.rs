@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// One operation in progress.
+pub struct Operation {
+    id: u64,
+    pub label: String,
+    /// Percent done, for operations that know it
+    pub progress: Option<u8>,
+}
+
+/// Operations in progress, shown stacked in the corner of the browser. Clones
+/// share the same list, so work on other threads and in other tabs can report
+/// itself while it runs.
+#[derive(Clone, Default)]
+pub struct StatusBoard {
+    operations: Arc<Mutex<Vec<Operation>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl StatusBoard {
+    /// Adds an operation to the board. It stays there until the returned guard
+    /// is dropped.
+    pub fn start(&self, label: impl Into<String>) -> OperationGuard {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.operations.lock().unwrap().push(Operation {
+            id,
+            label: label.into(),
+            progress: None,
+        });
+        OperationGuard {
+            board: self.clone(),
+            id,
+        }
+    }
+
+    /// Returns the labels and progress of the running operations, oldest first.
+    pub fn snapshot(&self) -> Vec<(String, Option<u8>)> {
+        self.operations
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|op| (op.label.clone(), op.progress))
+            .collect()
+    }
+}
+
+/// Keeps an operation on the board while it is alive.
+pub struct OperationGuard {
+    board: StatusBoard,
+    id: u64,
+}
+
+impl OperationGuard {
+    pub fn set_progress(&self, percent: u8) {
+        let mut operations = self.board.operations.lock().unwrap();
+        if let Some(op) = operations.iter_mut().find(|op| op.id == self.id) {
+            op.progress = Some(percent.min(100));
+        }
+    }
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        if let Ok(mut operations) = self.board.operations.lock() {
+            operations.retain(|op| op.id != self.id);
+        }
+    }
+}
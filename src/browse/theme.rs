@@ -0,0 +1,146 @@
+use std::sync::Mutex;
+
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::config::ColorPreset;
+
+/// Styles the browser draws with, by what they mark rather than by color, so a
+/// preset can change how things look without the drawing code knowing.
+pub struct Theme {
+    pub text: Style,
+    pub plain: Style,
+    /// Hints, separators and other secondary text
+    pub dim: Style,
+    /// Dialog borders and titles, and active filters
+    pub accent: Style,
+    /// Marks, dialogs that need attention
+    pub warning: Style,
+    pub success: Style,
+    pub error: Style,
+    /// The selected entry in lists and the active tab
+    pub selected: Style,
+    pub modal_bg: Color,
+    pub folder: Style,
+    pub video: Style,
+    pub audio: Style,
+    pub image: Style,
+    /// Archives and PDFs
+    pub archive: Style,
+    pub other: Style,
+}
+
+impl Theme {
+    pub fn file_type(&self, file_type: &str) -> Style {
+        match file_type {
+            "FOLDER" => self.folder,
+            "VIDEO" => self.video,
+            "AUDIO" => self.audio,
+            "IMAGE" => self.image,
+            "ARCHIVE" | "PDF" => self.archive,
+            _ => self.other,
+        }
+    }
+}
+
+const fn fg(color: Color) -> Style {
+    Style::new().fg(color)
+}
+
+const DEFAULT: Theme = Theme {
+    text: fg(Color::White),
+    plain: fg(Color::Gray),
+    dim: fg(Color::DarkGray),
+    accent: fg(Color::Cyan),
+    warning: fg(Color::Yellow),
+    success: fg(Color::Green),
+    error: fg(Color::Red),
+    selected: fg(Color::Black)
+        .bg(Color::LightCyan)
+        .add_modifier(Modifier::BOLD),
+    modal_bg: Color::Rgb(45, 45, 58),
+    // Folders in bright warm yellow are visually dominant, files use standard
+    // (non-bright) colors so they stay subordinate
+    folder: fg(Color::LightYellow).add_modifier(Modifier::BOLD),
+    video: fg(Color::Green),
+    audio: fg(Color::Magenta),
+    image: fg(Color::Cyan),
+    archive: fg(Color::Red),
+    other: fg(Color::Gray),
+};
+
+// The Okabe-Ito palette, which stays distinguishable with either kind of
+// red-green color blindness
+const ORANGE: Color = Color::Rgb(230, 159, 0);
+const SKY_BLUE: Color = Color::Rgb(86, 180, 233);
+const BLUISH_GREEN: Color = Color::Rgb(0, 158, 115);
+const YELLOW: Color = Color::Rgb(240, 228, 66);
+const VERMILLION: Color = Color::Rgb(213, 94, 0);
+const REDDISH_PURPLE: Color = Color::Rgb(204, 121, 167);
+
+/// Blue against orange instead of green against red
+const DEUTERANOPIA: Theme = Theme {
+    text: fg(Color::White),
+    plain: fg(Color::Gray),
+    dim: fg(Color::DarkGray),
+    accent: fg(SKY_BLUE),
+    warning: fg(YELLOW),
+    success: fg(SKY_BLUE),
+    error: fg(VERMILLION),
+    selected: fg(Color::Black).bg(SKY_BLUE).add_modifier(Modifier::BOLD),
+    modal_bg: Color::Rgb(45, 45, 58),
+    folder: fg(YELLOW).add_modifier(Modifier::BOLD),
+    video: fg(SKY_BLUE),
+    audio: fg(REDDISH_PURPLE),
+    image: fg(BLUISH_GREEN),
+    archive: fg(VERMILLION),
+    other: fg(Color::Gray),
+};
+
+/// Like the deuteranopia preset, but reds look dark without working red cones,
+/// so errors and archives use the brighter orange
+const PROTANOPIA: Theme = Theme {
+    error: fg(ORANGE).add_modifier(Modifier::BOLD),
+    archive: fg(ORANGE),
+    ..DEUTERANOPIA
+};
+
+/// Folders are bold, selections reversed and matches underlined, with no
+/// colors at all.
+const MONOCHROME: Theme = Theme {
+    text: Style::new(),
+    plain: Style::new(),
+    dim: Style::new().add_modifier(Modifier::DIM),
+    accent: Style::new().add_modifier(Modifier::BOLD),
+    warning: Style::new().add_modifier(Modifier::BOLD),
+    success: Style::new(),
+    error: Style::new().add_modifier(Modifier::BOLD),
+    selected: Style::new().add_modifier(Modifier::REVERSED.union(Modifier::BOLD)),
+    modal_bg: Color::Reset,
+    folder: Style::new().add_modifier(Modifier::BOLD),
+    video: Style::new(),
+    audio: Style::new(),
+    image: Style::new(),
+    archive: Style::new(),
+    other: Style::new(),
+};
+
+static PRESET: Mutex<ColorPreset> = Mutex::new(ColorPreset::Default);
+
+pub fn preset() -> ColorPreset {
+    *PRESET.lock().unwrap()
+}
+
+/// Switches every tab to another preset.
+pub fn set_preset(preset: ColorPreset) {
+    *PRESET.lock().unwrap() = preset;
+}
+
+/// The theme of the current preset.
+pub fn current() -> &'static Theme {
+    match preset() {
+        ColorPreset::Default => &DEFAULT,
+        ColorPreset::Deuteranopia => &DEUTERANOPIA,
+        ColorPreset::Protanopia => &PROTANOPIA,
+        ColorPreset::Monochrome => &MONOCHROME,
+    }
+}
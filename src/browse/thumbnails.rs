@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use base64::Engine;
+use ratatui::layout::Rect;
+
+use crate::put::Client;
+
+/// Maximum number of thumbnails downloaded at the same time
+const MAX_CONCURRENT_LOADS: usize = 4;
+
+/// Where a thumbnail goes in the grid.
+#[derive(Clone, PartialEq)]
+pub struct ThumbnailCell {
+    pub file_id: i64,
+    pub url: String,
+    pub area: Rect,
+}
+
+enum Thumbnail {
+    Loading,
+    /// The image, base64 encoded for the terminal
+    Ready(Arc<str>),
+    Failed,
+}
+
+/// Returns true if the terminal can draw inline images. Only the iTerm2 image
+/// protocol is spoken, which takes the JPEG previews put.io serves as they are.
+pub fn terminal_supports_images() -> bool {
+    let program = std::env::var("TERM_PROGRAM").unwrap_or_default();
+    let terminal = std::env::var("LC_TERMINAL").unwrap_or_default();
+    matches!(program.as_str(), "iTerm.app" | "WezTerm" | "mintty") || terminal == "iTerm2"
+}
+
+/// Thumbnails for the grid view. Each one is downloaded in the background the
+/// first time its cell is on screen and drawn once it has arrived.
+#[derive(Default)]
+pub struct Thumbnails {
+    images: Arc<Mutex<HashMap<i64, Thumbnail>>>,
+    /// Cells as they were last drawn, and whether their image was ready
+    shown: Vec<(ThumbnailCell, bool)>,
+}
+
+impl Thumbnails {
+    /// Forgets what was drawn, so everything is drawn again next time. Needed
+    /// whenever something may have been drawn over the images.
+    pub fn invalidate(&mut self) {
+        self.shown.clear();
+    }
+
+    /// Returns true if a drawn image isn't where it belongs anymore. Images stay
+    /// on screen until something is drawn over them, so the screen has to be
+    /// cleared and drawn again before the grid can move.
+    pub fn needs_clear(&self, cells: &[ThumbnailCell]) -> bool {
+        self.shown
+            .iter()
+            .any(|(cell, ready)| *ready && !cells.contains(cell))
+    }
+
+    /// Starts loading the thumbnails of `cells` that aren't loaded yet and draws
+    /// the ones that are, if anything changed since the last call.
+    pub fn show(
+        &mut self,
+        out: &mut impl Write,
+        cells: &[ThumbnailCell],
+        client: &Client,
+    ) -> io::Result<()> {
+        let mut current = Vec::with_capacity(cells.len());
+        {
+            let mut images = self.images.lock().unwrap();
+            let mut loading = images
+                .values()
+                .filter(|image| matches!(image, Thumbnail::Loading))
+                .count();
+            for cell in cells {
+                if !images.contains_key(&cell.file_id) && loading < MAX_CONCURRENT_LOADS {
+                    images.insert(cell.file_id, Thumbnail::Loading);
+                    self.load(cell, client);
+                    loading += 1;
+                }
+                let ready = matches!(images.get(&cell.file_id), Some(Thumbnail::Ready(_)));
+                current.push((cell.clone(), ready));
+            }
+        }
+        if current == self.shown {
+            return Ok(());
+        }
+
+        let images = self.images.lock().unwrap();
+        for (cell, _) in &current {
+            if let Some(Thumbnail::Ready(data)) = images.get(&cell.file_id) {
+                write!(
+                    out,
+                    "\x1b[{};{}H\x1b]1337;File=inline=1;width={};height={};preserveAspectRatio=1:{}\x07",
+                    cell.area.y + 1,
+                    cell.area.x + 1,
+                    cell.area.width,
+                    cell.area.height,
+                    data
+                )?;
+            }
+        }
+        out.flush()?;
+        drop(images);
+        self.shown = current;
+        Ok(())
+    }
+
+    fn load(&self, cell: &ThumbnailCell, client: &Client) {
+        let images = Arc::clone(&self.images);
+        let client = client.clone();
+        let file_id = cell.file_id;
+        let url = cell.url.clone();
+        thread::spawn(move || {
+            let image = client
+                .get(url)
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.bytes())
+                .map(|bytes| {
+                    Thumbnail::Ready(
+                        base64::engine::general_purpose::STANDARD
+                            .encode(bytes)
+                            .into(),
+                    )
+                })
+                .unwrap_or(Thumbnail::Failed);
+            images.lock().unwrap().insert(file_id, image);
+        });
+    }
+}
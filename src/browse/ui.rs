@@ -1,14 +1,29 @@
+use std::time::Duration;
+
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::{Block, BorderType, Borders, Clear, List, ListItem, Padding, Paragraph},
     Frame,
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-use super::app::{file_actions_for, AppState, BrowserApp, FileAction, ModalState, SortField};
+use super::actions::{self, PaletteEntry};
+use super::app::{
+    file_actions_for, AppState, BrowserApp, FileAction, FilterForm, FolderPicker, ModalState,
+    FILTER_TYPES,
+};
+use super::journal::Journal;
+use super::theme;
+use super::thumbnails::ThumbnailCell;
+use crate::release;
 
-const MODAL_BG: Color = Color::Rgb(45, 45, 58);
+const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+/// Width of a grid cell, including the gap to the next one
+const GRID_CELL_WIDTH: u16 = 24;
+/// Height of a thumbnail in the grid, above the name
+const GRID_THUMBNAIL_HEIGHT: u16 = 6;
 
 pub fn draw(f: &mut Frame, app: &mut BrowserApp) {
     if matches!(app.app_state, AppState::Quitting) {
@@ -30,42 +45,189 @@ pub fn draw(f: &mut Frame, app: &mut BrowserApp) {
 
     // Draw modal overlays last
     match &app.modal {
-        ModalState::Loading => draw_spinner(f, app.tick, &app.spinner_label),
+        ModalState::Loading => {}
         ModalState::Error(msg) => draw_error_modal(f, msg.clone()),
+        ModalState::TimedOut { message, .. } => draw_timed_out_modal(f, message),
         ModalState::Success(msg) => draw_success_modal(f, msg.clone()),
-        ModalState::ConfirmDelete { file_name, .. } => draw_confirm_modal(f, file_name.clone()),
+        ModalState::ConfirmDelete { label, .. } => {
+            draw_confirm_modal(f, " Confirm Delete ", &format!("Delete {}?", label))
+        }
+        ModalState::ConfirmPruneTrash { label, .. } => draw_confirm_modal(
+            f,
+            " Empty Trash ",
+            &format!("Remove {} of old trash for good?", label),
+        ),
         ModalState::FileActions {
             file_name,
             file_type,
             selected,
             ..
         } => {
-            draw_file_actions_modal(f, file_name, file_type, *selected, app.is_search_results);
+            draw_file_actions_modal(
+                f,
+                file_name,
+                file_type,
+                *selected,
+                app.is_search_results,
+                app.read_only,
+                !app.open_with.is_empty(),
+            );
         }
         ModalState::Find { query } => draw_find_bar(f, query),
-        ModalState::SearchInput { query } => draw_search_input(f, query),
+        ModalState::SearchInput { query, .. } => {
+            draw_search_input(f, query, &app.search_history.pinned)
+        }
+        ModalState::FetchUrl { url } => draw_text_input(f, " Fetch URL into this folder ", url),
+        ModalState::CopyTo(picker) => draw_folder_picker(f, picker),
+        ModalState::ShareLink { expires, .. } => draw_text_input(
+            f,
+            " Link expires after (e.g. 7d, empty for never) ",
+            expires,
+        ),
+        ModalState::SaveView { path } => {
+            draw_text_input(f, " Save listing to (.txt or .html) ", path)
+        }
+        ModalState::GlobSelect { pattern } => {
+            draw_text_input(f, " Mark files matching (e.g. *.rar) ", pattern)
+        }
+        ModalState::DownloadTo { path, .. } => {
+            draw_download_prompt(f, path, &app.recent_dirs.recent)
+        }
+        ModalState::DownloadConflict { file_name, .. } => draw_conflict_modal(f, file_name),
+        ModalState::PickProfile { selected } => {
+            let names: Vec<&str> = app.profiles.iter().map(String::as_str).collect();
+            draw_picker_modal(f, " Open a tab for ", &names, *selected);
+        }
+        ModalState::OpenWith {
+            file_name,
+            selected,
+            ..
+        } => {
+            let names: Vec<&str> = app.open_with.iter().map(|o| o.name.as_str()).collect();
+            let title = format!(" Open {} with ", truncate(file_name, 40));
+            draw_picker_modal(f, &title, &names, *selected);
+        }
+        ModalState::CastDevices {
+            devices, selected, ..
+        } => {
+            let names: Vec<&str> = devices.iter().map(|d| d.name.as_str()).collect();
+            draw_picker_modal(f, " Cast to device ", &names, *selected);
+        }
+        ModalState::Casting => {
+            if let Some(session) = &app.cast_session {
+                draw_casting_overlay(f, &session.device.name, &session.file_name, session.paused);
+            }
+        }
+        ModalState::Welcome => draw_welcome_modal(f),
+        ModalState::Login { code, .. } => draw_login_modal(f, code, app.tick),
+        ModalState::SessionExpired => draw_session_expired_modal(f),
+        ModalState::Filter(form) => draw_filter_modal(f, form),
+        ModalState::Activity { scroll } => draw_activity(f, &app.journal, *scroll),
+        ModalState::CommandPalette { query, selected } => {
+            let entries = actions::palette_entries(app, query);
+            draw_command_palette(f, query, &entries, *selected);
+        }
         ModalState::None => {}
     }
+
+    draw_status(f, app);
 }
 
 fn draw_breadcrumb(f: &mut Frame, app: &BrowserApp, area: Rect) {
-    let crumb_style = Style::default()
-        .fg(Color::White)
-        .add_modifier(Modifier::BOLD);
-    let sep_style = Style::default().fg(Color::DarkGray);
+    let theme = theme::current();
+    let crumb_style = theme.text.add_modifier(Modifier::BOLD);
+    let sep_style = theme.dim;
 
     let mut spans: Vec<Span> = vec![Span::raw(" ")];
+    // Tabs only get a mention once there is more than one
+    if app.tabs.len() > 1 {
+        for (i, name) in app.tabs.iter().enumerate() {
+            let style = if i == app.active_tab {
+                theme.selected
+            } else {
+                sep_style
+            };
+            spans.push(Span::styled(
+                format!(" {}:{} ", i + 1, truncate(name, 20)),
+                style,
+            ));
+        }
+        spans.push(Span::raw("  "));
+    }
     for (i, entry) in app.breadcrumbs.iter().enumerate() {
         if i > 0 {
             spans.push(Span::styled("  ›  ", sep_style));
         }
         spans.push(Span::styled(truncate(&entry.name, 24), crumb_style));
     }
+    if app.filter.is_active() {
+        spans.push(Span::styled(
+            format!("  [filter: {}]", app.filter.describe()),
+            theme.accent,
+        ));
+    }
+    if !app.marked.is_empty() {
+        spans.push(Span::styled(
+            format!("  [{} marked]", app.marked.len()),
+            theme.warning,
+        ));
+    }
+    if let Some(changes) = app.changes.as_ref().filter(|c| c.is_fresh()) {
+        let mut parts = vec![];
+        if !changes.added.is_empty() {
+            parts.push(format!("+{} new", changes.added.len()));
+        }
+        if changes.removed > 0 {
+            parts.push(format!("−{} removed", changes.removed));
+        }
+        spans.push(Span::styled(
+            format!("  [{}]", parts.join(", ")),
+            theme.success,
+        ));
+    }
+    if app.pending_count.is_some() || app.pending_g {
+        let count = app.pending_count.map(|n| n.to_string()).unwrap_or_default();
+        let g = if app.pending_g { "g" } else { "" };
+        spans.push(Span::styled(format!("  {}{}", count, g), theme.text));
+    }
+    if let Some(rate_limit) = app.rate_limit {
+        // Only worth a mention once a good part of the budget is gone
+        if rate_limit.remaining * 2 < rate_limit.limit {
+            spans.push(Span::styled(
+                format!("  [API {}/{}]", rate_limit.remaining, rate_limit.limit),
+                theme.warning,
+            ));
+        }
+    }
+    if app
+        .colors_changed
+        .is_some_and(|at| at.elapsed() < Duration::from_secs(3))
+    {
+        spans.push(Span::styled(
+            format!("  [colors: {}]", theme::preset().name()),
+            theme.dim,
+        ));
+    }
+    if let Some((notice, at)) = &app.transfer_notice {
+        if at.elapsed() < Duration::from_secs(10) {
+            spans.push(Span::styled(format!("  [{}]", notice), theme.success));
+        }
+    }
+    if app.read_only {
+        spans.push(Span::styled("  [read-only]", theme.warning));
+    }
 
     f.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
 fn draw_file_list(f: &mut Frame, app: &mut BrowserApp, area: Rect) {
+    let theme = theme::current();
+    app.thumbnail_cells.clear();
+    if app.showing_grid() {
+        draw_file_grid(f, app, area);
+        return;
+    }
+
     let search = app.last_search.clone();
     let items: Vec<ListItem> = app
         .files
@@ -73,22 +235,51 @@ fn draw_file_list(f: &mut Frame, app: &mut BrowserApp, area: Rect) {
         .enumerate()
         .map(|(i, file)| {
             let cursor = if i == app.selected_index { ">>" } else { "  " };
-            let color = file_type_color(&file.file_type);
             let is_folder = file.file_type == "FOLDER";
-            let name_style = if is_folder {
-                Style::default().fg(color).add_modifier(Modifier::BOLD)
-            } else {
-                Style::default().fg(color)
-            };
+            let name_style = theme.file_type(&file.file_type);
             let size_str = if is_folder {
                 "—".to_string()
             } else {
                 file.size.to_string()
             };
-            let name_trunc = truncate(&file.name, 64);
-            let padding = " ".repeat(64usize.saturating_sub(name_trunc.chars().count()) + 1);
+            let name = if app.pretty_names {
+                release::pretty_name(&file.name)
+            } else {
+                file.name.clone()
+            };
+            let name_trunc = truncate(&name, 64);
+            let padding = " ".repeat(64usize.saturating_sub(name_trunc.width()) + 1);
 
-            let mut spans = vec![Span::raw(format!("{} ", cursor))];
+            let is_new = app
+                .changes
+                .as_ref()
+                .is_some_and(|c| c.is_fresh() && c.added.contains(&file.id));
+            let (mark, mark_style) = if app.marked.contains(&file.id) {
+                ("●", theme.warning)
+            } else if is_new {
+                ("+", theme.success)
+            } else {
+                (" ", theme.warning)
+            };
+            // Fully watched videos get a check, partially watched ones a play mark
+            let watch = if file.is_watched {
+                "✓ "
+            } else if file.start_from > 0 {
+                "▸ "
+            } else {
+                "  "
+            };
+            let pin = if app.pinned.contains(&file.id) && !app.is_search_results {
+                "📌"
+            } else {
+                "  "
+            };
+            let mut spans = vec![
+                Span::raw(cursor),
+                Span::styled(mark, mark_style),
+                Span::raw(pin),
+                Span::styled(watch, theme.dim),
+            ];
             if let Some(ref query) = search {
                 let match_style = name_style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
                 spans.extend(highlight_match(&name_trunc, query, name_style, match_style));
@@ -96,10 +287,23 @@ fn draw_file_list(f: &mut Frame, app: &mut BrowserApp, area: Rect) {
                 spans.push(Span::styled(name_trunc, name_style));
             }
             spans.push(Span::styled(padding, name_style));
-            spans.push(Span::styled(
-                format!("{:>10}", size_str),
-                Style::default().fg(Color::DarkGray),
-            ));
+            spans.push(Span::styled(format!("{:>10}", size_str), theme.dim));
+            // Search results come from anywhere, so show where each one lives.
+            // Paths appear as the background lookups fill the folder cache.
+            if app.is_search_results {
+                let used: usize = spans.iter().map(|s| s.width()).sum();
+                let room = (area.width as usize).saturating_sub(used + 2);
+                if room >= 8 {
+                    let path = app
+                        .folders
+                        .cached_path(file.parent_id)
+                        .unwrap_or_else(|| "…".to_string());
+                    spans.push(Span::styled(
+                        format!("  {}", truncate(&path, room)),
+                        theme.dim.add_modifier(Modifier::DIM),
+                    ));
+                }
+            }
 
             ListItem::new(Line::from(spans))
         })
@@ -107,72 +311,122 @@ fn draw_file_list(f: &mut Frame, app: &mut BrowserApp, area: Rect) {
 
     let list = List::new(items)
         .block(Block::default().borders(Borders::NONE))
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightCyan)
-                .fg(Color::Black)
-                .add_modifier(Modifier::BOLD),
-        );
+        .highlight_style(theme.selected);
 
     f.render_stateful_widget(list, area, &mut app.list_state);
 }
 
-fn draw_help_bar(f: &mut Frame, app: &BrowserApp, area: Rect) {
-    let k = Style::default()
-        .fg(Color::White)
-        .add_modifier(Modifier::BOLD);
-    let l = Style::default().fg(Color::DarkGray);
-    let sep = Span::styled("    ", l);
-
-    let sort_label = match app.sort_field {
-        SortField::Name => "Name    ",
-        SortField::Size => "Size    ",
-        SortField::Date => "Date    ",
-        SortField::Modified => "Modified",
+/// Lays the listing out in columns of names, each under its thumbnail when the
+/// terminal can show them. The thumbnails themselves are drawn after the frame,
+/// into the cells recorded in `app.thumbnail_cells`.
+fn draw_file_grid(f: &mut Frame, app: &mut BrowserApp, area: Rect) {
+    let theme = theme::current();
+    let thumbnails = app.thumbnails.is_some();
+    let cell_height = if thumbnails {
+        GRID_THUMBNAIL_HEIGHT + 1
+    } else {
+        1
     };
+    let columns = (area.width / GRID_CELL_WIDTH).max(1) as usize;
+    let rows = (area.height / cell_height).max(1) as usize;
+    app.grid_columns = columns;
 
-    // 4 columns, 2 rows. Key right-aligned per column, label left-aligned.
-    // Col 1: key_w=5  (↑↓/jk  /   Bksp)
-    // Col 2: key_w=8  (Enter/^O  /  x)
-    // Col 3: key_w=1  (s  /  r)
-    // Col 4: key_w=5  (^U/^D  /  ^F)
-    let row1 = Line::from(vec![
-        Span::styled("↑↓", k),
-        Span::styled("/", l),
-        Span::styled("jk", k),
-        Span::styled(format!("  {:<8}", "Navigate"), l),
-        sep.clone(),
-        Span::styled("Enter", k),
-        Span::styled("/", l),
-        Span::styled("^O", k),
-        Span::styled(format!("  {:<6}", "Open"), l),
-        sep.clone(),
-        Span::styled("s", k),
-        Span::styled(format!("  {:<8}", sort_label), l),
-        sep.clone(),
-        Span::styled("^U", k),
-        Span::styled("/", l),
-        Span::styled("^D", k),
-        Span::styled(format!("  {:<6}", "Scroll"), l),
-    ]);
-    let row2 = Line::from(vec![
-        Span::styled(format!("{:>5}", "Bksp"), k),
-        Span::styled(format!("  {:<8}", "Back"), l),
-        sep.clone(),
-        Span::styled(format!("{:>8}", "x"), k),
-        Span::styled(format!("  {:<6}", "Delete"), l),
-        sep.clone(),
-        Span::styled("r", k),
-        Span::styled(format!("  {:<8}", "Reverse"), l),
-        sep.clone(),
-        Span::styled(format!("{:>5}", "^F"), k),
-        Span::styled(format!("  {:<6}", "Search"), l),
-    ]);
+    // Scroll just enough to keep the selection in view
+    let selected_row = app.selected_index / columns;
+    if selected_row < app.grid_scroll {
+        app.grid_scroll = selected_row;
+    } else if selected_row >= app.grid_scroll + rows {
+        app.grid_scroll = selected_row + 1 - rows;
+    }
 
-    f.render_widget(
-        Paragraph::new(vec![row1, row2]).alignment(Alignment::Center),
-        area,
-    );
+    let name_width = GRID_CELL_WIDTH as usize - 3;
+    let first = app.grid_scroll * columns;
+    for (i, file) in app
+        .files
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(rows * columns)
+    {
+        let x = area.x + ((i % columns) as u16) * GRID_CELL_WIDTH;
+        let y = area.y + ((i / columns - app.grid_scroll) as u16) * cell_height;
+
+        let style = if i == app.selected_index {
+            theme.selected
+        } else {
+            theme.file_type(&file.file_type)
+        };
+        let mark = if app.marked.contains(&file.id) {
+            Span::styled("●", theme.warning)
+        } else {
+            Span::raw(" ")
+        };
+        let name = if app.pretty_names {
+            release::pretty_name(&file.name)
+        } else {
+            file.name.clone()
+        };
+        let name = truncate(&name, name_width);
+        let padding = " ".repeat(name_width.saturating_sub(name.width()));
+        let line = Line::from(vec![
+            mark,
+            Span::styled(format!("{}{}", name, padding), style),
+        ]);
+        let name_area = Rect::new(x, y + cell_height - 1, GRID_CELL_WIDTH - 1, 1);
+        f.render_widget(Paragraph::new(line), name_area.intersection(area));
+
+        if !thumbnails {
+            continue;
+        }
+        let thumbnail_area = Rect::new(x + 1, y, name_width as u16, GRID_THUMBNAIL_HEIGHT);
+        match &file.screenshot {
+            Some(url) if file.file_type == "IMAGE" => app.thumbnail_cells.push(ThumbnailCell {
+                file_id: file.id,
+                url: url.clone(),
+                area: thumbnail_area,
+            }),
+            // Everything else gets its type where the picture would be
+            _ => {
+                let label = Paragraph::new(file.file_type.to_lowercase())
+                    .alignment(Alignment::Center)
+                    .style(theme.dim);
+                let middle = Rect::new(x + 1, y + GRID_THUMBNAIL_HEIGHT / 2, name_width as u16, 1);
+                f.render_widget(label, middle.intersection(area));
+            }
+        }
+    }
+}
+
+fn draw_help_bar(f: &mut Frame, app: &BrowserApp, area: Rect) {
+    let theme = theme::current();
+    let k = theme.text.add_modifier(Modifier::BOLD);
+    let l = theme.dim;
+    let gap = 4;
+
+    // Fill the rows in order, leaving out whatever doesn't fit
+    let mut rows: Vec<Vec<Span>> = vec![vec![]];
+    let mut used = 0;
+    for (keys, label) in actions::help_entries(app) {
+        let width = keys.width() + 2 + label.width();
+        let needed = if used == 0 { width } else { width + gap };
+        if used + needed > area.width as usize {
+            if rows.len() == area.height as usize {
+                break;
+            }
+            rows.push(vec![]);
+            used = 0;
+        }
+        let row = rows.last_mut().unwrap();
+        if used > 0 {
+            row.push(Span::raw(" ".repeat(gap)));
+        }
+        row.push(Span::styled(keys, k));
+        row.push(Span::styled(format!("  {}", label), l));
+        used += if used == 0 { width } else { width + gap };
+    }
+
+    let lines: Vec<Line> = rows.into_iter().map(Line::from).collect();
+    f.render_widget(Paragraph::new(lines).alignment(Alignment::Center), area);
 }
 
 fn centered_rect(percent_x: u16, height: u16, r: Rect) -> Rect {
@@ -187,27 +441,117 @@ fn centered_rect(percent_x: u16, height: u16, r: Rect) -> Rect {
     }
 }
 
-fn draw_search_input(f: &mut Frame, query: &str) {
+fn draw_text_input(f: &mut Frame, title: &str, query: &str) {
+    let theme = theme::current();
     let area = centered_rect(50, 5, f.size());
     f.render_widget(Clear, area);
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .padding(Padding::symmetric(2, 1))
-        .title(" Search put.io ")
-        .style(Style::default().fg(Color::Cyan).bg(MODAL_BG));
+        .title(title)
+        .style(theme.accent.bg(theme.modal_bg));
     let inner = block.inner(area);
     f.render_widget(block, area);
     f.render_widget(
-        Paragraph::new(query).style(Style::default().fg(Color::White).bg(MODAL_BG)),
+        Paragraph::new(query).style(theme.text.bg(theme.modal_bg)),
         inner,
     );
-    let cursor_x =
-        (inner.x + query.chars().count() as u16).min(inner.x + inner.width.saturating_sub(1));
+    let cursor_x = (inner.x + query.width() as u16).min(inner.x + inner.width.saturating_sub(1));
     f.set_cursor(cursor_x, inner.y);
 }
 
+fn draw_search_input(f: &mut Frame, query: &str, pinned: &[String]) {
+    let theme = theme::current();
+    let area = centered_rect(50, 7, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::horizontal(2))
+        .title(" Search put.io ")
+        .style(theme.accent.bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let k = theme.text.add_modifier(Modifier::BOLD);
+    let l = theme.dim;
+    let mut pinned_spans = vec![];
+    for (i, query) in pinned.iter().take(9).enumerate() {
+        pinned_spans.push(Span::styled(format!("M-{}", i + 1), k));
+        pinned_spans.push(Span::styled(format!(" {}  ", truncate(query, 16)), l));
+    }
+    if pinned_spans.is_empty() {
+        pinned_spans.push(Span::styled("No pinned searches", l));
+    }
+
+    let lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(query, theme.text)),
+        Line::from(""),
+        Line::from(pinned_spans),
+        Line::from(vec![
+            Span::styled("↑↓", k),
+            Span::styled(" History  ", l),
+            Span::styled("^P", k),
+            Span::styled(" Pin/unpin", l),
+        ]),
+    ];
+    f.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(theme.modal_bg)),
+        inner,
+    );
+    let cursor_x = (inner.x + query.width() as u16).min(inner.x + inner.width.saturating_sub(1));
+    f.set_cursor(cursor_x, inner.y + 1);
+}
+
+fn draw_download_prompt(f: &mut Frame, path: &str, recent: &[String]) {
+    let theme = theme::current();
+    let shown = recent.len().min(5);
+    let area = centered_rect(50, shown as u16 + 7, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::horizontal(2))
+        .title(" Download to ")
+        .style(theme.accent.bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let k = theme.text.add_modifier(Modifier::BOLD);
+    let l = theme.dim;
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(Span::styled(path, theme.text)),
+        Line::from(""),
+    ];
+    if recent.is_empty() {
+        lines.push(Line::from(Span::styled("No recent directories", l)));
+    }
+    for dir in recent.iter().take(shown) {
+        let max_width = inner.width.saturating_sub(2) as usize;
+        lines.push(Line::from(Span::styled(
+            format!("  {}", truncate(dir, max_width)),
+            l,
+        )));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("Tab", k),
+        Span::styled(" Complete  ", l),
+        Span::styled("↑↓", k),
+        Span::styled(" Recent", l),
+    ]));
+    f.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(theme.modal_bg)),
+        inner,
+    );
+    let cursor_x = (inner.x + path.width() as u16).min(inner.x + inner.width.saturating_sub(1));
+    f.set_cursor(cursor_x, inner.y + 1);
+}
+
 fn draw_find_bar(f: &mut Frame, query: &str) {
+    let theme = theme::current();
     let size = f.size();
     let y = size.height.saturating_sub(1);
     let area = Rect {
@@ -218,42 +562,67 @@ fn draw_find_bar(f: &mut Frame, query: &str) {
     };
     f.render_widget(Clear, area);
     let line = Line::from(vec![
-        Span::styled(
-            "/",
-            Style::default()
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD),
-        ),
-        Span::styled(query, Style::default().fg(Color::White)),
+        Span::styled("/", theme.text.add_modifier(Modifier::BOLD)),
+        Span::styled(query, theme.text),
     ]);
     f.render_widget(Paragraph::new(line), area);
     // Place the real terminal cursor at the end of the query
-    let cursor_x = (1 + query.chars().count() as u16).min(size.width.saturating_sub(1));
+    let cursor_x = (1 + query.width() as u16).min(size.width.saturating_sub(1));
     f.set_cursor(cursor_x, y);
 }
 
-fn draw_spinner(f: &mut Frame, tick: u8, label: &str) {
-    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
-    let ch = FRAMES[tick as usize % FRAMES.len()];
+/// Stacks the operations in progress in the bottom right corner, newest at
+/// the bottom, each with its own spinner.
+fn draw_status(f: &mut Frame, app: &BrowserApp) {
+    let theme = theme::current();
+    let mut operations = app.status.snapshot();
+    // Covers the moment between an action being queued and its work starting
+    if operations.is_empty() && matches!(app.modal, ModalState::Loading) {
+        operations.push((app.spinner_label.clone(), None));
+    }
     let size = f.size();
-    let text = format!("{} {}", ch, label);
-    let area = Rect {
-        x: 1,
-        y: size.height.saturating_sub(1),
-        width: text.chars().count() as u16,
-        height: 1,
-    };
-    f.render_widget(
-        Paragraph::new(text).style(
-            Style::default()
-                .fg(Color::Yellow)
-                .add_modifier(Modifier::BOLD),
-        ),
-        area,
-    );
+    let shown = operations.len().min(size.height.saturating_sub(2) as usize);
+    let lines: Vec<String> = operations[operations.len() - shown..]
+        .iter()
+        .enumerate()
+        .map(|(i, (label, progress))| {
+            // Offset the frames so stacked spinners don't move in lockstep
+            let frame = (app.tick as usize + i * 3) % SPINNER_FRAMES.len();
+            match progress {
+                Some(percent) => format!(" {} {} {}% ", SPINNER_FRAMES[frame], label, percent),
+                None => format!(" {} {} ", SPINNER_FRAMES[frame], label),
+            }
+        })
+        .collect();
+
+    let width = lines
+        .iter()
+        .map(|line| line.width() as u16)
+        .max()
+        .unwrap_or(0)
+        .min(size.width);
+    for (i, line) in lines.iter().enumerate() {
+        let area = Rect {
+            x: size.width - width,
+            y: size.height - (lines.len() - i) as u16,
+            width,
+            height: 1,
+        };
+        f.render_widget(Clear, area);
+        f.render_widget(
+            Paragraph::new(line.as_str()).style(
+                theme
+                    .warning
+                    .bg(theme.modal_bg)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            area,
+        );
+    }
 }
 
 fn draw_error_modal(f: &mut Frame, msg: String) {
+    let theme = theme::current();
     let area = centered_rect(50, 7, f.size());
     f.render_widget(Clear, area);
     let block = Block::default()
@@ -261,16 +630,35 @@ fn draw_error_modal(f: &mut Frame, msg: String) {
         .border_type(BorderType::Rounded)
         .padding(Padding::symmetric(2, 1))
         .title(" Error ")
-        .style(Style::default().fg(Color::Red).bg(MODAL_BG));
+        .style(theme.error.bg(theme.modal_bg));
     let inner = block.inner(area);
     f.render_widget(block, area);
     let p = Paragraph::new(format!("{}\n\nPress any key to dismiss", msg))
         .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::Red).bg(MODAL_BG));
+        .style(theme.error.bg(theme.modal_bg));
+    f.render_widget(p, inner);
+}
+
+fn draw_timed_out_modal(f: &mut Frame, msg: &str) {
+    let theme = theme::current();
+    let area = centered_rect(50, 7, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::symmetric(2, 1))
+        .title(" Timed out ")
+        .style(theme.warning.bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let p = Paragraph::new(format!("{}\n\nEnter retry · any other key dismisses", msg))
+        .alignment(Alignment::Center)
+        .style(theme.warning.bg(theme.modal_bg));
     f.render_widget(p, inner);
 }
 
 fn draw_success_modal(f: &mut Frame, msg: String) {
+    let theme = theme::current();
     let area = centered_rect(40, 5, f.size());
     f.render_widget(Clear, area);
     let block = Block::default()
@@ -278,12 +666,12 @@ fn draw_success_modal(f: &mut Frame, msg: String) {
         .border_type(BorderType::Rounded)
         .padding(Padding::symmetric(2, 1))
         .title(" Done ")
-        .style(Style::default().fg(Color::Green).bg(MODAL_BG));
+        .style(theme.success.bg(theme.modal_bg));
     let inner = block.inner(area);
     f.render_widget(block, area);
     let p = Paragraph::new(msg.as_str())
         .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::Green).bg(MODAL_BG));
+        .style(theme.success.bg(theme.modal_bg));
     f.render_widget(p, inner);
 }
 
@@ -293,8 +681,11 @@ fn draw_file_actions_modal(
     file_type: &str,
     selected: usize,
     in_search_results: bool,
+    read_only: bool,
+    open_with: bool,
 ) {
-    let actions = file_actions_for(file_type, in_search_results);
+    let theme = theme::current();
+    let actions = file_actions_for(file_type, in_search_results, read_only, open_with);
     let height = actions.len() as u16 + 4; // borders + vertical padding
     let area = centered_rect(38, height, f.size());
     f.render_widget(Clear, area);
@@ -305,7 +696,7 @@ fn draw_file_actions_modal(
         .border_type(BorderType::Rounded)
         .padding(Padding::symmetric(1, 1))
         .title(title)
-        .style(Style::default().bg(MODAL_BG));
+        .style(Style::default().bg(theme.modal_bg));
     let inner = block.inner(area);
     f.render_widget(block, area);
 
@@ -316,22 +707,18 @@ fn draw_file_actions_modal(
             let is_sel = i == selected;
             let cursor = if is_sel { "▶" } else { " " };
             let (row_style, key_style) = if is_sel {
-                let s = Style::default()
-                    .bg(Color::LightCyan)
-                    .fg(Color::Black)
-                    .add_modifier(Modifier::BOLD);
+                let s = theme.selected;
                 (s, s)
             } else {
                 (
-                    Style::default().bg(MODAL_BG),
-                    Style::default().bg(MODAL_BG).fg(Color::DarkGray),
+                    Style::default().bg(theme.modal_bg),
+                    theme.dim.bg(theme.modal_bg),
                 )
             };
             let cursor_text = format!(" {} ", cursor);
             let key_text = format!("[{}] ", key);
             let label_text = label.to_string();
-            let content_width =
-                cursor_text.chars().count() + key_text.chars().count() + label_text.chars().count();
+            let content_width = cursor_text.width() + key_text.width() + label_text.width();
             let pad_width = inner.width.saturating_sub(content_width as u16) as usize;
 
             ListItem::new(Line::from(vec![
@@ -347,35 +734,445 @@ fn draw_file_actions_modal(
     f.render_widget(List::new(items), inner);
 }
 
-fn draw_confirm_modal(f: &mut Frame, file_name: String) {
+fn draw_activity(f: &mut Frame, journal: &Journal, scroll: usize) {
+    let theme = theme::current();
+    let size = f.size();
+    let area = centered_rect(80, size.height.saturating_sub(4), size);
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::horizontal(1))
+        .title(" Activity ")
+        .style(Style::default().bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    if journal.entries.is_empty() {
+        f.render_widget(
+            Paragraph::new("Nothing done yet this session").style(theme.dim),
+            inner,
+        );
+        return;
+    }
+
+    let dim = theme.dim;
+    let lines: Vec<Line> = journal
+        .entries
+        .iter()
+        .rev()
+        .skip(scroll)
+        .map(|entry| {
+            let at = entry.at.as_secs();
+            let (status, status_style) = match &entry.outcome {
+                Ok(_) => ("ok".to_string(), theme.success),
+                Err(e) => (format!("failed: {}", e), theme.error),
+            };
+            let op_width = (inner.width as usize).saturating_sub(40).max(10);
+            Line::from(vec![
+                Span::styled(format!("+{:02}:{:02}  ", at / 60, at % 60), dim),
+                Span::styled(
+                    format!(
+                        "{:<width$}",
+                        truncate(&entry.operation, op_width),
+                        width = op_width
+                    ),
+                    theme.text,
+                ),
+                Span::styled(format!("{:>7.1}s  ", entry.took.as_secs_f64()), dim),
+                Span::styled(truncate(&status, 22), status_style),
+            ])
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+fn draw_command_palette(f: &mut Frame, query: &str, entries: &[PaletteEntry], selected: usize) {
+    let theme = theme::current();
+    let area = centered_rect(50, 16, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::horizontal(1))
+        .title(" Commands ")
+        .style(Style::default().bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let prompt = format!("> {}", query);
+    f.render_widget(
+        Paragraph::new(prompt.as_str()).style(theme.text),
+        Rect { height: 1, ..inner },
+    );
+    let cursor_x = (inner.x + prompt.width() as u16).min(inner.x + inner.width.saturating_sub(1));
+    f.set_cursor(cursor_x, inner.y);
+
+    let list_area = Rect {
+        y: inner.y + 2,
+        height: inner.height.saturating_sub(2),
+        ..inner
+    };
+    if entries.is_empty() {
+        f.render_widget(
+            Paragraph::new("No matching commands").style(theme.dim),
+            list_area,
+        );
+        return;
+    }
+
+    // Keep the selection in view by scrolling a page at a time
+    let rows = list_area.height.max(1) as usize;
+    let offset = selected / rows * rows;
+    let items: Vec<ListItem> = entries
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .take(rows)
+        .map(|(i, entry)| {
+            let (row_style, key_style) = if i == selected {
+                let s = theme.selected;
+                (s, s)
+            } else {
+                (
+                    Style::default().bg(theme.modal_bg),
+                    theme.dim.bg(theme.modal_bg),
+                )
+            };
+            let key_text = format!(" {} ", entry.keys);
+            let label_width = (list_area.width as usize).saturating_sub(key_text.width() + 1);
+            let label_text = format!(" {}", truncate(&entry.label, label_width));
+            let pad_width =
+                (list_area.width as usize).saturating_sub(label_text.width() + key_text.width());
+
+            ListItem::new(Line::from(vec![
+                Span::styled(label_text, row_style),
+                Span::styled(" ".repeat(pad_width), row_style),
+                Span::styled(key_text, key_style),
+            ]))
+        })
+        .collect();
+    f.render_widget(List::new(items), list_area);
+}
+
+/// Draws a list of names to choose one from, such as cast devices.
+fn draw_picker_modal(f: &mut Frame, title: &str, names: &[&str], selected: usize) {
+    let theme = theme::current();
+    let height = names.len() as u16 + 4; // borders + vertical padding
+    let area = centered_rect(38, height, f.size());
+    f.render_widget(Clear, area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::symmetric(1, 1))
+        .title(title)
+        .style(Style::default().bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let items: Vec<ListItem> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let is_sel = i == selected;
+            let cursor = if is_sel { "▶" } else { " " };
+            let row_style = if is_sel {
+                theme.selected
+            } else {
+                Style::default().bg(theme.modal_bg)
+            };
+            let text = format!(" {} {}", cursor, name);
+            let pad_width = (inner.width as usize).saturating_sub(text.width());
+            ListItem::new(Line::from(vec![
+                Span::styled(text, row_style),
+                Span::styled(" ".repeat(pad_width), row_style),
+            ]))
+        })
+        .collect();
+
+    f.render_widget(List::new(items), inner);
+}
+
+fn draw_folder_picker(f: &mut Frame, picker: &FolderPicker) {
+    let theme = theme::current();
+    let size = f.size();
+    let area = centered_rect(60, size.height.saturating_sub(6).min(20), size);
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::horizontal(1))
+        .title(format!(" Copy {} to ", truncate(&picker.file_name, 40)))
+        .style(Style::default().bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let k = theme.text.add_modifier(Modifier::BOLD);
+    let l = theme.dim;
+    let path: Vec<&str> = picker.path.iter().map(|(_, name)| name.as_str()).collect();
+    let mut lines = vec![
+        Line::from(Span::styled(
+            truncate(&path.join(" › "), inner.width as usize),
+            theme.accent,
+        )),
+        Line::from(""),
+    ];
+
+    let rows = (inner.height as usize).saturating_sub(4);
+    if picker.folders.is_empty() {
+        lines.push(Line::from(Span::styled("No subfolders", l)));
+    }
+    // Keep the selection in view
+    let first = picker.selected.saturating_sub(rows.saturating_sub(1));
+    for (i, folder) in picker.folders.iter().enumerate().skip(first).take(rows) {
+        let style = if i == picker.selected {
+            theme.selected
+        } else {
+            theme.text
+        };
+        lines.push(Line::from(Span::styled(
+            format!(" {} ", truncate(&folder.name, inner.width as usize - 2)),
+            style,
+        )));
+    }
+    while lines.len() < inner.height as usize - 1 {
+        lines.push(Line::from(""));
+    }
+    lines.push(Line::from(vec![
+        Span::styled("Enter", k),
+        Span::styled(" Open  ", l),
+        Span::styled("Bksp", k),
+        Span::styled(" Up  ", l),
+        Span::styled("c", k),
+        Span::styled(" Copy here  ", l),
+        Span::styled("Esc", k),
+        Span::styled(" Cancel", l),
+    ]));
+    f.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(theme.modal_bg)),
+        inner,
+    );
+}
+
+fn draw_casting_overlay(f: &mut Frame, device_name: &str, file_name: &str, paused: bool) {
+    let theme = theme::current();
+    let area = centered_rect(50, 8, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::symmetric(2, 1))
+        .title(format!(" Casting to {} ", truncate(device_name, 32)))
+        .style(theme.accent.bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let status = if paused { "Paused" } else { "Playing" };
+    let k = theme.text.add_modifier(Modifier::BOLD);
+    let l = theme.dim;
+    let lines = vec![
+        Line::from(Span::styled(
+            truncate(file_name, inner.width as usize),
+            theme.text,
+        )),
+        Line::from(Span::styled(status, theme.success)),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("Space", k),
+            Span::styled(" Pause  ", l),
+            Span::styled("←→", k),
+            Span::styled(" Seek 30s  ", l),
+            Span::styled("s", k),
+            Span::styled(" Stop  ", l),
+            Span::styled("Esc", k),
+            Span::styled(" Hide", l),
+        ]),
+    ];
+    f.render_widget(
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .style(Style::default().bg(theme.modal_bg)),
+        inner,
+    );
+}
+
+fn draw_welcome_modal(f: &mut Frame) {
+    let theme = theme::current();
+    let area = centered_rect(50, 9, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::symmetric(2, 1))
+        .title(" Welcome to Kaput ")
+        .style(theme.accent.bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let p = Paragraph::new(
+        "Kaput is not linked to a Put.io account yet.\n\n\
+        Press Enter to log in with a code from put.io/link\n\n\
+        [Enter] Log in  [q] Quit",
+    )
+    .alignment(Alignment::Center)
+    .style(theme.text.bg(theme.modal_bg));
+    f.render_widget(p, inner);
+}
+
+fn draw_filter_modal(f: &mut Frame, form: &FilterForm) {
+    let theme = theme::current();
+    let area = centered_rect(50, 10, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::symmetric(2, 1))
+        .title(" Filter ")
+        .style(theme.accent.bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let file_type = match form.type_index {
+        0 => "any",
+        i => FILTER_TYPES[i - 1],
+    };
+    let rows = [
+        ("Type", format!("‹ {} ›", file_type)),
+        ("Min size", form.min_size.clone()),
+        ("From", form.created_from.clone()),
+        ("Until", form.created_until.clone()),
+    ];
+    let mut lines: Vec<Line> = rows
+        .iter()
+        .enumerate()
+        .map(|(i, (label, value))| {
+            let style = if i == form.field {
+                theme.text.add_modifier(Modifier::BOLD)
+            } else {
+                theme.plain
+            };
+            Line::from(vec![
+                Span::styled(format!("{:<10}", label), theme.dim),
+                Span::styled(value.clone(), style),
+            ])
+        })
+        .collect();
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "↑↓ Field  ←→ Type  Enter Apply  Del Clear  Esc Cancel",
+        theme.dim,
+    )));
+    f.render_widget(
+        Paragraph::new(lines).style(Style::default().bg(theme.modal_bg)),
+        inner,
+    );
+
+    if form.field > 0 {
+        let value = &rows[form.field].1;
+        let cursor_x =
+            (inner.x + 10 + value.width() as u16).min(inner.x + inner.width.saturating_sub(1));
+        f.set_cursor(cursor_x, inner.y + form.field as u16);
+    }
+}
+
+fn draw_session_expired_modal(f: &mut Frame) {
+    let theme = theme::current();
     let area = centered_rect(50, 7, f.size());
     f.render_widget(Clear, area);
     let block = Block::default()
         .borders(Borders::ALL)
         .border_type(BorderType::Rounded)
         .padding(Padding::symmetric(2, 1))
-        .title(" Confirm Delete ")
-        .style(Style::default().fg(Color::Yellow).bg(MODAL_BG));
+        .title(" Session expired ")
+        .style(theme.warning.bg(theme.modal_bg));
     let inner = block.inner(area);
     f.render_widget(block, area);
-    let p = Paragraph::new(format!("Delete \"{}\"?\n\n[y] Yes  [n] No", file_name))
+    let p = Paragraph::new("Your Put.io session has expired.\n\n[L] Log in again  [Esc] Dismiss")
         .alignment(Alignment::Center)
-        .style(Style::default().fg(Color::Yellow).bg(MODAL_BG));
+        .style(theme.warning.bg(theme.modal_bg));
     f.render_widget(p, inner);
 }
 
-fn file_type_color(file_type: &str) -> Color {
-    match file_type {
-        // Folders: bright warm yellow — visually dominant
-        "FOLDER" => Color::LightYellow,
-        // Files: standard (non-bright) colors, clearly subordinate to folders
-        "VIDEO" => Color::Green,
-        "AUDIO" => Color::Magenta,
-        "IMAGE" => Color::Cyan,
-        "ARCHIVE" => Color::Red,
-        "PDF" => Color::Red,
-        _ => Color::Gray,
-    }
+fn draw_login_modal(f: &mut Frame, code: &str, tick: u8) {
+    let theme = theme::current();
+    let area = centered_rect(50, 11, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::symmetric(2, 1))
+        .title(" Log in ")
+        .style(theme.accent.bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let l = theme.text;
+    let lines = vec![
+        Line::from(Span::styled(
+            "Go to https://put.io/link and enter the code:",
+            l,
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            code.to_string(),
+            theme.warning.add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(Span::styled(
+            format!(
+                "{} Waiting for link...",
+                SPINNER_FRAMES[tick as usize % SPINNER_FRAMES.len()]
+            ),
+            theme.dim,
+        )),
+        Line::from(""),
+        Line::from(Span::styled("[Esc] Cancel", theme.dim)),
+    ];
+    f.render_widget(
+        Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .style(Style::default().bg(theme.modal_bg)),
+        inner,
+    );
+}
+
+fn draw_confirm_modal(f: &mut Frame, title: &str, question: &str) {
+    let theme = theme::current();
+    let area = centered_rect(50, 7, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::symmetric(2, 1))
+        .title(title)
+        .style(theme.warning.bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let p = Paragraph::new(format!("{}\n\n[y] Yes  [n] No", question))
+        .alignment(Alignment::Center)
+        .style(theme.warning.bg(theme.modal_bg));
+    f.render_widget(p, inner);
+}
+
+fn draw_conflict_modal(f: &mut Frame, file_name: &str) {
+    let theme = theme::current();
+    let area = centered_rect(50, 7, f.size());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_type(BorderType::Rounded)
+        .padding(Padding::symmetric(2, 1))
+        .title(" File exists ")
+        .style(theme.warning.bg(theme.modal_bg));
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let p = Paragraph::new(format!(
+        "\"{}\" already exists.\n\n[o] Overwrite  [r] Resume  [n] New name  [s] Skip",
+        truncate(file_name, inner.width.saturating_sub(20) as usize)
+    ))
+    .alignment(Alignment::Center)
+    .style(theme.warning.bg(theme.modal_bg));
+    f.render_widget(p, inner);
 }
 
 /// Splits `name` into up to three spans: text before the match, the matched
@@ -403,28 +1200,109 @@ fn highlight_match(name: &str, query: &str, base: Style, highlight: Style) -> Ve
     vec![Span::styled(name.to_string(), base)]
 }
 
-fn truncate(s: &str, max_chars: usize) -> String {
-    let count = s.chars().count();
-    if count <= max_chars {
+fn truncate(s: &str, max_width: usize) -> String {
+    let width = s.width();
+    if width <= max_width {
         s.to_string()
-    } else if max_chars == 0 {
+    } else if max_width == 0 {
         String::new()
-    } else if max_chars == 1 {
+    } else if max_width == 1 {
         "…".to_string()
     } else {
         if let Some(dot) = s.rfind('.') {
             if dot > 0 && dot < s.len() - 1 {
                 let (base, ext) = s.split_at(dot);
-                let ext_chars = ext.chars().count();
-                if ext_chars < max_chars {
-                    let base_chars = max_chars - ext_chars - 1;
-                    let base_trunc: String = base.chars().take(base_chars).collect();
+                let ext_width = ext.width();
+                if ext_width < max_width {
+                    let base_trunc = take_width(base, max_width - ext_width - 1);
                     return format!("{}…{}", base_trunc, ext);
                 }
             }
         }
 
-        let truncated: String = s.chars().take(max_chars.saturating_sub(1)).collect();
-        format!("{}…", truncated)
+        format!("{}…", take_width(s, max_width - 1))
+    }
+}
+
+/// Returns the longest prefix of `s` that fits in `max_width` terminal columns.
+/// Wide characters such as CJK and emoji take two columns.
+fn take_width(s: &str, max_width: usize) -> String {
+    let mut width = 0;
+    s.chars()
+        .take_while(|c| {
+            width += c.width().unwrap_or(0);
+            width <= max_width
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_keeps_names_that_fit() {
+        assert_eq!(truncate("映画.mkv", 8), "映画.mkv");
+        assert_eq!(truncate("🎬.mp4", 6), "🎬.mp4");
+    }
+
+    #[test]
+    fn truncate_cjk_keeps_extension() {
+        let truncated = truncate("映画の予告編.mkv", 10);
+        assert_eq!(truncated, "映画….mkv");
+        assert!(truncated.width() <= 10);
+    }
+
+    #[test]
+    fn truncate_emoji() {
+        let truncated = truncate("🎬🍿🎥🎞 night", 6);
+        assert_eq!(truncated, "🎬🍿…");
+        assert_eq!(truncated.width(), 5);
+    }
+
+    #[test]
+    fn truncate_never_exceeds_width() {
+        let names = [
+            "映画の予告編.mkv",
+            "🎬🍿🎥🎞 night.mp4",
+            "Ame\u{301}lie (2001).mkv",
+            "한국어 파일 이름",
+            "plain name.txt",
+        ];
+        for name in names {
+            for max_width in 0..=name.width() + 1 {
+                let truncated = truncate(name, max_width);
+                assert!(
+                    truncated.width() <= max_width,
+                    "{:?} at {} is {:?}",
+                    name,
+                    max_width,
+                    truncated
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn truncate_tiny_widths() {
+        assert_eq!(truncate("映画.mkv", 0), "");
+        assert_eq!(truncate("映画.mkv", 1), "…");
+        assert_eq!(truncate("a", 1), "a");
+        assert_eq!(truncate("", 0), "");
+    }
+
+    #[test]
+    fn take_width_does_not_split_wide_characters() {
+        assert_eq!(take_width("日本語", 3), "日");
+        assert_eq!(take_width("日本語", 4), "日本");
+        assert_eq!(take_width("日本語", 1), "");
+        assert_eq!(take_width("a日b", 2), "a");
+    }
+
+    #[test]
+    fn take_width_keeps_combining_marks() {
+        // "é" as "e" followed by a combining acute accent takes one column
+        assert_eq!(take_width("Ame\u{301}lie", 3), "Ame\u{301}");
+        assert_eq!(take_width("e\u{301}", 1), "e\u{301}");
     }
 }
@@ -0,0 +1,1057 @@
+use clap::{value_parser, Arg, Command};
+use std::path::PathBuf;
+
+/// Builds the full command tree. Also included by `build.rs` to generate man pages,
+/// so it must only depend on clap and std.
+pub fn cli() -> Command {
+    Command::new("kaput")
+        .about("The unofficial CLI for Put.io")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .disable_help_subcommand(true)
+        .arg(
+            Arg::new("proxy")
+            .long("proxy")
+            .help("Proxy to send requests through, e.g. http://host:3128 or socks5://host:1080")
+            .global(true)
+            .required(false)
+        )
+        .arg(
+            Arg::new("profile")
+            .long("profile")
+            .help("Use the account of a profile from the config instead of the default one")
+            .global(true)
+            .required(false)
+        )
+        .arg(
+            Arg::new("ca-bundle")
+            .long("ca-bundle")
+            .help("PEM file with extra root certificates to trust")
+            .value_parser(value_parser!(PathBuf))
+            .global(true)
+            .required(false)
+        )
+        .subcommand(
+            Command::new("login")
+                .about("Login to your Put.io account")
+                .long_about(
+                    "Logs into your Put.io by saving an auth token locally on your device."
+                )
+        )
+        .subcommand(
+            Command::new("logout")
+                .about("Logout of your account")
+                .long_about(
+                    "Logs out of your Put.io account by removing the auth token saved on your device."
+                )
+        )
+        .subcommand(
+            Command::new("files")
+                .about("Manage your files")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("List your files and folders")
+                        .long_about("Lists your files and folders.")
+                        .arg(
+                            Arg::new("FOLDER_ID")
+                            .help("Lists the contents of a folder (optional)")
+                            .value_parser(value_parser!(i64))
+                            .required(false)
+                            .num_args(1)
+                        )
+                        .arg(
+                            Arg::new("self")
+                            .short('s')
+                            .long("self")
+                            .help("If set, returns the info for the folder itself in JSON format")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("json")
+                            .long("json")
+                            .help("If set, returns the output in JSON format")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("pretty-names")
+                            .long("pretty-names")
+                            .help("Show cleaned-up release names, e.g. \"Show Name S02E05 [1080p].mkv\"")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("search")
+                        .about("Search you and your friend's files")
+                        .long_about("Searches you and your friend's files.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("QUERY")
+                            .required(true)
+                            .help("Keyword(s) to search for (required)")
+                        )
+                        .arg(
+                            Arg::new("pretty-names")
+                            .long("pretty-names")
+                            .help("Show cleaned-up release names, e.g. \"Show Name S02E05 [1080p].mkv\"")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("url")
+                        .about("Generate a URl for downloading a file or folder")
+                        .long_about("Generates a URl for downloading a file or folder.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FILE_ID")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                            .help("ID of a file or folder (required)")
+                        )
+                )
+                .subcommand(
+                    Command::new("download")
+                        .about("Download a file or folder")
+                        .long_about("Downloads a file or folder from your account to your device. Files that already exist locally are resumed, unless --overwrite, --skip-existing or --rename is given. An interrupted recursive download continues where it stopped when the same command is run again.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("TARGET")
+                            .help("File ID or path on Put.io (e.g. 12345 or Movies/film.mkv)")
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("path")
+                            .short('p')
+                            .long("path")
+                            .help("Path to download the file(s) to [default: download_dir from the config, else the current directory]")
+                            .required(false).num_args(1)
+                        )
+                        .arg(
+                            Arg::new("recursive")
+                            .short('r')
+                            .long("recursive")
+                            .help("Download the contents of a folder recursively without creating a zip")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("no-replace")
+                            .long("no-replace")
+                            .help("Disable character replacement of files with illegal characters")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("exclude")
+                            .short('x')
+                            .long("exclude")
+                            .help("When downloading recursively, skip files and folders matching this glob pattern (can be repeated)")
+                            .required(false)
+                            .action(clap::ArgAction::Append)
+                        )
+                        .arg(
+                            Arg::new("ignore-history")
+                            .long("ignore-history")
+                            .help("When downloading recursively, also download files that were downloaded before")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("overwrite")
+                            .long("overwrite")
+                            .help("Replace files that already exist locally instead of resuming them")
+                            .required(false)
+                            .num_args(0)
+                            .conflicts_with_all(["skip-existing", "rename"])
+                        )
+                        .arg(
+                            Arg::new("skip-existing")
+                            .long("skip-existing")
+                            .help("Leave files that already exist locally alone")
+                            .required(false)
+                            .num_args(0)
+                            .conflicts_with("rename")
+                        )
+                        .arg(
+                            Arg::new("rename")
+                            .long("rename")
+                            .help("Save under a new name, e.g. \"Movie (1).mkv\", when a file already exists locally")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("no-preflight")
+                            .long("no-preflight")
+                            .help("Start without checking that the destination has enough free space")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("extract")
+                            .long("extract")
+                            .help("Unpack downloaded archives into a folder next to them, using the extractors from the config")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("fetch-url")
+                        .about("Have Put.io download a URL into a folder")
+                        .long_about("Asks Put.io to fetch an HTTP or FTP URL into a folder on your account, so the file never passes through your own connection.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("URL")
+                            .help("HTTP or FTP URL to fetch (required)")
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("folder")
+                            .short('f')
+                            .long("folder")
+                            .help("Folder ID or path on Put.io to save to instead of the root folder")
+                            .required(false)
+                        )
+                )
+                .subcommand(
+                    Command::new("delete")
+                        .about("Delete file(s)")
+                        .long_about("Deletes the specified file(s) on your account.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FILE_ID")
+                            .required(true)
+                            .help("ID(s) of a file, separated by commas (required)")
+                        )
+                        .arg(
+                            Arg::new("yes")
+                            .short('y')
+                            .long("yes")
+                            .help("Delete without asking for confirmation")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("upload")
+                        .about("Upload file(s) to your account")
+                        .long_about("Uploads file(s) to your account. It will automatically switch to the resumable upload protocol if the file size is greater than or equal to 50 MB. A folder is uploaded with all of its subfolders.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("parent_id")
+                                .short('p')
+                                .long("parent")
+                                .help("ID of a Put folder to upload to instead of the root folder")
+                                .required(false)
+                        )
+                        .arg(
+                            Arg::new("is_silent")
+                                .short('s')
+                                .long("silent")
+                                .help("Run CURL in silent mode")
+                                .required(false)
+                                .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("force")
+                                .long("force")
+                                .help("Upload even if it won't fit in your remaining storage")
+                                .required(false)
+                                .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("sidecars")
+                                .long("sidecars")
+                                .help("When uploading a folder, add a manifest of checksums and modification times to each folder, which recursive downloads use to restore those times and check the files")
+                                .required(false)
+                                .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("PATH")
+                                .required(true)
+                                .help("Valid paths of files or a folder to upload")
+                                .value_parser(value_parser!(PathBuf))
+                        )
+                )
+                .subcommand(
+                    Command::new("move")
+                        .about("Move files")
+                        .long_about("Moves a file to a different parent folder.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FILE_ID")
+                            .help("ID of the file to move (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true))
+                        .arg(
+                            Arg::new("PARENT_ID")
+                            .help("ID of the new parent folder (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("cp")
+                        .about("Copy files")
+                        .long_about("Copies a file or folder into another folder. If Put.io can't copy it on its side, a file is downloaded and uploaded again instead.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FILE_ID")
+                            .help("ID of the file to copy (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true))
+                        .arg(
+                            Arg::new("PARENT_ID")
+                            .help("ID of the folder to copy to (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("rename")
+                        .about("Rename files")
+                        .long_about("Renames a file.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FILE_ID")
+                            .help("ID of the file to rename (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("NAME")
+                            .help("New name for the file (required)")
+                            .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("rename-bulk")
+                        .about("Rename the files in a folder from their release names")
+                        .long_about("Renames every file in a folder using a pattern filled from its release name, e.g. \"{title} - S{season}E{episode}.{ext}\". Placeholders: {title}, {year}, {season}, {episode}, {resolution}, {group}, {ext}, {name}. The new names are shown for confirmation first.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FOLDER")
+                            .help("Folder ID or path on Put.io (required)")
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("pattern")
+                            .long("pattern")
+                            .help("Pattern for the new names (required)")
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                            .long("dry-run")
+                            .help("Only show the new names")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("yes")
+                            .short('y')
+                            .long("yes")
+                            .help("Rename without asking for confirmation")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("extractions")
+                        .about("List active extractions")
+                        .long_about("Lists active extractions.")
+                )
+                .subcommand(
+                    Command::new("extract")
+                        .about("Extract ZIP and RAR archives")
+                        .long_about("Extracts ZIP and RAR archives.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FILE_ID")
+                            .help("ID of the file to extract (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("manifest")
+                        .about("Save a manifest of a folder tree")
+                        .long_about("Records the path, size and CRC32 of every file in a folder tree to a JSON manifest, which can later be checked against a local copy with `verify-manifest`.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FOLDER")
+                            .help("Folder ID or path on Put.io (e.g. 12345 or Movies)")
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("out")
+                            .short('o')
+                            .long("out")
+                            .help("File to write the manifest to")
+                            .value_parser(value_parser!(PathBuf))
+                            .default_value("manifest.json")
+                        )
+                )
+                .subcommand(
+                    Command::new("verify-manifest")
+                        .about("Check a local copy of a folder against a manifest")
+                        .long_about("Checks that every file recorded in a manifest exists locally with the same size and CRC32.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("MANIFEST")
+                            .help("Path to a manifest created with `files manifest` (required)")
+                            .value_parser(value_parser!(PathBuf))
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("path")
+                            .short('p')
+                            .long("path")
+                            .help("Local copy of the folder (defaults to ./<folder name>)")
+                            .value_parser(value_parser!(PathBuf))
+                            .required(false)
+                        )
+                        .arg(
+                            Arg::new("quick")
+                            .short('q')
+                            .long("quick")
+                            .help("Only compare file sizes, skipping CRC32 checks")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("export")
+                        .about("Export a folder listing as CSV or TSV")
+                        .long_about("Writes the path, ID, type, size, creation date and CRC32 of every entry in a folder as CSV or TSV, for spreadsheets and audits. With --recursive, the whole tree is exported, with rows written as each folder is listed.")
+                        .arg(
+                            Arg::new("FOLDER")
+                            .help("Folder ID or path on Put.io")
+                            .default_value("/")
+                        )
+                        .arg(
+                            Arg::new("format")
+                            .short('f')
+                            .long("format")
+                            .help("Output format")
+                            .value_parser(["csv", "tsv"])
+                            .default_value("csv")
+                        )
+                        .arg(
+                            Arg::new("recursive")
+                            .short('r')
+                            .long("recursive")
+                            .help("Include the contents of every subfolder")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("out")
+                            .short('o')
+                            .long("out")
+                            .help("File to write to instead of printing")
+                            .value_parser(value_parser!(PathBuf))
+                            .required(false)
+                        )
+                )
+                .subcommand(
+                    Command::new("playlist")
+                        .about("Create a playlist of a folder's videos")
+                        .long_about("Prints a playlist of stream URLs for the videos and audio files in a folder, ordered by episode number, for playing a whole season in one go.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FOLDER")
+                            .help("Folder ID or path on Put.io (e.g. 12345 or Shows/Season 1)")
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("format")
+                            .short('f')
+                            .long("format")
+                            .help("Playlist format")
+                            .value_parser(["m3u8", "pls"])
+                            .default_value("m3u8")
+                        )
+                        .arg(
+                            Arg::new("out")
+                            .short('o')
+                            .long("out")
+                            .help("File to write the playlist to instead of printing it")
+                            .value_parser(value_parser!(PathBuf))
+                            .required(false)
+                        )
+                )
+                .subcommand(
+                    Command::new("play")
+                        .about("Stream a video file")
+                        .long_about(
+                            "Plays a video file using MPV, resuming from where you last stopped.\n\
+                            If you do not have MPV installed, visit https://mpv.io/installation/.",
+                        )
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FILE_ID")
+                            .help("ID of a video file (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("from-start")
+                            .long("from-start")
+                            .help("Ignore the saved playback position")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("transfers")
+                .about("Manage your transfers")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("List the current transfers on your account")
+                        .long_about("Lists the current transfers on your account, including seeding ratio, seeding time and tracker status.")
+                        .arg(
+                            Arg::new("json")
+                            .long("json")
+                            .help("If set, returns the output in JSON format")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("watch")
+                            .short('w')
+                            .long("watch")
+                            .help("Keep the list on screen and update it as transfers start, finish or fail")
+                            .required(false)
+                            .num_args(0)
+                            .conflicts_with("json")
+                        )
+                        .arg(
+                            Arg::new("interval")
+                            .long("interval")
+                            .value_name("SECONDS")
+                            .help("With --watch, how often to check the account's event feed")
+                            .value_parser(value_parser!(u64).range(1..))
+                            .default_value("5")
+                            .required(false)
+                        )
+                )
+                .subcommand(
+                    Command::new("set")
+                        .about("Change a transfer's settings")
+                        .long_about("Changes the settings of a running transfer.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("TRANSFER_ID")
+                            .help("ID of a transfer (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("stop-seeding")
+                            .long("stop-seeding")
+                            .help("Stop seeding the transfer. Does not remove its files.")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("add")
+                        .about("Add new transfer with URL")
+                        .long_about("Adds new transfers to your account with a URL. A path to a local .torrent file can also be given, in which case you can choose which of its files to keep.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("URL")
+                            .help("URL or local .torrent file to transfer (required)")
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("callback_url")
+                            .long("callback-url")
+                            .help("URL to notify when the transfer completes (defaults to `transfer_callback_url` in the config). Not available for local .torrent files")
+                            .required(false)
+                        )
+                        .arg(
+                            Arg::new("force")
+                            .long("force")
+                            .help("For local .torrent files, add the transfer even if it won't fit in your remaining storage")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("wait")
+                            .short('w')
+                            .long("wait")
+                            .help("For local .torrent files, wait for the transfer to finish and remove deselected files")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("parent_id")
+                            .short('p')
+                            .long("parent")
+                            .value_parser(value_parser!(i64))
+                            .help("ID of a Put folder to upload to instead of the root folder")
+                            .required(false)
+                        )
+                        .arg(
+                            Arg::new("auto-folder")
+                            .long("auto-folder")
+                            .value_name("TEMPLATE")
+                            .help("Save into a folder named from the release name, created if needed, e.g. \"TV/{title}/Season {season}\". Placeholders: {title}, {year}, {season}, {episode}")
+                            .required(false)
+                            .conflicts_with("parent_id")
+                        )
+                )
+                .subcommand(
+                    Command::new("cancel")
+                        .about("Cancel or remove transfers")
+                        .long_about("Cancels or removes transfers on your account. If transfer is in SEEDING state, stops seeding. Otherwise, it removes the transfer entry. Does not remove their files.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("TRANSFER_ID")
+                            .help("ID of a transfer (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("retry")
+                        .about("Retry failed transfer")
+                        .long_about("Retries failed transfers.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("TRANSFER_ID")
+                            .help("ID of a transfer (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("remove")
+                        .about("Remove transfer(s)")
+                        .long_about("Removes transfer(s).")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("TRANSFER_ID")
+                            .help("ID of a transfer (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                )
+                .subcommand(
+                    Command::new("clean")
+                        .about("Clear all finished transfers")
+                        .long_about(
+                            "Clears all finished transfers on your account. Does not remove files."
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("torrent")
+                .about("Work with torrent files")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("inspect")
+                        .about("Show what a torrent or magnet link contains")
+                        .long_about("Prints the name, total size, piece size, trackers and files of a local .torrent file without contacting Put.io. Magnet links only carry a name, hash and trackers, and sometimes a size.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("TARGET")
+                            .help("Path to a .torrent file or a magnet link (required)")
+                            .required(true)
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("trash")
+                .about("Manage deleted files")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("List the files in your trash")
+                        .arg(
+                            Arg::new("json")
+                            .long("json")
+                            .help("If set, returns the output in JSON format")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("prune")
+                        .about("Remove old files from the trash for good")
+                        .long_about("Permanently deletes files that have been in the trash longer than `trash_max_age` from the config, or --max-age. Set `trash_prune_on_start` in the config to be offered this when the file browser starts.")
+                        .arg(
+                            Arg::new("max-age")
+                            .long("max-age")
+                            .value_name("DURATION")
+                            .help("Remove files deleted longer ago than this, e.g. 14d (defaults to `trash_max_age` in the config)")
+                            .required(false)
+                        )
+                        .arg(
+                            Arg::new("dry-run")
+                            .long("dry-run")
+                            .help("Only list what would be removed")
+                            .required(false)
+                            .num_args(0)
+                        )
+                        .arg(
+                            Arg::new("yes")
+                            .short('y')
+                            .long("yes")
+                            .help("Don't ask for confirmation")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("shares")
+                .about("Manage public links to your files")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("create")
+                        .about("Create a public link to a file or folder")
+                        .long_about("Creates a link that opens a file or folder without a Put.io account, prints it and copies it to the clipboard.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FILE_ID")
+                            .help("ID of a file or folder (required)")
+                            .value_parser(value_parser!(i64))
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("expires")
+                            .short('e')
+                            .long("expires")
+                            .value_name("DURATION")
+                            .help("Stop the link working after this long, e.g. 12h, 7d or 2w (default: never)")
+                            .required(false)
+                        )
+                )
+                .subcommand(
+                    Command::new("list")
+                        .about("List your public links")
+                        .arg(
+                            Arg::new("json")
+                            .long("json")
+                            .help("If set, returns the output in JSON format")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("revoke")
+                        .about("Revoke public links")
+                        .long_about("Revokes public links so they stop working. Does not remove their files.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("SHARE_ID")
+                            .help("ID of one or more links, as shown by `shares list` (required)")
+                            .value_parser(value_parser!(i64))
+                            .num_args(1..)
+                            .required(true)
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("account")
+                .about("Manage your account")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("settings")
+                        .about("View or change your account settings")
+                        .subcommand_required(true)
+                        .arg_required_else_help(true)
+                        .subcommand(
+                            Command::new("get")
+                                .about("Show your account settings")
+                                .long_about("Shows your Put.io account settings in JSON format, or a single setting if a key is given.")
+                                .arg(
+                                    Arg::new("KEY")
+                                    .help("Setting to show, e.g. trash_enabled (optional)")
+                                    .required(false)
+                                )
+                        )
+                        .subcommand(
+                            Command::new("set")
+                                .about("Change an account setting")
+                                .long_about("Changes a Put.io account setting, e.g. `set trash_enabled false`, `set default_download_folder 12345` or `set subtitle_languages eng,spa`.")
+                                .arg_required_else_help(true)
+                                .arg(
+                                    Arg::new("KEY")
+                                    .help("Setting to change (required)")
+                                    .required(true)
+                                )
+                                .arg(
+                                    Arg::new("VALUE")
+                                    .help("New value (required)")
+                                    .required(true)
+                                )
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Show how much was downloaded and uploaded")
+                .long_about("Prints the data kaput has downloaded and uploaded, in total, per day and per folder, with average speeds. Downloads are grouped by the remote folder they came from, uploads by the local one.")
+                .arg(
+                    Arg::new("since")
+                    .long("since")
+                    .value_name("DURATION")
+                    .help("Only count the last stretch of time, e.g. 7d or 4w")
+                    .required(false)
+                )
+                .arg(
+                    Arg::new("json")
+                    .long("json")
+                    .help("If set, returns the output in JSON format")
+                    .required(false)
+                    .num_args(0)
+                )
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Show your download history")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("List completed downloads")
+                        .long_about("Lists files downloaded with kaput, most recent first.")
+                        .arg(
+                            Arg::new("limit")
+                            .short('n')
+                            .long("limit")
+                            .help("Maximum number of downloads to show")
+                            .value_parser(value_parser!(usize))
+                            .default_value("50")
+                        )
+                        .arg(
+                            Arg::new("json")
+                            .long("json")
+                            .help("If set, returns the output in JSON format")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("search")
+                        .about("Search completed downloads")
+                        .long_about("Searches downloaded files by their remote or local path.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("QUERY")
+                            .required(true)
+                            .help("Text to search for (required)")
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("sync")
+                .about("Sync a folder with a local directory")
+                .long_about("Copies files changed since the last sync between a Put.io folder and a local directory, in both directions. Files changed on both sides are conflicts: you're asked whether to keep the local copy, the remote copy or both, unless --prefer decides. Nothing is deleted on either side. Files matching the ignore patterns from the config or `.kaputignore` are left out, as are new remote files that were downloaded before.")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new("FOLDER")
+                    .help("Folder ID or path on Put.io (required)")
+                    .required(true)
+                )
+                .arg(
+                    Arg::new("LOCAL_DIR")
+                    .help("Local directory to sync with (required)")
+                    .value_parser(value_parser!(PathBuf))
+                    .required(true)
+                )
+                .arg(
+                    Arg::new("prefer")
+                    .long("prefer")
+                    .help("Resolve conflicts without asking, e.g. for scheduled runs")
+                    .value_parser(["remote", "local", "newest"])
+                    .required(false)
+                )
+        )
+        .subcommand(
+            Command::new("browse")
+                .about("Browse your files interactively")
+                .arg(
+                    Arg::new("read-only")
+                    .long("read-only")
+                    .help("Hide delete, rename and move actions, e.g. when sharing the browser with others")
+                    .required(false)
+                    .num_args(0)
+                )
+        )
+        .subcommand(
+            Command::new("remote")
+                .about("Control a running file browser")
+                .long_about("Sends a command to the `kaput browse` session running on this machine, e.g. from an editor or script. Commands: `goto <path>` shows a folder or file, `download <id>` downloads a file to the default download folder and `refresh` reloads the current folder.")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new("COMMAND")
+                    .help("Command and its argument, e.g. goto Movies/Action (required)")
+                    .num_args(1..)
+                    .required(true)
+                )
+        )
+        .subcommand(
+            Command::new("serve")
+                .about("Serve your files on the local network")
+                .long_about(
+                    "Starts a local HTTP server that lists your folders and streams your files, so devices on your network (e.g. smart TVs) can play them without logging in. Only this machine can connect unless --bind is given, e.g. `--bind 0.0.0.0` for the whole network. Anyone who can connect can browse your folders; streams only open from links in a listing of the running server."
+                )
+                .arg(
+                    Arg::new("bind")
+                    .long("bind")
+                    .help("Address to listen on, e.g. 0.0.0.0 for every network interface")
+                    .default_value("127.0.0.1")
+                )
+                .arg(
+                    Arg::new("port")
+                    .short('p')
+                    .long("port")
+                    .help("Port to listen on")
+                    .value_parser(value_parser!(u16))
+                    .default_value("8080")
+                )
+        )
+        .subcommand(
+            Command::new("service")
+                .about("Run kaput in the background")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("install")
+                        .about("Install a background service")
+                        .long_about("Writes a systemd user unit (Linux) or launchd agent (macOS) that runs a long-running kaput command at login and restarts it if it fails. Arguments after `--` are passed to the command, e.g. `kaput service install --mode serve -- --port 9000`.")
+                        .arg(
+                            Arg::new("mode")
+                            .short('m')
+                            .long("mode")
+                            .help("Command to run")
+                            .value_parser(["serve"])
+                            .default_value("serve")
+                        )
+                        .arg(
+                            Arg::new("ARGS")
+                            .help("Arguments for the command")
+                            .num_args(0..)
+                            .last(true)
+                            .required(false)
+                        )
+                )
+                .subcommand(
+                    Command::new("status")
+                        .about("Check on a background service")
+                        .long_about("Shows whether a service installed with `service install` is running, as reported by systemd or launchd.")
+                        .arg(
+                            Arg::new("mode")
+                            .short('m')
+                            .long("mode")
+                            .help("Command the service runs")
+                            .value_parser(["serve"])
+                            .default_value("serve")
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("whoami")
+                .about("Check what account you are logged into")
+                .long_about(
+                    "Returns the username and email of the currently authenticated Put.io user."
+                )
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Check your setup for problems")
+                .long_about("Checks the config file, the connection to Put.io, your login, the download folder, curl, the clipboard and the terminal, and suggests a fix for each problem found.")
+        )
+        .subcommand(
+            Command::new("debug")
+                .about("Check the current config")
+                .long_about("Returns the current config file and path.")
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Copy your kaput setup between machines")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("export")
+                        .about("Export the config and browser data")
+                        .long_about("Writes the config file, saved searches and recent download folders to a single TOML file. API tokens and proxy credentials are left out unless --include-secrets is given.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("OUT")
+                            .help("File to write to (required)")
+                            .value_parser(value_parser!(PathBuf))
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("include-secrets")
+                            .long("include-secrets")
+                            .help("Include API tokens and proxy credentials")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Import a config exported with `config export`")
+                        .long_about("Replaces the config file, saved searches and recent download folders with those in an exported file. If the export has no API token, the current one is kept.")
+                        .arg_required_else_help(true)
+                        .arg(
+                            Arg::new("FILE")
+                            .help("Exported file (required)")
+                            .value_parser(value_parser!(PathBuf))
+                            .required(true)
+                        )
+                        .arg(
+                            Arg::new("yes")
+                            .short('y')
+                            .long("yes")
+                            .help("Import without asking for confirmation")
+                            .required(false)
+                            .num_args(0)
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("help")
+                .about("Print help for kaput or one of its subcommands")
+                .long_about("Prints help for kaput or the given subcommand. With `--man`, prints its man page instead.")
+                .arg(
+                    Arg::new("COMMAND")
+                    .help("Subcommand to show help for (e.g. files download)")
+                    .required(false)
+                    .num_args(1..)
+                )
+                .arg(
+                    Arg::new("man")
+                    .long("man")
+                    .help("Print the man page in roff format")
+                    .required(false)
+                    .num_args(0)
+                )
+                .arg(
+                    Arg::new("out-dir")
+                    .long("out-dir")
+                    .help("With --man, write every man page to this directory instead")
+                    .value_parser(value_parser!(PathBuf))
+                    .requires("man")
+                    .required(false)
+                )
+        )
+}
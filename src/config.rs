@@ -0,0 +1,319 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+/// Used with Confy to control the config storage location
+pub const APP_NAME: &str = "kaput-cli";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigFile {
+    pub api_token: String,
+    /// URL that Put.io calls when a new transfer completes. Not called for
+    /// transfers added from a local .torrent file
+    pub transfer_callback_url: Option<String>,
+    /// Glob patterns skipped by recursive downloads, e.g. `*.nfo` or `Sample`
+    pub ignore_patterns: Vec<String>,
+    /// Where downloads are saved when no path is given
+    pub download_dir: Option<String>,
+    /// On Windows, save files whose path is longer than 260 characters using
+    /// the `\\?\` prefix. Other programs may not be able to open them
+    pub windows_long_paths: bool,
+    /// Commands that `download --extract` unpacks archives with, by file
+    /// extension. `{archive}` is replaced with the archive and `{dir}` with
+    /// the folder to unpack into
+    pub extractors: HashMap<String, Vec<String>>,
+    /// Scripts run after each file `download` or the file browser finishes
+    pub post_download: Vec<PostDownloadHook>,
+    /// When to ask before deleting files
+    pub confirm_delete: ConfirmDelete,
+    /// Sort names by the value of numbers in them, so `Episode 2` comes before `Episode 10`
+    pub natural_sort: bool,
+    /// In the file browser, start with cleaned-up release names shown (toggled with `P`)
+    pub pretty_names: bool,
+    /// In the file browser, jump to the next name starting with a letter that has
+    /// no other binding when it is pressed
+    pub letter_jump: bool,
+    /// In the file browser, show folders that are mostly images as a grid of
+    /// names from the start (toggled with `V`)
+    pub grid_view: bool,
+    /// Colors of the file browser: `default`, the `deuteranopia` and
+    /// `protanopia` presets, or `monochrome` to tell things apart by bold,
+    /// underline and reverse only (cycled with `C`)
+    pub color_preset: ColorPreset,
+    /// Show thumbnails in the grid when the terminal can draw images
+    pub thumbnails: bool,
+    /// External programs offered under "Open with" in the file browser
+    pub open_with: Vec<OpenWith>,
+    /// What Enter does on a file in the file browser, by file type, e.g.
+    /// `VIDEO = "Play"`. Values are labels from the file actions menu; other file
+    /// types open the menu, as does Shift+Enter.
+    pub default_actions: HashMap<String, String>,
+    /// In the file browser, list the current folder again this often, e.g.
+    /// `30s` or `5m`, keeping the cursor where it is. When set, the account's
+    /// event feed is checked as often, so finished transfers and uploads
+    /// trigger a refresh and show in the status bar
+    pub auto_refresh: Option<String>,
+    /// IDs of files and folders the file browser keeps at the top of their
+    /// folder (toggled with `.`)
+    pub pinned: Vec<i64>,
+    /// How long deleted files stay in the trash before `kaput trash prune`
+    /// removes them for good, e.g. `14d`
+    pub trash_max_age: Option<String>,
+    /// Offer to prune the trash when the file browser starts
+    pub trash_prune_on_start: bool,
+    /// Other Put.io accounts by name, used with `--profile` and by the file
+    /// browser's account tabs. `kaput --profile NAME login` adds one
+    pub profiles: BTreeMap<String, Profile>,
+    pub http: HttpConfig,
+}
+
+/// Another Put.io account that commands can be run against.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub api_token: String,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConfirmDelete {
+    #[default]
+    Always,
+    /// Only when deleted files are removed for good rather than moved to the trash
+    TrashOnly,
+    Never,
+}
+
+impl ConfirmDelete {
+    /// Returns true if a delete should be confirmed. `trash_enabled` is only
+    /// called when the answer depends on it.
+    pub fn required(self, trash_enabled: impl FnOnce() -> bool) -> bool {
+        match self {
+            ConfirmDelete::Always => true,
+            ConfirmDelete::TrashOnly => !trash_enabled(),
+            ConfirmDelete::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorPreset {
+    #[default]
+    Default,
+    /// For red-green color blindness with weak green
+    Deuteranopia,
+    /// For red-green color blindness with weak red
+    Protanopia,
+    /// No colors at all, only text attributes
+    Monochrome,
+}
+
+impl ColorPreset {
+    /// The preset after this one, wrapping around.
+    pub fn next(self) -> Self {
+        match self {
+            ColorPreset::Default => ColorPreset::Deuteranopia,
+            ColorPreset::Deuteranopia => ColorPreset::Protanopia,
+            ColorPreset::Protanopia => ColorPreset::Monochrome,
+            ColorPreset::Monochrome => ColorPreset::Default,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ColorPreset::Default => "default",
+            ColorPreset::Deuteranopia => "deuteranopia",
+            ColorPreset::Protanopia => "protanopia",
+            ColorPreset::Monochrome => "monochrome",
+        }
+    }
+}
+
+/// A script run after a file is downloaded, e.g. to rename it or tell a media
+/// server about it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostDownloadHook {
+    /// Program and arguments. The download is described in `KAPUT_*`
+    /// environment variables and as JSON on stdin
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub on_failure: HookFailure,
+}
+
+/// What happens when a post-download script fails.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookFailure {
+    Ignore,
+    /// Print a warning and carry on
+    #[default]
+    Warn,
+    /// Stop downloading the rest of the folder
+    Abort,
+}
+
+/// A program that files can be handed to, e.g. IINA, mpv or ffprobe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenWith {
+    pub name: String,
+    /// Program and arguments. `{url}`, `{id}` and `{name}` are replaced with the
+    /// file's stream or download URL, its ID and its name.
+    pub command: Vec<String>,
+    /// Wait for Enter after the program exits so its output can be read
+    #[serde(default)]
+    pub pause: bool,
+}
+
+impl OpenWith {
+    /// Returns the command line for a file, with placeholders filled in.
+    pub fn args_for(&self, url: &str, id: i64, name: &str) -> Vec<String> {
+        self.command
+            .iter()
+            .map(|arg| {
+                arg.replace("{url}", url)
+                    .replace("{id}", &id.to_string())
+                    .replace("{name}", name)
+            })
+            .collect()
+    }
+}
+
+/// Settings for the HTTP client shared by every command.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpConfig {
+    /// Total time allowed for a request, 0 to wait forever
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    /// Time allowed for each page of a folder listing, 0 to use `timeout_secs`
+    pub list_timeout_secs: u64,
+    /// Time allowed for a search, 0 to use `timeout_secs`
+    pub search_timeout_secs: u64,
+    /// Time allowed for curl to connect when downloading, 0 for curl's default
+    pub download_connect_timeout_secs: u64,
+    /// Idle connections kept open per host for reuse
+    pub pool_max_idle_per_host: usize,
+    /// Proxy for all requests, e.g. `http://host:3128` or `socks5://host:1080`
+    pub proxy: Option<String>,
+    /// PEM file with extra root certificates to trust
+    pub ca_bundle: Option<PathBuf>,
+    /// API to talk to instead of `https://api.put.io/v2`, e.g. a compatible
+    /// gateway or the staging environment. Uploads still go to upload.put.io
+    pub api_base_url: Option<String>,
+    /// `User-Agent` header sent with API requests
+    pub user_agent: Option<String>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            timeout_secs: 30,
+            connect_timeout_secs: 10,
+            list_timeout_secs: 20,
+            search_timeout_secs: 30,
+            download_connect_timeout_secs: 15,
+            pool_max_idle_per_host: 8,
+            proxy: None,
+            ca_bundle: None,
+            api_base_url: None,
+            user_agent: None,
+        }
+    }
+}
+
+impl Default for ConfigFile {
+    fn default() -> Self {
+        ConfigFile {
+            api_token: "".into(),
+            transfer_callback_url: None,
+            ignore_patterns: vec![],
+            download_dir: None,
+            windows_long_paths: false,
+            extractors: crate::extract::default_extractors(),
+            post_download: vec![],
+            confirm_delete: ConfirmDelete::default(),
+            natural_sort: true,
+            pretty_names: false,
+            letter_jump: true,
+            grid_view: false,
+            color_preset: ColorPreset::default(),
+            thumbnails: true,
+            open_with: vec![],
+            default_actions: HashMap::from([
+                ("VIDEO".to_string(), "Play".to_string()),
+                ("ARCHIVE".to_string(), "Extract".to_string()),
+            ]),
+            auto_refresh: None,
+            pinned: vec![],
+            trash_max_age: None,
+            trash_prune_on_start: false,
+            profiles: BTreeMap::new(),
+            http: HttpConfig::default(),
+        }
+    }
+}
+
+/// Parses a duration such as `30s`, `30m`, `12h`, `7d` or `2w` into seconds.
+pub fn parse_duration(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let invalid = || format!("Invalid duration \"{}\", use e.g. 12h, 7d or 2w", text);
+    let unit = match text.chars().last() {
+        Some('s') => 1,
+        Some('m') => 60,
+        Some('h') => 3600,
+        Some('d') => 86400,
+        Some('w') => 7 * 86400,
+        _ => return Err(invalid()),
+    };
+    let amount: u64 = text[..text.len() - 1].parse().map_err(|_| invalid())?;
+    if amount == 0 {
+        return Err(invalid());
+    }
+    Ok(amount * unit)
+}
+
+/// Returns the path of a data file stored next to the config file, e.g. the download history.
+pub fn data_file_path(file_name: &str) -> PathBuf {
+    let config_path =
+        confy::get_configuration_file_path(APP_NAME, None).expect("getting config file path");
+    config_path
+        .parent()
+        .map(|dir| dir.join(file_name))
+        .unwrap_or_else(|| file_name.into())
+}
+
+/// Saves the pinned file IDs, leaving the rest of the stored config untouched.
+pub fn store_pinned(pinned: &[i64]) -> Result<(), confy::ConfyError> {
+    let config: ConfigFile = confy::load(APP_NAME, None)?;
+    confy::store(
+        APP_NAME,
+        None,
+        ConfigFile {
+            pinned: pinned.to_vec(),
+            ..config
+        },
+    )
+}
+
+/// Saves a new API token for the default account or a profile, leaving the
+/// rest of the stored config untouched. A profile that doesn't exist is added.
+///
+/// The config is re-read from disk so overrides given on the command line
+/// (e.g. `--proxy`) are not persisted.
+pub fn store_api_token(profile: Option<&str>, api_token: &str) -> Result<(), confy::ConfyError> {
+    let mut config: ConfigFile = confy::load(APP_NAME, None)?;
+    match profile {
+        Some(name) => {
+            config
+                .profiles
+                .entry(name.to_string())
+                .or_default()
+                .api_token = api_token.to_string()
+        }
+        None => config.api_token = api_token.to_string(),
+    }
+    confy::store(APP_NAME, None, config)
+}
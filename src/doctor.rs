@@ -0,0 +1,264 @@
+use std::fs;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::process::{Command as ProcessCommand, Stdio};
+use std::time::{Duration, Instant};
+
+use reqwest::StatusCode;
+
+use crate::browse::download_dirs;
+use crate::config::{ConfigFile, APP_NAME};
+use crate::put::{self, Client};
+
+/// API round trips slower than this are reported as a warning
+const SLOW_API: Duration = Duration::from_secs(2);
+
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// The result of one check, with what to do about it if it didn't pass.
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Runs every check and prints the results. `config_error` is the reason the
+/// config file couldn't be read, in which case `config` holds the defaults.
+/// Returns false if any check failed.
+pub fn run(client: &Client, config: &ConfigFile, config_error: Option<String>) -> bool {
+    let checks = vec![
+        check_config(config_error),
+        check_api(client),
+        check_token(client, config),
+        check_download_dir(config),
+        check_curl(),
+        check_clipboard(),
+        check_terminal(),
+        check_color(),
+        Check::ok(
+            "Nerd Fonts",
+            "can't be detected; if this looks like a folder icon they're installed: \u{f07b}",
+        ),
+    ];
+
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut failed = false;
+    for check in &checks {
+        let mark = match check.status {
+            Status::Ok => "✔",
+            Status::Warn => "!",
+            Status::Fail => {
+                failed = true;
+                "✘"
+            }
+        };
+        println!("{} {:width$}  {}", mark, check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            println!("  {:width$}  → {}", "", fix);
+        }
+    }
+    !failed
+}
+
+fn check_config(config_error: Option<String>) -> Check {
+    let path = confy::get_configuration_file_path(APP_NAME, None)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| "the config file".to_string());
+    match config_error {
+        None => Check::ok("Config", format!("read {}", path)),
+        Some(e) => Check::fail(
+            "Config",
+            format!("could not be read: {}", e),
+            format!(
+                "Fix the setting named in the error in {}, or move the file away to start from the defaults",
+                path
+            ),
+        ),
+    }
+}
+
+fn check_api(client: &Client) -> Check {
+    let url = client.api_url("/");
+    let started = Instant::now();
+    match client.get(&url).send() {
+        Ok(_) => {
+            let elapsed = started.elapsed();
+            let detail = format!("{} answered in {} ms", url, elapsed.as_millis());
+            if elapsed > SLOW_API {
+                Check::warn(
+                    "API",
+                    detail,
+                    "The connection is slow; raise the `http` timeouts in the config if requests time out",
+                )
+            } else {
+                Check::ok("API", detail)
+            }
+        }
+        Err(e) => Check::fail(
+            "API",
+            format!("{} could not be reached: {}", url, e),
+            "Check your network connection, and the `http.proxy`, `http.ca_bundle` and `http.api_base_url` settings if you use them",
+        ),
+    }
+}
+
+fn check_token(client: &Client, config: &ConfigFile) -> Check {
+    if config.api_token.is_empty() {
+        return Check::fail("Token", "not logged in", "Run `kaput login`");
+    }
+    match put::account::info(client, &config.api_token) {
+        Ok(account) if account.info.account_active => Check::ok(
+            "Token",
+            format!("valid, logged in as {}", account.info.username),
+        ),
+        Ok(account) => Check::fail(
+            "Token",
+            format!(
+                "valid, but the account of {} is inactive",
+                account.info.username
+            ),
+            "Renew your Put.io subscription",
+        ),
+        Err(e) if e.status() == Some(StatusCode::UNAUTHORIZED) => Check::fail(
+            "Token",
+            "rejected by Put.io",
+            "Run `kaput login` to get a new token",
+        ),
+        Err(e) => Check::warn(
+            "Token",
+            format!("could not be checked: {}", e),
+            "Fix the API check above, then run `kaput doctor` again",
+        ),
+    }
+}
+
+fn check_download_dir(config: &ConfigFile) -> Check {
+    let dir = config.download_dir.as_deref().unwrap_or(".");
+    let dir = download_dirs::expand_home(dir)
+        .to_string_lossy()
+        .to_string();
+    let dir = dir.as_str();
+    if !Path::new(dir).is_dir() {
+        return Check::fail(
+            "Download folder",
+            format!("{} does not exist", dir),
+            format!(
+                "Create it with `mkdir -p {}` or change `download_dir` in the config",
+                dir
+            ),
+        );
+    }
+    let probe = Path::new(dir).join(".kaput-doctor");
+    match fs::write(&probe, b"") {
+        Ok(_) => {
+            fs::remove_file(&probe).ok();
+            Check::ok("Download folder", format!("{} is writable", dir))
+        }
+        Err(e) => Check::fail(
+            "Download folder",
+            format!("{} is not writable: {}", dir, e),
+            "Fix its permissions or change `download_dir` in the config",
+        ),
+    }
+}
+
+fn check_curl() -> Check {
+    let found = ProcessCommand::new("curl")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    if found {
+        Check::ok("curl", "found")
+    } else {
+        Check::fail(
+            "curl",
+            "not found",
+            "Install curl; downloads and uploads run through it",
+        )
+    }
+}
+
+fn check_clipboard() -> Check {
+    match arboard::Clipboard::new() {
+        Ok(_) => Check::ok("Clipboard", "available"),
+        Err(e) => Check::warn(
+            "Clipboard",
+            format!("unavailable: {}", e),
+            "Copy actions need a graphical session; on Linux also check that X11 or Wayland is reachable",
+        ),
+    }
+}
+
+fn check_terminal() -> Check {
+    if !std::io::stdout().is_terminal() {
+        return Check::warn(
+            "Terminal",
+            "output is not a terminal",
+            "Run `kaput browse` directly in a terminal, not through a pipe",
+        );
+    }
+    match crossterm::terminal::size() {
+        Ok((columns, rows)) if columns >= 80 && rows >= 20 => {
+            Check::ok("Terminal", format!("{}x{}", columns, rows))
+        }
+        Ok((columns, rows)) => Check::warn(
+            "Terminal",
+            format!("{}x{} is small", columns, rows),
+            "Make the window at least 80x20 so the file browser fits",
+        ),
+        Err(e) => Check::warn(
+            "Terminal",
+            format!("size unknown: {}", e),
+            "Run `kaput browse` directly in a terminal",
+        ),
+    }
+}
+
+fn check_color() -> Check {
+    let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+    if colorterm == "truecolor" || colorterm == "24bit" {
+        Check::ok("Colors", "24-bit color")
+    } else {
+        Check::warn(
+            "Colors",
+            "24-bit color not advertised",
+            "Set COLORTERM=truecolor if your terminal supports it; otherwise colors are approximated",
+        )
+    }
+}
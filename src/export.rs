@@ -0,0 +1,114 @@
+use std::io::{self, Write};
+
+use crate::put::walk::{self, Visit};
+use crate::put::{self, files::File, Client};
+
+/// Column order of an export
+const COLUMNS: [&str; 6] = ["path", "id", "type", "size", "created", "crc32"];
+
+pub enum Format {
+    Csv,
+    Tsv,
+}
+
+impl Format {
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "csv" => Some(Format::Csv),
+            "tsv" => Some(Format::Tsv),
+            _ => None,
+        }
+    }
+
+    fn write_row(&self, out: &mut dyn Write, fields: &[&str]) -> io::Result<()> {
+        let fields: Vec<String> = fields.iter().map(|field| self.escape(field)).collect();
+        let separator = match self {
+            Format::Csv => ",",
+            Format::Tsv => "\t",
+        };
+        writeln!(out, "{}", fields.join(separator))
+    }
+
+    fn escape(&self, field: &str) -> String {
+        match self {
+            Format::Csv if field.contains([',', '"', '\n', '\r']) => {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            }
+            Format::Csv => field.to_string(),
+            // TSV has no quoting, so separators inside a field become spaces
+            Format::Tsv => field.replace(['\t', '\n', '\r'], " "),
+        }
+    }
+}
+
+fn write_file(format: &Format, out: &mut dyn Write, path: &str, file: &File) -> io::Result<()> {
+    format.write_row(
+        out,
+        &[
+            path,
+            &file.id.to_string(),
+            &file.file_type,
+            &file.size.0.to_string(),
+            &file.created_at,
+            file.crc32.as_deref().unwrap_or(""),
+        ],
+    )
+}
+
+/// Writes one row per file and folder in a folder, or in its whole tree if
+/// `recursive` is set. Rows are written as soon as their folder is listed.
+///
+/// Returns the number of rows written.
+pub fn write(
+    client: &Client,
+    api_token: &String,
+    folder_id: i64,
+    recursive: bool,
+    format: &Format,
+    out: &mut dyn Write,
+) -> Result<usize, String> {
+    format.write_row(out, &COLUMNS).map_err(|e| e.to_string())?;
+
+    let mut rows = 0;
+    if !recursive {
+        let listing = put::files::list(client, api_token, folder_id).map_err(|e| e.to_string())?;
+        for file in &listing.files {
+            write_file(format, out, &file.name, file).map_err(|e| e.to_string())?;
+            rows += 1;
+        }
+        out.flush().map_err(|e| e.to_string())?;
+        return Ok(rows);
+    }
+
+    let mut failed: Option<io::Error> = None;
+    walk::walk(
+        client,
+        api_token,
+        folder_id,
+        |entry| match write_file(format, out, &entry.path, &entry.file) {
+            Ok(_) => {
+                rows += 1;
+                Visit::Descend
+            }
+            // e.g. the output was piped into `head`, so there's no point going on
+            Err(e) => {
+                failed = Some(e);
+                Visit::Stop
+            }
+        },
+        |progress| {
+            eprint!(
+                "\rListed {} folder(s), {} remaining...",
+                progress.folders_listed, progress.folders_pending
+            );
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    eprintln!();
+
+    if let Some(e) = failed {
+        return Err(e.to_string());
+    }
+    out.flush().map_err(|e| e.to_string())?;
+    Ok(rows)
+}
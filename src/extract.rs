@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::ignore::IgnoreRules;
+use crate::put::files::free_path;
+
+/// The commands used when none are configured, by file extension.
+pub fn default_extractors() -> HashMap<String, Vec<String>> {
+    let command = |args: &[&str]| args.iter().map(|a| a.to_string()).collect();
+    HashMap::from([
+        (
+            "zip".to_string(),
+            command(&["unzip", "-o", "-q", "{archive}", "-d", "{dir}"]),
+        ),
+        (
+            "rar".to_string(),
+            command(&["unrar", "x", "-o+", "-idq", "{archive}", "{dir}/"]),
+        ),
+        (
+            "7z".to_string(),
+            command(&["7z", "x", "-y", "-bd", "-o{dir}", "{archive}"]),
+        ),
+    ])
+}
+
+/// Returns the name of the folder an archive unpacks into, or `None` if the
+/// file isn't one to unpack. Only the first part of a multi-part RAR counts.
+fn folder_name(archive: &Path, extractors: &HashMap<String, Vec<String>>) -> Option<String> {
+    let extension = archive.extension()?.to_string_lossy().to_lowercase();
+    extractors.get(&extension)?;
+
+    let stem = archive.file_stem()?.to_string_lossy().into_owned();
+    match stem.rsplit_once(".part") {
+        Some((name, part)) if !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) => {
+            (part.parse::<u32>() == Ok(1)).then(|| name.to_string())
+        }
+        _ => Some(stem),
+    }
+}
+
+/// Unpacks a downloaded archive into a folder named after it, next to it.
+///
+/// The archive is unpacked into a scratch folder first. A single folder at its
+/// root is flattened away, entries matching `ignore` are dropped, and files
+/// whose name is already taken get a free name like `Movie (1).mkv` rather
+/// than overwriting anything. Returns the folder, or `None` if the file isn't
+/// an archive with a configured extractor.
+pub fn extract(
+    archive: &Path,
+    extractors: &HashMap<String, Vec<String>>,
+    ignore: &IgnoreRules,
+) -> Result<Option<PathBuf>, String> {
+    let Some(name) = folder_name(archive, extractors) else {
+        return Ok(None);
+    };
+    let extension = archive
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let parent = archive.parent().unwrap_or(Path::new("."));
+    let target = parent.join(&name);
+    let scratch = parent.join(format!(".{}.extracting", name));
+
+    if scratch.exists() {
+        fs::remove_dir_all(&scratch)
+            .map_err(|e| format!("clearing {}: {}", scratch.display(), e))?;
+    }
+    fs::create_dir_all(&scratch).map_err(|e| format!("creating {}: {}", scratch.display(), e))?;
+
+    println!("Extracting: {}", archive.display());
+    let args: Vec<String> = extractors[&extension]
+        .iter()
+        .map(|arg| {
+            arg.replace("{archive}", &archive.to_string_lossy())
+                .replace("{dir}", &scratch.to_string_lossy())
+        })
+        .collect();
+    let Some((program, args)) = args.split_first() else {
+        return Err(format!("the extractor for .{} files is empty", extension));
+    };
+    let status = Command::new(program).args(args).status();
+    let result = match status {
+        Ok(status) if status.success() => {
+            move_entries(&unwrap_single_folder(&scratch), &target, "", ignore)
+                .map_err(|e| format!("moving extracted files: {}", e))
+        }
+        Ok(status) => Err(format!("{} exited with {}", program, status)),
+        Err(e) => Err(format!("could not run {}: {}", program, e)),
+    };
+    fs::remove_dir_all(&scratch).ok();
+
+    result.map(|_| Some(target))
+}
+
+/// Follows folders that are the only entry in their parent, so an archive
+/// holding just `Movie/` doesn't unpack to `Movie/Movie/`.
+fn unwrap_single_folder(dir: &Path) -> PathBuf {
+    let mut dir = dir.to_path_buf();
+    loop {
+        let entries: Vec<fs::DirEntry> = match fs::read_dir(&dir) {
+            Ok(entries) => entries.filter_map(Result::ok).collect(),
+            Err(_) => return dir,
+        };
+        match entries.as_slice() {
+            [only] if only.path().is_dir() => dir = only.path(),
+            _ => return dir,
+        }
+    }
+}
+
+/// Moves everything below `from` into `to`, skipping ignored entries and
+/// renaming files whose name is already taken.
+fn move_entries(from: &Path, to: &Path, prefix: &str, ignore: &IgnoreRules) -> std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        if ignore.is_ignored(&relative) {
+            println!("Skipping (ignored): {}", relative);
+            continue;
+        }
+
+        let destination = to.join(&name);
+        if entry.file_type()?.is_dir() {
+            move_entries(&entry.path(), &destination, &relative, ignore)?;
+        } else if destination.exists() {
+            let free = free_path(&destination.to_string_lossy());
+            println!("Saving {} as {} (name taken)", relative, free);
+            fs::rename(entry.path(), free)?;
+        } else {
+            fs::rename(entry.path(), destination)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use std::fs;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::config;
+
+const HISTORY_FILE: &str = "history.json";
+const TRANSFERS_FILE: &str = "transfers.json";
+
+/// A completed download.
+#[derive(Debug, Clone, Serialize, Deserialize, Tabled)]
+pub struct HistoryEntry {
+    pub file_id: i64,
+    pub remote_path: String,
+    #[tabled(display_with = "display_size")]
+    pub size: u64,
+    #[tabled(display_with = "display_crc32")]
+    pub crc32: Option<String>,
+    pub local_path: String,
+    #[tabled(rename = "downloaded_at", display_with = "display_timestamp")]
+    pub downloaded_at: u64,
+}
+
+fn display_size(size: &u64) -> String {
+    bytefmt::format(*size)
+}
+
+fn display_crc32(crc32: &Option<String>) -> String {
+    crc32.clone().unwrap_or_default()
+}
+
+fn display_timestamp(timestamp: &u64) -> String {
+    format_timestamp(*timestamp)
+}
+
+/// Returns every recorded download, oldest first. A missing history is empty,
+/// but one that can't be read or parsed is an error.
+pub fn load() -> io::Result<Vec<HistoryEntry>> {
+    let contents = match fs::read_to_string(config::data_file_path(HISTORY_FILE)) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    serde_json::from_str(&contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Appends a completed download to the history. A history file that can't be
+/// parsed is left alone rather than replaced by this one entry.
+pub fn record(entry: HistoryEntry) -> io::Result<()> {
+    let mut entries = load()?;
+    entries.push(entry);
+
+    let path = config::data_file_path(HISTORY_FILE);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(&entries)?)
+}
+
+/// Returns true if the file was downloaded before, wherever it was saved.
+pub fn contains(entries: &[HistoryEntry], file_id: i64) -> bool {
+    entries.iter().any(|e| e.file_id == file_id)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Download,
+    Upload,
+}
+
+/// Everything transferred in one direction from one folder on one day.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyTotal {
+    /// `YYYY-MM-DD` in UTC
+    pub day: String,
+    pub direction: Direction,
+    /// The folder the files came from: the remote folder for downloads and the
+    /// local one for uploads. Empty for files downloaded on their own
+    pub folder: String,
+    pub bytes: u64,
+    pub files: u64,
+    /// Time spent transferring, for average speeds
+    pub seconds: f64,
+}
+
+/// Returns the daily transfer totals, oldest first.
+pub fn load_totals() -> Vec<DailyTotal> {
+    fs::read_to_string(config::data_file_path(TRANSFERS_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Adds a finished transfer to today's totals. Statistics are best-effort, so
+/// a failure to save them is only reported.
+pub fn count_transfer(direction: Direction, folder: &str, bytes: u64, took: Duration) {
+    let mut totals = load_totals();
+    let day = format_timestamp(now())[..10].to_string();
+    match totals
+        .iter_mut()
+        .find(|t| t.day == day && t.direction == direction && t.folder == folder)
+    {
+        Some(total) => {
+            total.bytes += bytes;
+            total.files += 1;
+            total.seconds += took.as_secs_f64();
+        }
+        None => totals.push(DailyTotal {
+            day,
+            direction,
+            folder: folder.to_string(),
+            bytes,
+            files: 1,
+            seconds: took.as_secs_f64(),
+        }),
+    }
+
+    let path = config::data_file_path(TRANSFERS_FILE);
+    let saved = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| Ok(serde_json::to_string(&totals)?))
+        .and_then(|contents| fs::write(&path, contents));
+    if let Err(e) = saved {
+        eprintln!("Warning: could not update transfer statistics: {}", e);
+    }
+}
+
+/// Returns the current time as seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs()
+}
+
+/// Formats seconds since the Unix epoch as `YYYY-MM-DD HH:MM` (UTC).
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+
+    // Civil-from-days conversion, see https://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        (seconds % 3600) / 60
+    )
+}
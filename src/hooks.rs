@@ -0,0 +1,84 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+use crate::config::{HookFailure, PostDownloadHook};
+
+/// What a post-download script is told about the file it runs for.
+#[derive(Debug, Serialize)]
+pub struct DownloadContext<'a> {
+    pub file_id: i64,
+    /// Path on Put.io, relative to where the download started
+    pub remote_path: &'a str,
+    pub local_path: &'a str,
+    pub size: u64,
+    pub crc32: Option<&'a str>,
+    /// `None` for the default account
+    pub profile: Option<&'a str>,
+}
+
+impl DownloadContext<'_> {
+    fn env(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("KAPUT_FILE_ID", self.file_id.to_string()),
+            ("KAPUT_REMOTE_PATH", self.remote_path.to_string()),
+            ("KAPUT_LOCAL_PATH", self.local_path.to_string()),
+            ("KAPUT_SIZE", self.size.to_string()),
+            ("KAPUT_CRC32", self.crc32.unwrap_or_default().to_string()),
+            (
+                "KAPUT_PROFILE",
+                self.profile.unwrap_or_default().to_string(),
+            ),
+        ]
+    }
+}
+
+/// Runs each hook for a finished download, in order. Failures of hooks set to
+/// `warn` are printed; the first failure of a hook set to `abort` is returned
+/// and stops the remaining hooks.
+pub fn run_post_download(
+    hooks: &[PostDownloadHook],
+    context: &DownloadContext,
+) -> Result<(), String> {
+    for hook in hooks {
+        let Err(e) = run(hook, context) else {
+            continue;
+        };
+        match hook.on_failure {
+            HookFailure::Ignore => {}
+            HookFailure::Warn => eprintln!("Warning: {}", e),
+            HookFailure::Abort => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+fn run(hook: &PostDownloadHook, context: &DownloadContext) -> Result<(), String> {
+    let Some((program, args)) = hook.command.split_first() else {
+        return Err("a post-download hook has an empty command".to_string());
+    };
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(context.env())
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("could not run {}: {}", program, e))?;
+
+    // Scripts that don't read stdin close it early, which isn't a failure
+    if let Some(mut stdin) = child.stdin.take() {
+        let json = serde_json::to_string(context).expect("serializing download context");
+        stdin.write_all(json.as_bytes()).ok();
+    }
+    let status = child
+        .wait()
+        .map_err(|e| format!("waiting for {}: {}", program, e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "post-download hook {} exited with {} for {}",
+            program, status, context.local_path
+        ))
+    }
+}
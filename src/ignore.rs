@@ -0,0 +1,81 @@
+use std::fs;
+
+/// Name of the file, read from the current directory, that lists extra ignore patterns
+const IGNORE_FILE: &str = ".kaputignore";
+
+/// Glob patterns for files that recursive commands should skip.
+///
+/// Patterns support `*` and `?` and are matched case-insensitively. A pattern
+/// containing `/` is matched against the path relative to the starting folder,
+/// anything else against the file or folder name alone.
+#[derive(Default)]
+pub struct IgnoreRules {
+    patterns: Vec<String>,
+}
+
+impl IgnoreRules {
+    /// Combines patterns from the config, `.kaputignore` and the command line.
+    pub fn load(config_patterns: &[String], extra_patterns: &[String]) -> Self {
+        let mut patterns: Vec<String> = config_patterns.to_vec();
+
+        if let Ok(contents) = fs::read_to_string(IGNORE_FILE) {
+            patterns.extend(
+                contents
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(String::from),
+            );
+        }
+
+        patterns.extend(extra_patterns.iter().cloned());
+
+        IgnoreRules {
+            patterns: patterns.iter().map(|p| p.to_lowercase()).collect(),
+        }
+    }
+
+    /// Returns true if the entry at `path` (relative, `/`-separated) should be skipped.
+    pub fn is_ignored(&self, path: &str) -> bool {
+        let path = path.to_lowercase();
+        let name = path.rsplit('/').next().unwrap_or(&path);
+
+        self.patterns.iter().any(|pattern| {
+            if pattern.contains('/') {
+                glob_match(pattern.trim_start_matches('/'), &path)
+            } else {
+                glob_match(pattern, name)
+            }
+        })
+    }
+}
+
+/// Matches `text` against a pattern where `*` matches any run of characters
+/// and `?` matches exactly one. Matching is case-sensitive.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` seen and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = backtrack {
+            // Let the last `*` swallow one more character and retry
+            p = star_p + 1;
+            t = star_t + 1;
+            backtrack = Some((star_p, star_t + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
@@ -0,0 +1,144 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// A file that was downloaded completely.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Completed {
+    pub local_path: String,
+    pub size: u64,
+}
+
+/// A file whose download stopped early, with a checksum of what was saved so
+/// a changed or damaged file isn't resumed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Partial {
+    pub local_path: String,
+    pub offset: u64,
+    pub crc32: String,
+}
+
+/// Progress of one recursive download, so running the same command again
+/// resumes where it stopped, even after a crash or Ctrl-C. Saved after every
+/// file and removed once the whole download finished.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct JobState {
+    #[serde(skip)]
+    path: PathBuf,
+    pub completed: HashMap<i64, Completed>,
+    pub partial: HashMap<i64, Partial>,
+}
+
+/// Where the state of downloading `folder_id` into `destination` is kept.
+fn state_path(folder_id: i64, destination: &str) -> PathBuf {
+    let destination = fs::canonicalize(destination)
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_else(|_| destination.to_string());
+    let key = crc32fast::hash(destination.as_bytes());
+    config::data_file_path("jobs").join(format!("{}-{:08x}.json", folder_id, key))
+}
+
+impl JobState {
+    /// Loads the state left by an earlier run of the same download, or starts
+    /// a new one.
+    pub fn load(folder_id: i64, destination: &str) -> Self {
+        let path = state_path(folder_id, destination);
+        let mut state: JobState = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        state.path = path;
+        state
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string(self)?)
+    }
+
+    /// True if an earlier run finished the file and it's still on disk in full.
+    pub fn is_completed(&self, file_id: i64) -> bool {
+        self.completed
+            .get(&file_id)
+            .is_some_and(|done| fs::metadata(&done.local_path).is_ok_and(|m| m.len() == done.size))
+    }
+
+    /// Checks that a partial download left by an earlier run still starts with
+    /// the bytes that were saved. Returns false if it should be started over.
+    pub fn partial_is_intact(&self, file_id: i64) -> bool {
+        let Some(partial) = self.partial.get(&file_id) else {
+            return true;
+        };
+        crc32_prefix(Path::new(&partial.local_path), partial.offset)
+            .is_ok_and(|crc32| crc32 == partial.crc32)
+    }
+
+    pub fn record_completed(&mut self, file_id: i64, local_path: &str) -> io::Result<()> {
+        let size = fs::metadata(local_path)?.len();
+        self.partial.remove(&file_id);
+        self.completed.insert(
+            file_id,
+            Completed {
+                local_path: local_path.to_string(),
+                size,
+            },
+        );
+        self.save()
+    }
+
+    /// Records how much of a file was saved before its download stopped.
+    pub fn record_partial(&mut self, file_id: i64, local_path: &str) -> io::Result<()> {
+        let offset = match fs::metadata(local_path) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => {
+                self.partial.remove(&file_id);
+                return self.save();
+            }
+        };
+        let crc32 = crc32_prefix(Path::new(local_path), offset)?;
+        self.partial.insert(
+            file_id,
+            Partial {
+                local_path: local_path.to_string(),
+                offset,
+                crc32,
+            },
+        );
+        self.save()
+    }
+
+    /// Removes the state once nothing is left to resume.
+    pub fn finish(self) {
+        fs::remove_file(&self.path).ok();
+    }
+}
+
+/// Returns the CRC32 of the first `len` bytes of a file.
+fn crc32_prefix(path: &Path, len: u64) -> io::Result<String> {
+    let mut reader = BufReader::new(fs::File::open(path)?).take(len);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0; 1_048_576];
+    let mut read = 0;
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        read += bytes_read as u64;
+        hasher.update(&buffer[..bytes_read]);
+    }
+    if read < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "file is shorter than recorded",
+        ));
+    }
+    Ok(format!("{:08x}", hasher.finalize()))
+}
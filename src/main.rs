@@ -1,26 +1,38 @@
-use clap::{value_parser, Arg, Command};
-use reqwest::blocking::Client;
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::process::{Command as ProcessCommand, Stdio};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::{thread, time};
 use tabled::{settings::Style, Table};
 
+use cli::cli;
+use config::{ConfigFile, APP_NAME};
+use put::Client;
+
 mod browse;
+mod cli;
+mod config;
+mod doctor;
+mod export;
+mod extract;
+mod history;
+mod hooks;
+mod ignore;
+mod jobs;
+mod manifest;
+mod mpv;
+mod natsort;
+mod playlist;
+mod portable;
 mod put;
+mod release;
+mod serve;
+mod service;
+mod stats;
+mod sync;
+mod torrent;
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ConfigFile {
-    api_token: String,
-}
-
-impl Default for ConfigFile {
-    fn default() -> Self {
-        ConfigFile {
-            api_token: "".into(),
-        }
-    }
-}
+// Man pages rendered by build.rs, as (page name, roff source) pairs
+include!(concat!(env!("OUT_DIR"), "/man_pages.rs"));
 
 /// Verifies that the user has a valid API key set and that their account is still active
 fn require_auth(client: &Client, config: &ConfigFile) -> put::account::AccountResponse {
@@ -36,319 +48,258 @@ fn require_auth(client: &Client, config: &ConfigFile) -> put::account::AccountRe
     account
 }
 
-fn cli() -> Command {
-    Command::new("kaput")
-        .about("The unofficial CLI for Put.io")
-        .subcommand_required(true)
-        .arg_required_else_help(true)
-        .subcommand(
-            Command::new("login")
-                .about("Login to your Put.io account")
-                .long_about(
-                    "Logs into your Put.io by saving an auth token locally on your device."
-                )
-        )
-        .subcommand(
-            Command::new("logout")
-                .about("Logout of your account")
-                .long_about(
-                    "Logs out of your Put.io account by removing the auth token saved on your device."
-                )
-        )
-        .subcommand(
-            Command::new("files")
-                .about("Manage your files")
-                .subcommand_required(true)
-                .arg_required_else_help(true)
-                .subcommand(
-                    Command::new("list")
-                        .about("List your files and folders")
-                        .long_about("Lists your files and folders.")
-                        .arg(
-                            Arg::new("FOLDER_ID")
-                            .help("Lists the contents of a folder (optional)")
-                            .value_parser(value_parser!(i64))
-                            .required(false)
-                            .num_args(1)
-                        )
-                        .arg(
-                            Arg::new("self")
-                            .short('s')
-                            .long("self")
-                            .help("If set, returns the info for the folder itself in JSON format")
-                            .required(false)
-                            .num_args(0)
-                        )
-                        .arg(
-                            Arg::new("json")
-                            .long("json")
-                            .help("If set, returns the output in JSON format")
-                            .required(false)
-                            .num_args(0)
-                        )
-                )
-                .subcommand(
-                    Command::new("search")
-                        .about("Search you and your friend's files")
-                        .long_about("Searches you and your friend's files.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("QUERY")
-                            .required(true)
-                            .help("Keyword(s) to search for (required)")
-                        )
-                )
-                .subcommand(
-                    Command::new("url")
-                        .about("Generate a URl for downloading a file or folder")
-                        .long_about("Generates a URl for downloading a file or folder.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("FILE_ID")
-                            .value_parser(value_parser!(i64))
-                            .required(true)
-                            .help("ID of a file or folder (required)")
-                        )
-                )
-                .subcommand(
-                    Command::new("download")
-                        .about("Download a file or folder")
-                        .long_about("Downloads a file or folder from your account to your device.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("TARGET")
-                            .help("File ID or path on Put.io (e.g. 12345 or Movies/film.mkv)")
-                            .required(true)
-                        )
-                        .arg(
-                            Arg::new("path")
-                            .short('p')
-                            .long("path")
-                            .help("Path to download the file(s) to")
-                            .required(false).num_args(1)
-                        )
-                        .arg(
-                            Arg::new("recursive")
-                            .short('r')
-                            .long("recursive")
-                            .help("Download the contents of a folder recursively without creating a zip")
-                            .required(false)
-                            .num_args(0)
-                        )
-                        .arg(
-                            Arg::new("no-replace")
-                            .long("no-replace")
-                            .help("Disable character replacement of files with illegal characters")
-                            .required(false)
-                            .num_args(0)
-                        )
-                )
-                .subcommand(
-                    Command::new("delete")
-                        .about("Delete file(s)")
-                        .long_about("Deletes the specified file(s) on your account.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("FILE_ID")
-                            .value_parser(value_parser!(i64))
-                            .required(true)
-                            .help("ID(s) of a file (required)")
-                        )
-                )
-                .subcommand(
-                    Command::new("upload")
-                        .about("Upload file(s) to your account")
-                        .long_about("Uploads file(s) to your account. It will automatically switch to the resumable upload protocol if the file size is greater than or equal to 50 MB.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("parent_id")
-                                .short('p')
-                                .long("parent")
-                                .help("ID of a Put folder to upload to instead of the root folder")
-                                .required(false)
-                        )
-                        .arg(
-                            Arg::new("is_silent")
-                                .short('s')
-                                .long("silent")
-                                .help("Run CURL in silent mode")
-                                .required(false)
-                                .num_args(0)
-                        )
-                        .arg(
-                            Arg::new("PATH")
-                                .required(true)
-                                .help("Valid paths of files to upload")
-                                .value_parser(value_parser!(PathBuf))
-                        )
-                )
-                .subcommand(
-                    Command::new("move")
-                        .about("Move files")
-                        .long_about("Moves a file to a different parent folder.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("FILE_ID")
-                            .help("ID of the file to move (required)")
-                            .value_parser(value_parser!(i64))
-                            .required(true))
-                        .arg(
-                            Arg::new("PARENT_ID")
-                            .help("ID of the new parent folder (required)")
-                            .value_parser(value_parser!(i64))
-                            .required(true)
-                        )
-                )
-                .subcommand(
-                    Command::new("rename")
-                        .about("Rename files")
-                        .long_about("Renames a file.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("FILE_ID")
-                            .help("ID of the file to rename (required)")
-                            .value_parser(value_parser!(i64))
-                            .required(true)
-                        )
-                        .arg(
-                            Arg::new("NAME")
-                            .help("New name for the file (required)")
-                            .required(true)
-                        )
-                )
-                .subcommand(
-                    Command::new("extractions")
-                        .about("List active extractions")
-                        .long_about("Lists active extractions.")
-                )
-                .subcommand(
-                    Command::new("extract")
-                        .about("Extract ZIP and RAR archives")
-                        .long_about("Extracts ZIP and RAR archives.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("FILE_ID")
-                            .help("ID of the file to extract (required)")
-                            .value_parser(value_parser!(i64))
-                            .required(true)
-                        )
-                )
-                .subcommand(
-                    Command::new("play")
-                        .about("Stream a video file")
-                        .long_about(
-                            "Plays a video file using MPV.\n\
-                            If you do not have MPV installed, visit https://mpv.io/installation/.",
-                        )
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("FILE_ID")
-                            .help("ID of a video file (required)")
-                            .value_parser(value_parser!(i64))
-                            .required(true)
-                        )
-                )
-        )
-        .subcommand(
-            Command::new("transfers")
-                .about("Manage your transfers")
-                .subcommand_required(true)
-                .arg_required_else_help(true)
-                .subcommand(
-                    Command::new("list")
-                        .about("List the current transfers on your account")
-                        .long_about("Lists the current transfers on your account.")
-                )
-                .subcommand(
-                    Command::new("add")
-                        .about("Add new transfer with URL")
-                        .long_about("Adds new transfers to your account with a URL.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("URL")
-                            .help("URL to transfer (required)")
-                            .required(true)
-                        )
-                        .arg(
-                            Arg::new("parent_id")
-                            .short('p')
-                            .long("parent")
-                            .value_parser(value_parser!(i64))
-                            .help("ID of a Put folder to upload to instead of the root folder")
-                            .required(false)
-                        )
-                )
-                .subcommand(
-                    Command::new("cancel")
-                        .about("Cancel or remove transfers")
-                        .long_about("Cancels or removes transfers on your account. If transfer is in SEEDING state, stops seeding. Otherwise, it removes the transfer entry. Does not remove their files.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("TRANSFER_ID")
-                            .help("ID of a transfer (required)")
-                            .value_parser(value_parser!(i64))
-                            .required(true)
-                        )
-                )
-                .subcommand(
-                    Command::new("retry")
-                        .about("Retry failed transfer")
-                        .long_about("Retries failed transfers.")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("TRANSFER_ID")
-                            .help("ID of a transfer (required)")
-                            .value_parser(value_parser!(i64))
-                            .required(true)
-                        )
-                )
-                .subcommand(
-                    Command::new("remove")
-                        .about("Remove transfer(s)")
-                        .long_about("Removes transfer(s).")
-                        .arg_required_else_help(true)
-                        .arg(
-                            Arg::new("TRANSFER_ID")
-                            .help("ID of a transfer (required)")
-                            .value_parser(value_parser!(i64))
-                            .required(true)
-                        )
-                )
-                .subcommand(
-                    Command::new("clean")
-                        .about("Clear all finished transfers")
-                        .long_about(
-                            "Clears all finished transfers on your account. Does not remove files."
-                        )
-                )
-        )
-        .subcommand(
-            Command::new("browse")
-                .about("Browse your files interactively")
-        )
-        .subcommand(
-            Command::new("whoami")
-                .about("Check what account you are logged into")
-                .long_about(
-                    "Returns the username and email of the currently authenticated Put.io user."
-                )
-        )
-        .subcommand(
-            Command::new("debug")
-                .about("Check the current config")
-                .long_about("Returns the current config file and path.")
-        )
+/// Stops before putting `needed` more bytes on an account without room for
+/// them. With `force` it only warns. If the quota can't be fetched, nothing is
+/// stopped.
+fn check_quota(client: &Client, api_token: &String, needed: u64, force: bool) {
+    let Some(disk) = put::account::info(client, api_token)
+        .ok()
+        .and_then(|account| account.info.disk)
+    else {
+        return;
+    };
+    if needed <= disk.avail {
+        return;
+    }
+
+    eprintln!(
+        "{} won't fit: only {} of your {} storage is free.",
+        bytefmt::format(needed),
+        bytefmt::format(disk.avail),
+        bytefmt::format(disk.size)
+    );
+    if !force {
+        eprintln!("Free up space first, or use --force to try anyway.");
+        std::process::exit(1);
+    }
 }
 
-/// Used with Confy to control the config storage location
-const APP_NAME: &str = "kaput-cli";
+fn print_trackers(trackers: &[String]) {
+    if trackers.is_empty() {
+        println!("Trackers:   none (DHT only)");
+        return;
+    }
+    println!("Trackers:");
+    for tracker in trackers {
+        println!("  {}", tracker);
+    }
+}
 
-fn main() {
-    let config: ConfigFile = confy::load(APP_NAME, None).expect("reading config file");
+/// Asks a yes/no question on the terminal. Anything but "y" or "yes" counts as no.
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    io::stdout().flush().ok();
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).ok();
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Converts a setting given on the command line to JSON. Numbers, booleans and
+/// JSON literals are passed through, comma-separated lists become arrays for
+/// list settings, and anything else is sent as a string.
+fn parse_setting_value(key: &str, value: &str) -> serde_json::Value {
+    if let Ok(parsed) = serde_json::from_str(value) {
+        return parsed;
+    }
+    if key == "subtitle_languages" {
+        return value
+            .split(',')
+            .map(|lang| serde_json::Value::from(lang.trim()))
+            .collect();
+    }
+    serde_json::Value::from(value)
+}
+
+/// Adds a transfer from a local `.torrent` file, letting the user pick which files to keep.
+///
+/// Put.io downloads every file in a torrent, so deselected files are removed once the
+/// transfer finishes when `wait` is set. Otherwise they are listed for manual cleanup.
+fn add_local_torrent(
+    client: &Client,
+    api_token: &String,
+    path: &Path,
+    parent_id: Option<&i64>,
+    wait: bool,
+    force: bool,
+) {
+    let data = std::fs::read(path).expect("reading torrent file");
+    let torrent = torrent::parse(&data).unwrap_or_else(|e| panic!("Invalid torrent: {}", e));
+    // Put.io fetches the whole torrent, even files that are deselected below
+    check_quota(client, api_token, torrent.total_size(), force);
+
+    let mut deselected: Vec<String> = vec![];
+    if torrent.is_multi_file() {
+        let items = torrent
+            .files
+            .iter()
+            .map(|f| browse::checklist::ChecklistItem {
+                label: f.path.clone(),
+                detail: bytefmt::format(f.length),
+                // Samples are almost never wanted, so start with them unchecked
+                checked: !f.path.to_lowercase().contains("sample"),
+            })
+            .collect();
+        let title = format!(
+            "{} ({})",
+            torrent.name,
+            bytefmt::format(torrent.total_size())
+        );
+        let Some(checked) = browse::checklist::run(&title, items).expect("showing file list")
+        else {
+            println!("Cancelled.");
+            return;
+        };
+        if !checked.iter().any(|c| *c) {
+            println!("No files selected.");
+            return;
+        }
+        deselected = torrent
+            .files
+            .iter()
+            .zip(checked)
+            .filter(|(_, checked)| !checked)
+            .map(|(f, _)| f.path.clone())
+            .collect();
+    }
+
+    let file_name = path.file_name().unwrap().to_string_lossy().to_string();
+    let transfer = put::transfers::add_torrent(client, api_token, file_name, data, parent_id)
+        .expect("starting transfer");
+
+    println!("Transfer added!");
+    if deselected.is_empty() {
+        return;
+    }
+
+    let Some(transfer) = transfer.filter(|_| wait) else {
+        println!("\nPut.io does not support selecting files when adding a torrent. Remove these once it finishes:");
+        for path in &deselected {
+            println!("  {}/{}", torrent.name, path);
+        }
+        return;
+    };
+
+    println!("Waiting for the transfer to finish...");
+    let transfer = loop {
+        thread::sleep(time::Duration::from_secs(10));
+        let transfer =
+            put::transfers::get(client, api_token, transfer.id).expect("checking transfer");
+        match transfer.status.as_str() {
+            "COMPLETED" | "SEEDING" => break transfer,
+            "ERROR" => panic!("Transfer failed"),
+            _ => println!("{}% done", transfer.percent_done),
+        }
+    };
+
+    // Walk the transfer's folder and match files by their path inside the torrent
+    let mut to_delete: Vec<String> = vec![];
+    put::walk::walk(
+        client,
+        api_token,
+        transfer.file_id,
+        |entry| {
+            if deselected.contains(&entry.path) {
+                to_delete.push(entry.file.id.to_string());
+            }
+            put::walk::Visit::Descend
+        },
+        |_| {},
+    )
+    .expect("listing files");
+
+    if !to_delete.is_empty() {
+        put::files::delete(client, api_token, &to_delete.join(",")).expect("deleting files");
+    }
+    println!("Removed {} deselected file(s).", to_delete.len());
+}
 
+/// How often `transfers list --watch` lists the transfers again for their
+/// progress, when the event feed has nothing to report
+const WATCH_PROGRESS_INTERVAL: time::Duration = time::Duration::from_secs(30);
+
+/// Shows the transfers table and lists it again whenever the event feed, checked
+/// every `interval`, reports a transfer starting, finishing or failing.
+fn watch_transfers(client: &Client, api_token: &String, interval: time::Duration) {
+    let events = put::events::subscribe(client, api_token, interval);
+    loop {
+        let transfers = put::transfers::list(client, api_token).expect("fetching transfers");
+        let table = Table::new(transfers.transfers)
+            .with(Style::markdown())
+            .to_string();
+        // Clear the screen so the table stays in place
+        print!("\x1b[2J\x1b[H");
+        println!("\n# Your transfers\n");
+        println!("{}\n", table);
+        println!("Watching for changes, press Ctrl-C to stop.");
+
+        let next_listing = time::Instant::now() + WATCH_PROGRESS_INTERVAL;
+        while let Some(event) =
+            events.recv_timeout(next_listing.saturating_duration_since(time::Instant::now()))
+        {
+            if event.concerns_transfer() {
+                break;
+            }
+        }
+    }
+}
+
+fn main() {
     let matches: clap::ArgMatches = cli().get_matches();
 
-    let client: Client = reqwest::blocking::Client::new();
+    // `doctor` reports a broken config file instead of stopping on it
+    let loaded: Result<ConfigFile, confy::ConfyError> = confy::load(APP_NAME, None);
+    let config_error = loaded
+        .as_ref()
+        .err()
+        .filter(|_| matches!(matches.subcommand(), Some(("doctor", _))))
+        .map(|e| e.to_string());
+    let mut config: ConfigFile = match config_error {
+        Some(_) => ConfigFile::default(),
+        None => loaded.expect("reading config file"),
+    };
+
+    if let Some(proxy) = matches.get_one::<String>("proxy") {
+        config.http.proxy = Some(proxy.clone());
+    }
+    if let Some(ca_bundle) = matches.get_one::<PathBuf>("ca-bundle") {
+        config.http.ca_bundle = Some(ca_bundle.clone());
+    }
+    let profile = matches.get_one::<String>("profile").map(String::as_str);
+    if let Some(name) = profile {
+        match config.profiles.get(name) {
+            Some(found) => config.api_token = found.api_token.clone(),
+            // Logging in is how a profile is added
+            None if matches!(matches.subcommand(), Some(("login", _))) => {
+                config.api_token = String::new()
+            }
+            None => {
+                eprintln!("There is no profile named '{}' in the config.", name);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    // Downloads and small uploads run through curl, which reads these from the environment
+    if let Some(proxy) = &config.http.proxy {
+        std::env::set_var("ALL_PROXY", proxy);
+    }
+    if let Some(ca_bundle) = &config.http.ca_bundle {
+        match put::curl_ca_bundle(ca_bundle) {
+            Ok(combined) => std::env::set_var("CURL_CA_BUNDLE", combined),
+            Err(e) => {
+                eprintln!(
+                    "Warning: {}, so downloads and uploads will only trust the certificates in {}",
+                    e,
+                    ca_bundle.to_string_lossy()
+                );
+                std::env::set_var("CURL_CA_BUNDLE", ca_bundle);
+            }
+        }
+    }
+    // For the file browser and the torrent file checklist
+    browse::theme::set_preset(config.color_preset);
+
+    let client: Client = put::client(&config.http).expect("building HTTP client");
 
     match matches.subcommand() {
         Some(("login", _sub_matches)) => {
@@ -381,10 +332,7 @@ fn main() {
                 };
 
                 if !oauth_token.is_empty() {
-                    let cfg = ConfigFile {
-                        api_token: oauth_token,
-                    };
-                    confy::store(APP_NAME, None, cfg).expect("updating OAuth token");
+                    config::store_api_token(profile, &oauth_token).expect("updating OAuth token");
                     println!("Signed-in successfully!");
                     break;
                 }
@@ -394,10 +342,7 @@ fn main() {
             }
         }
         Some(("logout", _sub_matches)) => {
-            let cfg = ConfigFile {
-                api_token: "".into(),
-            };
-            confy::store(APP_NAME, None, cfg).expect("updating config file");
+            config::store_api_token(profile, "").expect("updating config file");
             println!("Signed out successfully!")
         }
         Some(("whoami", _sub_matches)) => {
@@ -406,7 +351,13 @@ fn main() {
             println!(
                 "Logged in as {} ({})",
                 account.info.username, account.info.mail
-            )
+            );
+            if let Some(rate_limit) = put::rate_limit::current(&config.api_token) {
+                println!(
+                    "API calls left: {} of {}",
+                    rate_limit.remaining, rate_limit.limit
+                );
+            }
         }
         Some(("debug", _sub_matches)) => {
             let config_path = confy::get_configuration_file_path(APP_NAME, None)
@@ -415,6 +366,45 @@ fn main() {
             println!("Config:");
             println!("{:#?}", config);
         }
+        Some(("doctor", _sub_matches)) => {
+            if !doctor::run(&client, &config, config_error) {
+                std::process::exit(1);
+            }
+        }
+        Some(("config", sub_matches)) => match sub_matches.subcommand() {
+            Some(("export", sub_matches)) => {
+                let out = sub_matches.get_one::<PathBuf>("OUT").expect("missing path");
+                let bundle = portable::export(sub_matches.get_flag("include-secrets"))
+                    .expect("reading config");
+
+                portable::write(&bundle, out).expect("writing export");
+
+                println!("Exported config to {}", out.to_string_lossy());
+                if !sub_matches.get_flag("include-secrets") {
+                    println!(
+                        "API tokens and proxy credentials were left out. Use --include-secrets to include them."
+                    );
+                }
+            }
+            Some(("import", sub_matches)) => {
+                let path = sub_matches
+                    .get_one::<PathBuf>("FILE")
+                    .expect("missing path");
+                let bundle = portable::read(path)
+                    .unwrap_or_else(|e| panic!("Could not read {}: {}", path.to_string_lossy(), e));
+
+                if !sub_matches.get_flag("yes")
+                    && !confirm("Replace your current config, saved searches and download folders?")
+                {
+                    println!("Cancelled.");
+                    return;
+                }
+                portable::import(bundle).expect("saving config");
+
+                println!("Imported config from {}", path.to_string_lossy());
+            }
+            _ => println!("Invalid command. Try using the `--help` flag."),
+        },
         Some(("files", sub_matches)) => match sub_matches.subcommand() {
             Some(("play", sub_matches)) => {
                 require_auth(&client, &config);
@@ -433,13 +423,22 @@ fn main() {
                 let download_url: put::files::UrlResponse =
                     put::files::url(&client, &config.api_token, *file_id).expect("generating url");
 
-                ProcessCommand::new("mpv")
-                    .arg(download_url.url)
-                    .stdout(Stdio::piped())
-                    .spawn()
-                    .expect("error while spawning mpv (is it installed?)")
-                    .wait()
-                    .expect("error while running MPV");
+                let start_from = if sub_matches.get_flag("from-start") {
+                    0
+                } else {
+                    file_info.parent.start_from
+                };
+                let position = mpv::play(&download_url.url, start_from)
+                    .expect("error while running mpv (is it installed?)");
+
+                // Remember where playback stopped, or mark the video watched if it was finished
+                if let Some(position) = position {
+                    if let Err(e) =
+                        mpv::save_position(&client, &config.api_token, *file_id, &position)
+                    {
+                        eprintln!("Warning: could not save playback position: {}", e);
+                    }
+                }
             }
             Some(("list", sub_matches)) => {
                 require_auth(&client, &config);
@@ -451,8 +450,13 @@ fn main() {
                     None => 0,
                 };
 
-                let files = put::files::list(&client, &config.api_token, folder_id)
+                let mut files = put::files::list(&client, &config.api_token, folder_id)
                     .expect("fetching files");
+                if config.natural_sort {
+                    files
+                        .files
+                        .sort_by(|a, b| natsort::compare(&a.name, &b.name));
+                }
 
                 let should_only_show_self: Option<&bool> = sub_matches.get_one::<bool>("self");
 
@@ -475,6 +479,11 @@ fn main() {
                 }
 
                 // Return table format
+                if sub_matches.get_flag("pretty-names") {
+                    for file in files.files.iter_mut() {
+                        file.name = release::pretty_name(&file.name);
+                    }
+                }
                 let table = Table::new(&files.files).with(Style::markdown()).to_string();
                 println!("\n# {}\n", &files.parent.name);
                 println!("{}\n", table);
@@ -510,8 +519,13 @@ fn main() {
                     .get_one::<String>("QUERY")
                     .expect("missing query argument");
 
-                let files =
+                let mut files =
                     put::files::search(&client, &config.api_token, query).expect("querying files");
+                if sub_matches.get_flag("pretty-names") {
+                    for file in files.files.iter_mut() {
+                        file.name = release::pretty_name(&file.name);
+                    }
+                }
 
                 let table = Table::new(files.files).with(Style::markdown()).to_string();
 
@@ -523,7 +537,15 @@ fn main() {
 
                 let recursive = sub_matches.get_flag("recursive");
                 let no_replace = sub_matches.get_flag("no-replace");
-                let dest_path = sub_matches.get_one::<String>("path");
+                // `~` isn't expanded by the shell in the config or in `--path=~/...`
+                let dest_path: Option<String> = sub_matches
+                    .get_one::<String>("path")
+                    .or(config.download_dir.as_ref())
+                    .map(|p| {
+                        browse::download_dirs::expand_home(p)
+                            .to_string_lossy()
+                            .to_string()
+                    });
                 let target = sub_matches
                     .get_one::<String>("TARGET")
                     .expect("missing target");
@@ -533,15 +555,93 @@ fn main() {
                         .unwrap_or_else(|e| panic!("Could not find '{}': {}", target, e)),
                 };
 
-                put::files::download(
-                    &client,
-                    &config.api_token,
-                    file_id,
+                let excludes: Vec<String> = sub_matches
+                    .get_many::<String>("exclude")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect();
+
+                let options = put::files::DownloadOptions {
                     recursive,
-                    dest_path,
+                    path: dest_path.as_ref(),
                     no_replace,
+                    skip_downloaded: !sub_matches.get_flag("ignore-history"),
+                    ignore: ignore::IgnoreRules::load(&config.ignore_patterns, &excludes),
+                    existing: if sub_matches.get_flag("overwrite") {
+                        put::files::ExistingFile::Overwrite
+                    } else if sub_matches.get_flag("skip-existing") {
+                        put::files::ExistingFile::Skip
+                    } else if sub_matches.get_flag("rename") {
+                        put::files::ExistingFile::Rename
+                    } else {
+                        put::files::ExistingFile::Resume
+                    },
+                    long_paths: config.windows_long_paths,
+                    extract: sub_matches
+                        .get_flag("extract")
+                        .then_some(&config.extractors),
+                    post_download: &config.post_download,
+                    profile,
+                };
+
+                if !sub_matches.get_flag("no-preflight") {
+                    let directory = dest_path.as_deref().unwrap_or(".");
+                    // Like in the file browser, a failed check doesn't stop the download
+                    let shortfall = put::files::space_shortfall(
+                        &client,
+                        &config.api_token,
+                        file_id,
+                        directory,
+                        options.existing,
+                    )
+                    .unwrap_or_else(|e| {
+                        eprintln!("Warning: checking free space failed: {}", e);
+                        None
+                    });
+                    if let Some(missing) = shortfall {
+                        eprintln!(
+                            "Not enough free space in {}: {} more is needed.",
+                            directory,
+                            bytefmt::format(missing)
+                        );
+                        if !confirm("Download anyway?") {
+                            println!("Cancelled.");
+                            std::process::exit(1);
+                        }
+                    }
+                }
+
+                put::files::download(&client, &config.api_token, file_id, &options)
+                    .expect("downloading file(s)");
+            }
+            Some(("fetch-url", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let url: &String = sub_matches.get_one("URL").expect("missing URL argument");
+                if !put::transfers::is_fetchable_url(url) {
+                    eprintln!("Only http://, https:// and ftp:// URLs can be fetched.");
+                    std::process::exit(1);
+                }
+
+                let folder_id: Option<i64> =
+                    sub_matches.get_one::<String>("folder").map(|target| {
+                        match target.parse::<i64>() {
+                            Ok(id) => id,
+                            Err(_) => put::files::resolve_path(&client, &config.api_token, target)
+                                .unwrap_or_else(|e| panic!("Could not find '{}': {}", target, e)),
+                        }
+                    });
+
+                put::transfers::add(
+                    &client,
+                    &config.api_token,
+                    url,
+                    folder_id.as_ref(),
+                    config.transfer_callback_url.as_ref(),
                 )
-                .expect("downloading file(s)");
+                .expect("starting transfer");
+
+                println!("Put.io is fetching the URL. Track it with `kaput transfers list`.");
             }
             Some(("delete", sub_matches)) => {
                 require_auth(&client, &config);
@@ -550,6 +650,17 @@ fn main() {
                     .get_one::<String>("FILE_ID")
                     .expect("missing file_id argument");
 
+                let needs_confirmation = !sub_matches.get_flag("yes")
+                    && config.confirm_delete.required(|| {
+                        put::account::settings(&client, &config.api_token)
+                            .map(|r| r.settings.trash_enabled)
+                            .unwrap_or(false)
+                    });
+                if needs_confirmation && !confirm(&format!("Delete file(s) {}?", file_id)) {
+                    println!("Cancelled.");
+                    return;
+                }
+
                 put::files::delete(&client, &config.api_token, file_id).expect("deleting file");
 
                 println!("File deleted!");
@@ -575,16 +686,43 @@ fn main() {
                 let metadata: std::fs::Metadata =
                     std::fs::metadata(path).expect("reading file metadata");
 
+                let upload_size = put::files::local_size(path).expect("reading file sizes");
+                check_quota(
+                    &client,
+                    &config.api_token,
+                    upload_size,
+                    sub_matches.get_flag("force"),
+                );
+
+                if metadata.is_dir() {
+                    let parent_id = parent_id
+                        .map_or(0, |id| id.parse().expect("parent_id must be a folder ID"));
+                    put::files::upload_dir(
+                        &client,
+                        &config.api_token,
+                        path,
+                        parent_id,
+                        sub_matches.get_flag("sidecars"),
+                        &curl_args,
+                    )
+                    .unwrap_or_else(|e| panic!("Uploading folder failed: {}", e));
+                    return;
+                }
+
                 let file_size: u64 = metadata.len();
 
-                if file_size >= 52_428_800 {
+                let result = if file_size >= put::files::RESUMABLE_UPLOAD_SIZE {
                     println!("Mode: Resumable");
 
-                    put::tus::upload(&client, &config.api_token, path, parent_id);
+                    put::tus::upload(&client, &config.api_token, path, parent_id)
                 } else {
                     println!("Mode: Non-resumable");
 
-                    put::files::upload(&config.api_token, path, parent_id, &curl_args);
+                    put::files::upload(&config.api_token, path, parent_id, &curl_args).map(|_| ())
+                };
+                if let Err(e) = result {
+                    eprintln!("Upload failed: {}", e);
+                    std::process::exit(1);
                 }
             }
             Some(("move", sub_matches)) => {
@@ -603,6 +741,33 @@ fn main() {
 
                 println!("File(s) moved!");
             }
+            Some(("cp", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let file_id = sub_matches
+                    .get_one("FILE_ID")
+                    .expect("missing file_id argument");
+
+                let new_parent_id = sub_matches
+                    .get_one("PARENT_ID")
+                    .expect("missing parent_id argument");
+
+                let copied = put::files::copy(&client, &config.api_token, *file_id, *new_parent_id)
+                    .expect("copying file");
+
+                if !copied {
+                    eprintln!("Warning: Put.io can't copy this on its side, so it will be downloaded and uploaded again.\n");
+                    put::files::copy_via_local(
+                        &client,
+                        &config.api_token,
+                        *file_id,
+                        *new_parent_id,
+                    )
+                    .unwrap_or_else(|e| panic!("Copying failed: {}", e));
+                }
+
+                println!("File copied!");
+            }
             Some(("rename", sub_matches)) => {
                 require_auth(&client, &config);
 
@@ -619,6 +784,102 @@ fn main() {
 
                 println!("File renamed!");
             }
+            Some(("rename-bulk", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let target = sub_matches
+                    .get_one::<String>("FOLDER")
+                    .expect("missing folder");
+                let pattern = sub_matches
+                    .get_one::<String>("pattern")
+                    .expect("missing pattern");
+                let folder_id = match target.parse::<i64>() {
+                    Ok(id) => id,
+                    Err(_) => put::files::resolve_path(&client, &config.api_token, target)
+                        .unwrap_or_else(|e| panic!("Could not find '{}': {}", target, e)),
+                };
+
+                let mut files = put::files::list(&client, &config.api_token, folder_id)
+                    .expect("fetching files")
+                    .files;
+                files.sort_by(|a, b| natsort::compare(&a.name, &b.name));
+
+                let mut renames: Vec<(i64, String, String)> = vec![];
+                // Names that stay in the folder as they are
+                let mut kept: HashSet<String> = HashSet::new();
+                for file in files {
+                    match release::rename(pattern, &file.name) {
+                        Ok(new_name) if new_name != file.name && !new_name.is_empty() => {
+                            renames.push((file.id, file.name, new_name))
+                        }
+                        Ok(_) => {
+                            kept.insert(file.name);
+                        }
+                        Err(e) => {
+                            println!("Skipping \"{}\": {}", file.name, e);
+                            kept.insert(file.name);
+                        }
+                    }
+                }
+                if renames.is_empty() {
+                    println!("Nothing to rename.");
+                    return;
+                }
+
+                let preview: Vec<(String, String)> = renames
+                    .iter()
+                    .map(|(_, old, new)| (old.clone(), new.clone()))
+                    .collect();
+                let table = Table::new(preview).with(Style::markdown()).to_string();
+                println!("\n{}\n", table);
+
+                // Nothing is renamed if any two files would end up with the same name
+                let mut targets: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+                for (_, old_name, new_name) in &renames {
+                    targets.entry(new_name).or_default().push(old_name);
+                }
+                let mut conflicts: Vec<String> = vec![];
+                for (new_name, old_names) in &targets {
+                    if old_names.len() > 1 {
+                        conflicts.push(format!(
+                            "\"{}\" would be the new name of {}",
+                            new_name,
+                            old_names.join(", ")
+                        ));
+                    }
+                    if kept.contains(*new_name) {
+                        conflicts.push(format!(
+                            "\"{}\" is already taken, renaming {}",
+                            new_name,
+                            old_names.join(", ")
+                        ));
+                    }
+                }
+                if !conflicts.is_empty() {
+                    eprintln!("Not renaming anything, since names would collide:");
+                    for conflict in conflicts {
+                        eprintln!("  {}", conflict);
+                    }
+                    std::process::exit(1);
+                }
+
+                if sub_matches.get_flag("dry-run") {
+                    return;
+                }
+                if !sub_matches.get_flag("yes")
+                    && !confirm(&format!("Rename {} file(s)?", renames.len()))
+                {
+                    println!("Cancelled.");
+                    return;
+                }
+
+                for (file_id, old_name, new_name) in &renames {
+                    match put::files::rename(&client, &config.api_token, *file_id, new_name) {
+                        Ok(_) => println!("{} -> {}", old_name, new_name),
+                        Err(e) => eprintln!("Renaming \"{}\" failed: {}", old_name, e),
+                    }
+                }
+            }
             Some(("extractions", _sub_matches)) => {
                 require_auth(&client, &config);
 
@@ -644,17 +905,176 @@ fn main() {
 
                 println!("Extraction started!");
             }
+            Some(("manifest", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let target = sub_matches
+                    .get_one::<String>("FOLDER")
+                    .expect("missing folder");
+                let out = sub_matches.get_one::<PathBuf>("out").expect("missing out");
+                let folder_id = match target.parse::<i64>() {
+                    Ok(id) => id,
+                    Err(_) => put::files::resolve_path(&client, &config.api_token, target)
+                        .unwrap_or_else(|e| panic!("Could not find '{}': {}", target, e)),
+                };
+
+                let manifest = manifest::build(&client, &config.api_token, folder_id)
+                    .expect("building manifest");
+
+                std::fs::write(out, serde_json::to_string_pretty(&manifest).unwrap())
+                    .expect("writing manifest");
+
+                println!(
+                    "Saved {} files from \"{}\" to {}",
+                    manifest.entries.len(),
+                    manifest.root_name,
+                    out.to_string_lossy()
+                );
+            }
+            Some(("export", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let target = sub_matches
+                    .get_one::<String>("FOLDER")
+                    .expect("missing folder");
+                let format = sub_matches
+                    .get_one::<String>("format")
+                    .and_then(|name| export::Format::from_name(name))
+                    .expect("missing format");
+                let folder_id = match target.parse::<i64>() {
+                    Ok(id) => id,
+                    Err(_) => put::files::resolve_path(&client, &config.api_token, target)
+                        .unwrap_or_else(|e| panic!("Could not find '{}': {}", target, e)),
+                };
+
+                let mut out: Box<dyn Write> = match sub_matches.get_one::<PathBuf>("out") {
+                    Some(path) => Box::new(io::BufWriter::new(
+                        std::fs::File::create(path).expect("creating output file"),
+                    )),
+                    None => Box::new(io::BufWriter::new(io::stdout().lock())),
+                };
+                let rows = export::write(
+                    &client,
+                    &config.api_token,
+                    folder_id,
+                    sub_matches.get_flag("recursive"),
+                    &format,
+                    &mut out,
+                )
+                .unwrap_or_else(|e| panic!("Exporting failed: {}", e));
+
+                if let Some(path) = sub_matches.get_one::<PathBuf>("out") {
+                    eprintln!("Exported {} entries to {}", rows, path.to_string_lossy());
+                }
+            }
+            Some(("playlist", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let target = sub_matches
+                    .get_one::<String>("FOLDER")
+                    .expect("missing folder");
+                let format = sub_matches
+                    .get_one::<String>("format")
+                    .expect("missing format");
+                let folder_id = match target.parse::<i64>() {
+                    Ok(id) => id,
+                    Err(_) => put::files::resolve_path(&client, &config.api_token, target)
+                        .unwrap_or_else(|e| panic!("Could not find '{}': {}", target, e)),
+                };
+
+                let listing = put::files::list(&client, &config.api_token, folder_id)
+                    .expect("listing folder");
+                let entries = playlist::entries(listing.files);
+                if entries.is_empty() {
+                    eprintln!("No videos or audio files in \"{}\".", listing.parent.name);
+                    std::process::exit(1);
+                }
+                let output = playlist::render(&client, &entries, &config.api_token, format);
+
+                match sub_matches.get_one::<PathBuf>("out") {
+                    Some(out) => {
+                        std::fs::write(out, output).expect("writing playlist");
+                        eprintln!(
+                            "Saved {} entries to {}",
+                            entries.len(),
+                            out.to_string_lossy()
+                        );
+                    }
+                    None => print!("{}", output),
+                }
+            }
+            Some(("verify-manifest", sub_matches)) => {
+                let manifest_path = sub_matches
+                    .get_one::<PathBuf>("MANIFEST")
+                    .expect("missing manifest");
+                let quick = sub_matches.get_flag("quick");
+
+                let contents = std::fs::read_to_string(manifest_path).expect("reading manifest");
+                let manifest: manifest::Manifest =
+                    serde_json::from_str(&contents).expect("parsing manifest");
+
+                let local_root = match sub_matches.get_one::<PathBuf>("path") {
+                    Some(p) => p.clone(),
+                    None => PathBuf::from(".").join(&manifest.root_name),
+                };
+
+                let problems =
+                    manifest::verify(&manifest, &local_root, quick).expect("verifying local files");
+
+                for (entry, problem) in &problems {
+                    match problem {
+                        manifest::Problem::Missing => println!("MISSING  {}", entry.path),
+                        manifest::Problem::SizeMismatch { expected, found } => println!(
+                            "SIZE     {} (expected {}, found {})",
+                            entry.path, expected, found
+                        ),
+                        manifest::Problem::CrcMismatch { expected, found } => println!(
+                            "CRC32    {} (expected {}, found {})",
+                            entry.path, expected, found
+                        ),
+                    }
+                }
+
+                if problems.is_empty() {
+                    println!("All {} files verified!", manifest.entries.len());
+                } else {
+                    println!(
+                        "\n{} of {} files failed verification.",
+                        problems.len(),
+                        manifest.entries.len()
+                    );
+                    std::process::exit(1);
+                }
+            }
             _ => {
                 println!("Invalid command. Try using the `--help` flag.")
             }
         },
         Some(("transfers", sub_matches)) => match sub_matches.subcommand() {
-            Some(("list", _sub_matches)) => {
+            Some(("list", sub_matches)) if sub_matches.get_flag("watch") => {
+                require_auth(&client, &config);
+
+                let interval: &u64 = sub_matches.get_one("interval").expect("missing interval");
+                watch_transfers(
+                    &client,
+                    &config.api_token,
+                    time::Duration::from_secs(*interval),
+                );
+            }
+            Some(("list", sub_matches)) => {
                 require_auth(&client, &config);
 
                 let transfers_response =
                     put::transfers::list(&client, &config.api_token).expect("fetching transfers");
 
+                if sub_matches.get_flag("json") {
+                    println!(
+                        "{}",
+                        serde_json::to_string_pretty(&transfers_response.transfers).unwrap()
+                    );
+                    return;
+                }
+
                 let table = Table::new(transfers_response.transfers)
                     .with(Style::markdown())
                     .to_string();
@@ -665,15 +1085,90 @@ fn main() {
             Some(("add", sub_matches)) => {
                 require_auth(&client, &config);
 
-                let url = sub_matches.get_one("URL").expect("missing URL argument");
+                let url: &String = sub_matches.get_one("URL").expect("missing URL argument");
+                let torrent_path = Path::new(url);
+                let is_local_torrent = url.ends_with(".torrent") && torrent_path.is_file();
+
+                let auto_folder_id = sub_matches
+                    .get_one::<String>("auto-folder")
+                    .map(|template| {
+                        let name = if is_local_torrent {
+                            let data = std::fs::read(torrent_path).expect("reading torrent file");
+                            torrent::parse(&data)
+                                .map(|t| t.name)
+                                .unwrap_or_else(|e| panic!("Invalid torrent: {}", e))
+                        } else {
+                            put::transfers::release_name(url).unwrap_or_else(|| {
+                                panic!("Could not tell the release name from '{}'", url)
+                            })
+                        };
+                        let folder = release::fill_template(template, &release::parse(&name))
+                            .unwrap_or_else(|e| {
+                                panic!("Could not fill '{}' for '{}': {}", template, name, e)
+                            });
+                        println!("Saving to: {}", folder);
+                        put::files::ensure_folder(&client, &config.api_token, &folder)
+                            .unwrap_or_else(|e| panic!("Could not create '{}': {}", folder, e))
+                    });
+                let parent = auto_folder_id.as_ref().or(sub_matches.get_one("parent_id"));
+
+                if is_local_torrent {
+                    // Torrent files are uploaded, and uploads take no callback URL
+                    if sub_matches.contains_id("callback_url") {
+                        eprintln!("--callback-url can't be used with a local .torrent file.");
+                        std::process::exit(1);
+                    }
+                    if config.transfer_callback_url.is_some() {
+                        eprintln!("Warning: `transfer_callback_url` is not called for local .torrent files.");
+                    }
+                    let wait = sub_matches.get_flag("wait");
+                    let force = sub_matches.get_flag("force");
+                    add_local_torrent(
+                        &client,
+                        &config.api_token,
+                        torrent_path,
+                        parent,
+                        wait,
+                        force,
+                    );
+                    return;
+                }
 
-                let parent = sub_matches.get_one("parent_id");
+                let callback_url = sub_matches
+                    .get_one::<String>("callback_url")
+                    .or(config.transfer_callback_url.as_ref());
 
-                put::transfers::add(&client, &config.api_token, url, parent)
+                put::transfers::add(&client, &config.api_token, url, parent, callback_url)
                     .expect("starting transfer");
 
                 println!("Transfer added!");
             }
+            Some(("set", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let transfer_id: &i64 = sub_matches
+                    .get_one("TRANSFER_ID")
+                    .expect("missing transfer_id argument");
+
+                if !sub_matches.get_flag("stop-seeding") {
+                    println!("Nothing to change. Try using the `--help` flag.");
+                    return;
+                }
+
+                let transfer = put::transfers::get(&client, &config.api_token, *transfer_id)
+                    .expect("fetching transfer");
+
+                if transfer.status != "SEEDING" {
+                    println!("Transfer is not seeding (status: {}).", transfer.status);
+                    return;
+                }
+
+                // Cancelling a seeding transfer only stops seeding
+                put::transfers::cancel(&client, &config.api_token, *transfer_id)
+                    .expect("stopping seeding");
+
+                println!("Seeding stopped!");
+            }
             Some(("cancel", sub_matches)) => {
                 require_auth(&client, &config);
 
@@ -722,9 +1217,446 @@ fn main() {
             }
         },
 
-        Some(("browse", _)) => {
+        Some(("sync", sub_matches)) => {
             require_auth(&client, &config);
-            browse::run(&client, &config.api_token).expect("error running file browser");
+
+            let target = sub_matches
+                .get_one::<String>("FOLDER")
+                .expect("missing folder");
+            let local_dir = sub_matches
+                .get_one::<PathBuf>("LOCAL_DIR")
+                .expect("missing local directory");
+            let prefer = sub_matches
+                .get_one::<String>("prefer")
+                .and_then(|name| sync::Prefer::from_name(name));
+            let folder_id = match target.parse::<i64>() {
+                Ok(id) => id,
+                Err(_) => put::files::resolve_path(&client, &config.api_token, target)
+                    .unwrap_or_else(|e| panic!("Could not find '{}': {}", target, e)),
+            };
+
+            let summary = sync::run(
+                &client,
+                &config.api_token,
+                folder_id,
+                local_dir,
+                prefer,
+                config.windows_long_paths,
+                &ignore::IgnoreRules::load(&config.ignore_patterns, &[]),
+            )
+            .unwrap_or_else(|e| panic!("Sync failed: {}", e));
+
+            println!(
+                "\nSync finished: {} downloaded, {} uploaded, {} conflict(s), {} skipped.",
+                summary.downloaded, summary.uploaded, summary.conflicts, summary.skipped
+            );
+        }
+
+        Some(("torrent", sub_matches)) => match sub_matches.subcommand() {
+            Some(("inspect", sub_matches)) => {
+                let target = sub_matches
+                    .get_one::<String>("TARGET")
+                    .expect("missing target");
+
+                if target.trim().starts_with("magnet:") {
+                    let magnet = torrent::parse_magnet(target).unwrap_or_else(|e| panic!("{}", e));
+                    println!(
+                        "Name:       {}",
+                        magnet.name.as_deref().unwrap_or("(unknown)")
+                    );
+                    println!("Info hash:  {}", magnet.info_hash);
+                    match magnet.size {
+                        Some(size) => println!("Total size: {}", bytefmt::format(size)),
+                        None => println!("Total size: (unknown)"),
+                    }
+                    print_trackers(&magnet.trackers);
+                    println!(
+                        "\nThe file list is only known once the torrent's metadata is fetched."
+                    );
+                    return;
+                }
+
+                let data = std::fs::read(target).expect("reading torrent file");
+                let torrent =
+                    torrent::parse(&data).unwrap_or_else(|e| panic!("Invalid torrent: {}", e));
+                let pieces = torrent.total_size().div_ceil(torrent.piece_length.max(1));
+                println!("Name:       {}", torrent.name);
+                println!("Total size: {}", bytefmt::format(torrent.total_size()));
+                println!(
+                    "Piece size: {} ({} pieces)",
+                    bytefmt::format(torrent.piece_length),
+                    pieces
+                );
+                print_trackers(&torrent.trackers);
+
+                let mut builder = tabled::builder::Builder::default();
+                builder.push_record(["path", "size"]);
+                for file in &torrent.files {
+                    builder.push_record([file.path.clone(), bytefmt::format(file.length)]);
+                }
+                println!("\n# Files ({})\n", torrent.files.len());
+                println!("{}\n", builder.build().with(Style::markdown()));
+            }
+            _ => {
+                println!("Invalid command. Try using the `--help` flag.")
+            }
+        },
+
+        Some(("trash", sub_matches)) => match sub_matches.subcommand() {
+            Some(("list", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let files = put::trash::list(&client, &config.api_token).expect("fetching trash");
+
+                if sub_matches.get_flag("json") {
+                    println!("{}", serde_json::to_string_pretty(&files).unwrap());
+                    return;
+                }
+
+                let table = Table::new(files).with(Style::markdown()).to_string();
+
+                println!("\n# Your trash\n");
+                println!("{}\n", table);
+            }
+            Some(("prune", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let Some(max_age) = sub_matches
+                    .get_one::<String>("max-age")
+                    .or(config.trash_max_age.as_ref())
+                else {
+                    eprintln!("No age given. Use --max-age or set `trash_max_age` in the config.");
+                    std::process::exit(1);
+                };
+                let max_age = config::parse_duration(max_age).unwrap_or_else(|e| panic!("{}", e));
+
+                let files = put::trash::list(&client, &config.api_token).expect("fetching trash");
+                let old = put::trash::older_than(files, max_age);
+                if old.is_empty() {
+                    println!("Nothing in the trash is that old.");
+                    return;
+                }
+
+                let size: u64 = old.iter().map(|f| f.size.0).sum();
+                println!("{}\n", Table::new(&old).with(Style::markdown()));
+                if sub_matches.get_flag("dry-run") {
+                    println!(
+                        "Would remove {} item(s), {}.",
+                        old.len(),
+                        bytefmt::format(size)
+                    );
+                    return;
+                }
+                let question = format!(
+                    "Remove {} item(s), {}, for good?",
+                    old.len(),
+                    bytefmt::format(size)
+                );
+                if !sub_matches.get_flag("yes") && !confirm(&question) {
+                    println!("Cancelled.");
+                    return;
+                }
+
+                let file_ids: Vec<String> = old.iter().map(|f| f.id.to_string()).collect();
+                put::trash::delete(&client, &config.api_token, &file_ids.join(","))
+                    .expect("emptying trash");
+
+                println!("Removed {} item(s) from the trash.", old.len());
+            }
+            _ => {
+                println!("Invalid command. Try using the `--help` flag.")
+            }
+        },
+
+        Some(("shares", sub_matches)) => match sub_matches.subcommand() {
+            Some(("create", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let file_id: &i64 = sub_matches.get_one("FILE_ID").expect("missing file_id");
+                let expires_in = sub_matches
+                    .get_one::<String>("expires")
+                    .map(|text| config::parse_duration(text).unwrap_or_else(|e| panic!("{}", e)));
+
+                let share = put::shares::create(&client, &config.api_token, *file_id, expires_in)
+                    .expect("creating public link");
+
+                println!("{}", share.url);
+                let copied = arboard::Clipboard::new().and_then(|mut cb| cb.set_text(&share.url));
+                if copied.is_ok() {
+                    eprintln!("Link copied to the clipboard.");
+                }
+            }
+            Some(("list", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let shares = put::shares::list(&client, &config.api_token)
+                    .expect("fetching public links")
+                    .public_shares;
+
+                if sub_matches.get_flag("json") {
+                    println!("{}", serde_json::to_string_pretty(&shares).unwrap());
+                    return;
+                }
+
+                let table = Table::new(shares).with(Style::markdown()).to_string();
+
+                println!("\n# Your public links\n");
+                println!("{}\n", table);
+            }
+            Some(("revoke", sub_matches)) => {
+                require_auth(&client, &config);
+
+                let share_ids: Vec<String> = sub_matches
+                    .get_many::<i64>("SHARE_ID")
+                    .expect("missing share_id")
+                    .map(|id| id.to_string())
+                    .collect();
+
+                put::shares::revoke(&client, &config.api_token, &share_ids.join(","))
+                    .expect("revoking public links");
+
+                println!("Links revoked!");
+            }
+            _ => {
+                println!("Invalid command. Try using the `--help` flag.")
+            }
+        },
+
+        Some(("account", sub_matches)) => match sub_matches.subcommand() {
+            Some(("settings", sub_matches)) => match sub_matches.subcommand() {
+                Some(("get", sub_matches)) => {
+                    require_auth(&client, &config);
+
+                    let settings = put::account::settings(&client, &config.api_token)
+                        .expect("fetching account settings")
+                        .settings;
+                    let settings = serde_json::to_value(settings).unwrap();
+
+                    match sub_matches.get_one::<String>("KEY") {
+                        Some(key) => match settings.get(key) {
+                            Some(value) => println!("{}", value),
+                            None => {
+                                eprintln!("Unknown setting '{}'.", key);
+                                std::process::exit(1);
+                            }
+                        },
+                        None => println!("{}", serde_json::to_string_pretty(&settings).unwrap()),
+                    }
+                }
+                Some(("set", sub_matches)) => {
+                    require_auth(&client, &config);
+
+                    let key = sub_matches.get_one::<String>("KEY").expect("missing key");
+                    let value = sub_matches
+                        .get_one::<String>("VALUE")
+                        .expect("missing value");
+
+                    let mut changes = serde_json::Map::new();
+                    changes.insert(key.clone(), parse_setting_value(key, value));
+                    put::account::update_settings(&client, &config.api_token, &changes)
+                        .expect("updating account settings");
+
+                    println!("Updated {}.", key);
+                }
+                _ => {
+                    println!("Invalid command. Try using the `--help` flag.")
+                }
+            },
+            _ => {
+                println!("Invalid command. Try using the `--help` flag.")
+            }
+        },
+        Some(("stats", sub_matches)) => {
+            let since = sub_matches.get_one::<String>("since").map(|since| {
+                let seconds = config::parse_duration(since).unwrap_or_else(|e| panic!("{}", e));
+                history::format_timestamp(history::now().saturating_sub(seconds))[..10].to_string()
+            });
+            let summary = stats::summarize(&history::load_totals(), since.as_deref());
+
+            if sub_matches.get_flag("json") {
+                println!("{}", serde_json::to_string_pretty(&summary).unwrap());
+                return;
+            }
+
+            match &summary.since {
+                Some(since) => println!("\n# Transfers since {}\n", since),
+                None => println!("\n# Transfers\n"),
+            }
+            println!("Downloaded: {}", summary.downloaded.describe());
+            println!("Uploaded:   {}\n", summary.uploaded.describe());
+            if summary.days.is_empty() {
+                return;
+            }
+
+            for (title, rows) in [("Per day", &summary.days), ("Per folder", &summary.folders)] {
+                let mut builder = tabled::builder::Builder::default();
+                builder.push_record(["", "downloaded", "uploaded", "files"]);
+                for row in rows {
+                    builder.push_record([
+                        row.name.clone(),
+                        bytefmt::format(row.downloaded.bytes),
+                        bytefmt::format(row.uploaded.bytes),
+                        (row.downloaded.files + row.uploaded.files).to_string(),
+                    ]);
+                }
+                println!("## {}\n", title);
+                println!("{}\n", builder.build().with(Style::markdown()));
+            }
+        }
+        Some(("history", sub_matches)) => {
+            let mut entries = match history::load() {
+                Ok(entries) => entries,
+                Err(e) => {
+                    eprintln!("Could not read the download history: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            entries.reverse();
+
+            match sub_matches.subcommand() {
+                Some(("list", sub_matches)) => {
+                    let limit: &usize = sub_matches.get_one("limit").expect("missing limit");
+                    entries.truncate(*limit);
+
+                    if sub_matches.get_flag("json") {
+                        println!("{}", serde_json::to_string_pretty(&entries).unwrap());
+                        return;
+                    }
+
+                    let table = Table::new(entries).with(Style::markdown()).to_string();
+                    println!("\n# Download history\n");
+                    println!("{}\n", table);
+                }
+                Some(("search", sub_matches)) => {
+                    let query = sub_matches
+                        .get_one::<String>("QUERY")
+                        .expect("missing query argument");
+                    let lower_query = query.to_lowercase();
+
+                    let matches: Vec<history::HistoryEntry> = entries
+                        .into_iter()
+                        .filter(|e| {
+                            e.remote_path.to_lowercase().contains(&lower_query)
+                                || e.local_path.to_lowercase().contains(&lower_query)
+                        })
+                        .collect();
+
+                    let table = Table::new(matches).with(Style::markdown()).to_string();
+                    println!("\n# Downloads matching `{}`\n", query);
+                    println!("{}\n", table);
+                }
+                _ => {
+                    println!("Invalid command. Try using the `--help` flag.")
+                }
+            }
+        }
+        Some(("browse", sub_matches)) => {
+            // The browser handles missing and expired tokens by logging in itself
+            let read_only = sub_matches.get_flag("read-only");
+            browse::run(&client, &config, read_only).expect("error running file browser");
+        }
+        Some(("remote", sub_matches)) => {
+            let command: Vec<&str> = sub_matches
+                .get_many::<String>("COMMAND")
+                .unwrap_or_default()
+                .map(String::as_str)
+                .collect();
+
+            match browse::remote::send(&command.join(" ")) {
+                Ok(reply) if reply.starts_with("error") => {
+                    eprintln!("{}", reply);
+                    std::process::exit(1);
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    eprintln!(
+                        "Could not reach the file browser (is `kaput browse` running?): {}",
+                        e
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+        Some(("serve", sub_matches)) => {
+            require_auth(&client, &config);
+
+            let port: &u16 = sub_matches.get_one("port").expect("missing port");
+            let bind: &String = sub_matches.get_one("bind").expect("missing bind address");
+
+            serve::run(&client, &config.api_token, bind, *port).expect("running server");
+        }
+        Some(("service", sub_matches)) => match sub_matches.subcommand() {
+            Some(("install", sub_matches)) => {
+                let mode = sub_matches.get_one::<String>("mode").expect("missing mode");
+                let args: Vec<String> = sub_matches
+                    .get_many::<String>("ARGS")
+                    .unwrap_or_default()
+                    .cloned()
+                    .collect();
+
+                let path = service::install(mode, &args).expect("writing service file");
+
+                println!("Wrote {}", path.to_string_lossy());
+                println!("Start it with:\n  {}", service::start_hint(mode));
+            }
+            Some(("status", sub_matches)) => {
+                let mode = sub_matches.get_one::<String>("mode").expect("missing mode");
+                let path = service::service_path(mode).expect("finding service file");
+                if !path.exists() {
+                    println!(
+                        "The {} service is not installed. Install it with `kaput service install --mode {}`.",
+                        mode, mode
+                    );
+                    std::process::exit(1);
+                }
+                if !service::status(mode).expect("checking service") {
+                    std::process::exit(1);
+                }
+            }
+            _ => println!("Invalid command. Try using the `--help` flag."),
+        },
+        Some(("help", sub_matches)) => {
+            let names: Vec<&String> = sub_matches
+                .get_many::<String>("COMMAND")
+                .unwrap_or_default()
+                .collect();
+
+            if !sub_matches.get_flag("man") {
+                // Let clap render the help exactly as `kaput <COMMAND> --help` would
+                let args = std::iter::once("kaput")
+                    .chain(names.iter().map(|n| n.as_str()))
+                    .chain(std::iter::once("--help"));
+                if let Err(e) = cli().try_get_matches_from(args) {
+                    e.exit();
+                }
+                return;
+            }
+
+            if let Some(out_dir) = sub_matches.get_one::<PathBuf>("out-dir") {
+                std::fs::create_dir_all(out_dir).expect("creating output directory");
+                for (page, source) in MAN_PAGES {
+                    std::fs::write(out_dir.join(format!("{}.1", page)), source)
+                        .expect("writing man page");
+                }
+                println!(
+                    "Wrote {} man pages to {}",
+                    MAN_PAGES.len(),
+                    out_dir.display()
+                );
+                return;
+            }
+
+            let command: Vec<&str> = std::iter::once("kaput")
+                .chain(names.iter().map(|n| n.as_str()))
+                .collect();
+            let page = command.join("-");
+            match MAN_PAGES.iter().find(|(name, _)| *name == page) {
+                Some((_, source)) => print!("{}", source),
+                None => {
+                    eprintln!("No man page for '{}'.", command.join(" "));
+                    std::process::exit(1);
+                }
+            }
         }
         _ => {
             println!("Invalid command. Try using the `--help` flag.")
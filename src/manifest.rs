@@ -0,0 +1,252 @@
+use std::fs;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use reqwest::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::put::walk::{self, Visit};
+use crate::put::{self, Client};
+
+/// A record of every file in a remote folder tree, used to audit local mirrors.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub root_id: i64,
+    pub root_name: String,
+    pub entries: Vec<ManifestEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Path relative to the root folder, separated by `/`
+    pub path: String,
+    pub id: i64,
+    pub size: u64,
+    pub crc32: Option<String>,
+}
+
+/// Builds a manifest for the folder with the given ID by walking its entire tree.
+pub fn build(client: &Client, api_token: &String, folder_id: i64) -> Result<Manifest, Error> {
+    let mut entries: Vec<ManifestEntry> = vec![];
+
+    let root = walk::walk(
+        client,
+        api_token,
+        folder_id,
+        |entry| {
+            if entry.file.file_type != "FOLDER" {
+                entries.push(ManifestEntry {
+                    path: entry.path,
+                    id: entry.file.id,
+                    size: entry.file.size.0,
+                    crc32: entry.file.crc32,
+                });
+            }
+            Visit::Descend
+        },
+        |progress| {
+            eprint!(
+                "\rListed {} folder(s), {} remaining...",
+                progress.folders_listed, progress.folders_pending
+            );
+        },
+    )?;
+    eprintln!();
+
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(Manifest {
+        root_id: root.id,
+        root_name: root.name,
+        entries,
+    })
+}
+
+pub enum Problem {
+    Missing,
+    SizeMismatch { expected: u64, found: u64 },
+    CrcMismatch { expected: String, found: String },
+}
+
+/// Compares a local directory against the manifest.
+///
+/// Returns every entry that is missing or differs, paired with the problem found.
+/// If `quick` is set, only file sizes are compared.
+pub fn verify<'a>(
+    manifest: &'a Manifest,
+    local_root: &Path,
+    quick: bool,
+) -> io::Result<Vec<(&'a ManifestEntry, Problem)>> {
+    let mut problems = vec![];
+
+    for entry in &manifest.entries {
+        let Some(local_path) = local_path_for(local_root, &entry.path) else {
+            problems.push((entry, Problem::Missing));
+            continue;
+        };
+
+        let found = fs::metadata(&local_path)?.len();
+        if found != entry.size {
+            problems.push((
+                entry,
+                Problem::SizeMismatch {
+                    expected: entry.size,
+                    found,
+                },
+            ));
+            continue;
+        }
+
+        if quick {
+            continue;
+        }
+        if let Some(expected) = &entry.crc32 {
+            let found = crc32_file(&local_path)?;
+            if !found.eq_ignore_ascii_case(expected) {
+                problems.push((
+                    entry,
+                    Problem::CrcMismatch {
+                        expected: expected.clone(),
+                        found,
+                    },
+                ));
+            }
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Finds the local file for a manifest path, accepting names that were
+/// rewritten by the download command's illegal character replacement.
+fn local_path_for(local_root: &Path, relative: &str) -> Option<PathBuf> {
+    let raw = local_root.join(relative);
+    if raw.is_file() {
+        return Some(raw);
+    }
+    let replaced = local_root.join(put::files::replace_illegal_chars(relative));
+    if replaced.is_file() {
+        return Some(replaced);
+    }
+    None
+}
+
+/// Returns the CRC32 of a local file as a lowercase hex string, matching Put.io's format.
+pub fn crc32_file(path: &Path) -> io::Result<String> {
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buffer = vec![0; 1_048_576];
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    Ok(format!("{:08x}", hasher.finalize()))
+}
+
+/// Name of the manifest uploaded next to the files of each folder by
+/// `files upload --sidecars`
+pub const SIDECAR_NAME: &str = ".kaput-manifest.json";
+
+/// What the files of one local folder looked like when it was uploaded, so
+/// their modification times can be restored and their contents checked after
+/// downloading them again.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Sidecar {
+    pub files: Vec<SidecarEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SidecarEntry {
+    pub name: String,
+    pub size: u64,
+    pub crc32: String,
+    /// Modification time in seconds since the Unix epoch
+    pub modified: u64,
+}
+
+/// Builds the sidecar for the files directly inside `dir`.
+pub fn build_sidecar(dir: &Path) -> io::Result<Sidecar> {
+    let mut files = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let metadata = entry.metadata()?;
+        if !metadata.is_file() || name == SIDECAR_NAME {
+            continue;
+        }
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        files.push(SidecarEntry {
+            crc32: crc32_file(&entry.path())?,
+            name,
+            size: metadata.len(),
+            modified,
+        });
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(Sidecar { files })
+}
+
+/// Finds the sidecars under `root`, sets the modification time of every file
+/// that still matches its sidecar entry, and returns the files that don't.
+/// Returns the number of files restored along with the problems.
+pub fn restore_sidecars(root: &Path) -> io::Result<(usize, Vec<(PathBuf, Problem)>)> {
+    let mut restored = 0;
+    let mut problems = vec![];
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                pending.push(entry.path());
+            }
+        }
+
+        let Ok(contents) = fs::read_to_string(dir.join(SIDECAR_NAME)) else {
+            continue;
+        };
+        let Ok(sidecar) = serde_json::from_str::<Sidecar>(&contents) else {
+            continue;
+        };
+        for file in sidecar.files {
+            let Some(path) = local_path_for(&dir, &file.name) else {
+                problems.push((dir.join(&file.name), Problem::Missing));
+                continue;
+            };
+            let found = fs::metadata(&path)?.len();
+            if found != file.size {
+                problems.push((
+                    path,
+                    Problem::SizeMismatch {
+                        expected: file.size,
+                        found,
+                    },
+                ));
+                continue;
+            }
+            let found = crc32_file(&path)?;
+            if !found.eq_ignore_ascii_case(&file.crc32) {
+                problems.push((
+                    path,
+                    Problem::CrcMismatch {
+                        expected: file.crc32,
+                        found,
+                    },
+                ));
+                continue;
+            }
+            fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(UNIX_EPOCH + Duration::from_secs(file.modified))?;
+            restored += 1;
+        }
+    }
+    Ok((restored, problems))
+}
@@ -0,0 +1,108 @@
+use std::io;
+use std::process::{Child, Command, Stdio};
+
+use crate::put::{self, Client};
+
+/// Playback past this percentage of a video counts as having watched it
+pub const WATCHED_PERCENT: f64 = 95.0;
+
+/// Where playback stopped, as reported by mpv.
+pub struct PlaybackPosition {
+    pub seconds: u64,
+    pub percent: f64,
+}
+
+/// Plays `url` in mpv starting `start_from` seconds in and waits for it to exit.
+///
+/// Returns the last position mpv reported over its IPC socket, or `None` if it
+/// could not be read (e.g. on platforms without Unix sockets).
+///
+/// If you do not have MPV installed, visit https://mpv.io/installation/.
+pub fn play(url: &str, start_from: u64) -> io::Result<Option<PlaybackPosition>> {
+    let socket_path = std::env::temp_dir().join(format!("kaput-mpv-{}.sock", std::process::id()));
+
+    let mut command = Command::new("mpv");
+    command.arg(url).stdout(Stdio::piped());
+    if start_from > 0 {
+        command.arg(format!("--start={}", start_from));
+    }
+    if cfg!(unix) {
+        command.arg(format!("--input-ipc-server={}", socket_path.display()));
+    }
+
+    let child = command.spawn()?;
+    let position = track_position(child, &socket_path)?;
+    std::fs::remove_file(&socket_path).ok();
+
+    Ok(position)
+}
+
+/// Remembers where playback of a file stopped, or marks it watched if it was finished.
+pub fn save_position(
+    client: &Client,
+    api_token: &String,
+    file_id: i64,
+    position: &PlaybackPosition,
+) -> Result<(), reqwest::Error> {
+    if position.percent >= WATCHED_PERCENT {
+        put::files::set_watch_status(client, api_token, &file_id.to_string(), true)
+    } else {
+        put::files::set_start_from(client, api_token, file_id, position.seconds)
+    }
+}
+
+#[cfg(unix)]
+fn track_position(
+    mut child: Child,
+    socket_path: &std::path::Path,
+) -> io::Result<Option<PlaybackPosition>> {
+    let mut last = None;
+    while child.try_wait()?.is_none() {
+        if let Some(position) = query_position(socket_path) {
+            last = Some(position);
+        }
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+    Ok(last)
+}
+
+#[cfg(not(unix))]
+fn track_position(
+    mut child: Child,
+    _socket_path: &std::path::Path,
+) -> io::Result<Option<PlaybackPosition>> {
+    child.wait()?;
+    Ok(None)
+}
+
+#[cfg(unix)]
+fn query_position(socket_path: &std::path::Path) -> Option<PlaybackPosition> {
+    Some(PlaybackPosition {
+        seconds: get_property(socket_path, "time-pos")? as u64,
+        percent: get_property(socket_path, "percent-pos")?,
+    })
+}
+
+/// Reads a numeric property using mpv's JSON IPC protocol.
+#[cfg(unix)]
+fn get_property(socket_path: &std::path::Path, name: &str) -> Option<f64> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let mut stream = UnixStream::connect(socket_path).ok()?;
+    stream
+        .set_read_timeout(Some(std::time::Duration::from_millis(500)))
+        .ok()?;
+    let request = serde_json::json!({ "command": ["get_property", name] });
+    writeln!(stream, "{}", request).ok()?;
+
+    // mpv may also send unrelated event lines, so look for the reply
+    for line in BufReader::new(stream).lines() {
+        let reply: serde_json::Value = serde_json::from_str(&line.ok()?).ok()?;
+        if reply.get("event").is_some() {
+            continue;
+        }
+        return reply.get("data")?.as_f64();
+    }
+    None
+}
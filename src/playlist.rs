@@ -0,0 +1,80 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use crate::natsort;
+use crate::put::{files::File, Client};
+
+/// Returns the playable files of a folder listing in episode order.
+pub fn entries(files: Vec<File>) -> Vec<File> {
+    let mut entries: Vec<File> = files
+        .into_iter()
+        .filter(|f| f.file_type == "VIDEO" || f.file_type == "AUDIO")
+        .collect();
+    entries.sort_by(|a, b| natsort::compare(&a.name, &b.name));
+    entries
+}
+
+pub fn stream_url(client: &Client, file_id: i64, api_token: &str) -> String {
+    client.api_url(&format!(
+        "/files/{}/stream?oauth_token={}",
+        file_id, api_token
+    ))
+}
+
+/// Renders a playlist of stream URLs, as `pls` or otherwise as `m3u8`.
+pub fn render(client: &Client, entries: &[File], api_token: &str, format: &str) -> String {
+    let mut out = String::new();
+    if format == "pls" {
+        out.push_str("[playlist]\n");
+        for (i, file) in entries.iter().enumerate() {
+            let n = i + 1;
+            out.push_str(&format!(
+                "File{}={}\n",
+                n,
+                stream_url(client, file.id, api_token)
+            ));
+            out.push_str(&format!("Title{}={}\n", n, file.name));
+            out.push_str(&format!("Length{}=-1\n", n));
+        }
+        out.push_str(&format!("NumberOfEntries={}\nVersion=2\n", entries.len()));
+    } else {
+        out.push_str("#EXTM3U\n");
+        for file in entries {
+            out.push_str(&format!("#EXTINF:-1,{}\n", file.name));
+            out.push_str(&stream_url(client, file.id, api_token));
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Writes a playlist to a new directory in the temp dir that only the user can
+/// read, since its URLs carry the API token. Neither the directory nor the file
+/// may exist beforehand, so a planted file or symlink is never written through.
+/// Returns the file's path; the caller removes its directory when done.
+pub fn write_private(contents: &str, extension: &str) -> io::Result<PathBuf> {
+    let mut random = [0u8; 8];
+    getrandom::getrandom(&mut random).map_err(|e| io::Error::other(e.to_string()))?;
+    let suffix: String = random.iter().map(|b| format!("{:02x}", b)).collect();
+    let dir = std::env::temp_dir().join(format!("kaput-playlist-{}", suffix));
+
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(&dir)?;
+
+    let path = dir.join(format!("playlist.{}", extension));
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let written = options
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents.as_bytes()));
+    if let Err(e) = written {
+        fs::remove_dir_all(&dir).ok();
+        return Err(e);
+    }
+    Ok(path)
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::browse::download_dirs::RecentDirs;
+use crate::browse::searches::SearchHistory;
+use crate::config::{ConfigFile, APP_NAME};
+
+/// The config file and browser data in one TOML file, for copying a setup to
+/// another machine.
+#[derive(Serialize, Deserialize)]
+pub struct Bundle {
+    pub config: ConfigFile,
+    #[serde(default)]
+    pub searches: SearchHistory,
+    #[serde(default)]
+    pub download_dirs: RecentDirs,
+}
+
+/// Collects the stored config and browser data. With `include_secrets` unset,
+/// the API tokens of the default account and every profile are left out, as
+/// is any user name and password in the proxy URL.
+pub fn export(include_secrets: bool) -> Result<Bundle, String> {
+    let mut config: ConfigFile = confy::load(APP_NAME, None).map_err(|e| e.to_string())?;
+    if !include_secrets {
+        config.api_token = String::new();
+        for profile in config.profiles.values_mut() {
+            profile.api_token = String::new();
+        }
+        config.http.proxy = config.http.proxy.as_deref().map(without_credentials);
+    }
+    Ok(Bundle {
+        config,
+        searches: SearchHistory::load(),
+        download_dirs: RecentDirs::load(),
+    })
+}
+
+pub fn write(bundle: &Bundle, path: &Path) -> io::Result<()> {
+    let contents = toml::to_string_pretty(bundle).map_err(io::Error::other)?;
+    fs::write(path, contents)
+}
+
+pub fn read(path: &Path) -> Result<Bundle, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    toml::from_str(&contents).map_err(|e| e.to_string())
+}
+
+/// Replaces the stored config and browser data with the bundle's. A bundle
+/// exported without secrets keeps the API tokens that are already stored, and
+/// the stored proxy credentials if it names the same proxy.
+pub fn import(mut bundle: Bundle) -> Result<(), String> {
+    let stored: ConfigFile = confy::load(APP_NAME, None).map_err(|e| e.to_string())?;
+    if bundle.config.api_token.is_empty() {
+        bundle.config.api_token = stored.api_token;
+    }
+    for (name, profile) in bundle.config.profiles.iter_mut() {
+        if profile.api_token.is_empty() {
+            if let Some(found) = stored.profiles.get(name) {
+                profile.api_token = found.api_token.clone();
+            }
+        }
+    }
+    if let (Some(proxy), Some(stored_proxy)) = (&bundle.config.http.proxy, stored.http.proxy) {
+        if *proxy == without_credentials(&stored_proxy) {
+            bundle.config.http.proxy = Some(stored_proxy);
+        }
+    }
+    confy::store(APP_NAME, None, &bundle.config).map_err(|e| e.to_string())?;
+    bundle.searches.save().map_err(|e| e.to_string())?;
+    bundle.download_dirs.save().map_err(|e| e.to_string())
+}
+
+/// Returns a proxy URL with any `user:password@` removed.
+fn without_credentials(proxy: &str) -> String {
+    let (scheme, rest) = match proxy.find("://") {
+        Some(i) => proxy.split_at(i + 3),
+        None => ("", proxy),
+    };
+    let host_end = rest.find('/').unwrap_or(rest.len());
+    match rest[..host_end].rfind('@') {
+        Some(at) => format!("{}{}", scheme, &rest[at + 1..]),
+        None => proxy.to_string(),
+    }
+}
@@ -1,6 +1,167 @@
 pub mod account;
+pub mod cache;
+pub mod cancel;
+pub mod events;
 pub mod files;
 pub mod oob;
+pub mod rate_limit;
+pub mod shares;
 pub mod transfers;
+pub mod trash;
 pub mod tus;
+pub mod walk;
 pub mod zips;
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use reqwest::blocking::{Client as HttpClient, RequestBuilder};
+use reqwest::{Certificate, Proxy};
+
+use crate::config::{self, HttpConfig};
+
+/// Deadlines for particular operations, taken from the config passed to `client`.
+/// `None` leaves the client-wide timeout in charge.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Timeouts {
+    pub list: Option<Duration>,
+    pub search: Option<Duration>,
+    pub download_connect: Option<Duration>,
+}
+
+/// An HTTP client together with the API address and operation deadlines of
+/// the config it was built from. Derefs to the reqwest client, and cloning it
+/// shares the connection pool.
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: HttpClient,
+    /// Where API requests are sent, without a trailing `/`
+    api_base_url: String,
+    pub timeouts: Timeouts,
+}
+
+impl Client {
+    /// Returns the URL of an API endpoint, e.g. `client.api_url("/files/list")`.
+    pub fn api_url(&self, path: &str) -> String {
+        format!("{}{}", self.api_base_url, path)
+    }
+}
+
+impl Deref for Client {
+    type Target = HttpClient;
+
+    fn deref(&self) -> &HttpClient {
+        &self.http
+    }
+}
+
+const DEFAULT_API_BASE_URL: &str = "https://api.put.io/v2";
+
+/// Applies an operation's deadline to a request, if one is configured.
+pub fn with_timeout(request: RequestBuilder, timeout: Option<Duration>) -> RequestBuilder {
+    match timeout {
+        Some(timeout) => request.timeout(timeout),
+        None => request,
+    }
+}
+
+/// Builds the HTTP client shared by every command. Cloning the returned client
+/// shares its connection pool.
+pub fn client(http: &HttpConfig) -> Result<Client, String> {
+    let api_base_url = match &http.api_base_url {
+        Some(base_url) => {
+            reqwest::Url::parse(base_url)
+                .map_err(|e| format!("invalid API base URL '{}': {}", base_url, e))?;
+            base_url.trim_end_matches('/').to_string()
+        }
+        None => DEFAULT_API_BASE_URL.to_string(),
+    };
+
+    let mut builder = HttpClient::builder()
+        .connect_timeout(Duration::from_secs(http.connect_timeout_secs))
+        .pool_max_idle_per_host(http.pool_max_idle_per_host)
+        .tcp_keepalive(Duration::from_secs(60))
+        .gzip(true)
+        .deflate(true);
+
+    if let Some(user_agent) = &http.user_agent {
+        builder = builder.user_agent(user_agent);
+    }
+
+    builder = match http.timeout_secs {
+        0 => builder.timeout(None),
+        secs => builder.timeout(Duration::from_secs(secs)),
+    };
+
+    if let Some(proxy) = &http.proxy {
+        let proxy = Proxy::all(proxy).map_err(|e| format!("invalid proxy '{}': {}", proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(ca_bundle) = &http.ca_bundle {
+        let pem = fs::read(ca_bundle)
+            .map_err(|e| format!("reading CA bundle {}: {}", ca_bundle.to_string_lossy(), e))?;
+        let certificates = Certificate::from_pem_bundle(&pem)
+            .map_err(|e| format!("invalid CA bundle {}: {}", ca_bundle.to_string_lossy(), e))?;
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    let seconds = |secs: u64| (secs > 0).then(|| Duration::from_secs(secs));
+    Ok(Client {
+        http: builder.build().map_err(|e| e.to_string())?,
+        api_base_url,
+        timeouts: Timeouts {
+            list: seconds(http.list_timeout_secs),
+            search: seconds(http.search_timeout_secs),
+            download_connect: seconds(http.download_connect_timeout_secs),
+        },
+    })
+}
+
+/// Where distributions keep the system's root certificates as one PEM file
+const SYSTEM_CA_BUNDLES: &[&str] = &[
+    "/etc/ssl/certs/ca-certificates.crt",
+    "/etc/pki/tls/certs/ca-bundle.crt",
+    "/etc/pki/ca-trust/extracted/pem/tls-ca-bundle.pem",
+    "/etc/ssl/ca-bundle.pem",
+    "/etc/pki/tls/cacert.pem",
+    "/etc/ssl/cert.pem",
+    "/usr/local/etc/openssl/cert.pem",
+    "/usr/local/share/certs/ca-root-nss.crt",
+];
+
+/// Returns a CA bundle for curl with the system's root certificates followed by
+/// those in `ca_bundle`. curl replaces its trust store with `CURL_CA_BUNDLE`
+/// rather than adding to it, so passing the extra certificates alone would make
+/// every other site untrusted.
+pub fn curl_ca_bundle(ca_bundle: &Path) -> Result<PathBuf, String> {
+    let system = std::env::var_os("SSL_CERT_FILE")
+        .map(PathBuf::from)
+        .into_iter()
+        .chain(SYSTEM_CA_BUNDLES.iter().map(PathBuf::from))
+        .find(|path| path.is_file())
+        .ok_or("no system CA bundle found")?;
+
+    let mut combined =
+        fs::read(&system).map_err(|e| format!("reading {}: {}", system.to_string_lossy(), e))?;
+    let extra = fs::read(ca_bundle)
+        .map_err(|e| format!("reading CA bundle {}: {}", ca_bundle.to_string_lossy(), e))?;
+    combined.push(b'\n');
+    combined.extend(extra);
+
+    // Written next to the final file and renamed, so other running commands
+    // never read half of it
+    let path = config::data_file_path("ca-bundle.pem");
+    let partial = path.with_extension(format!("pem.{}", std::process::id()));
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(&partial, combined)
+        .and_then(|_| fs::rename(&partial, &path))
+        .map_err(|e| format!("writing {}: {}", path.to_string_lossy(), e))?;
+    Ok(path)
+}
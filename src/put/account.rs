@@ -1,11 +1,24 @@
-use reqwest::{blocking::Client, Error};
+use reqwest::Error;
+
+use crate::put::{rate_limit::SendTracked, Client};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AccountInfo {
     pub username: String,
     pub mail: String,
     pub account_active: bool,
+    #[serde(default)]
+    pub disk: Option<Disk>,
+}
+
+/// Storage use of the account in bytes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Disk {
+    pub avail: u64,
+    pub used: u64,
+    pub size: u64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,13 +26,63 @@ pub struct AccountResponse {
     pub info: AccountInfo,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountSettings {
+    /// Whether deleted files are moved to the trash instead of being removed
+    #[serde(default)]
+    pub trash_enabled: bool,
+    /// Folder new transfers are saved to
+    #[serde(default)]
+    pub default_download_folder: i64,
+    /// Preferred subtitle languages as ISO 639-2 codes, e.g. `eng`
+    #[serde(default)]
+    pub subtitle_languages: Vec<String>,
+    /// Every other setting, kept so they can be shown as-is
+    #[serde(flatten)]
+    pub other: Map<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsResponse {
+    pub settings: AccountSettings,
+}
+
 /// Returns the user's account info.
 pub fn info(client: &Client, api_key: &String) -> Result<AccountResponse, Error> {
     let response: AccountResponse = client
-        .get("https://api.put.io/v2/account/info")
+        .get(client.api_url("/account/info"))
         .header("authorization", format!("Bearer {api_key}"))
-        .send()?
+        .send_tracked()?
+        .error_for_status()?
         .json()?;
 
     Ok(response)
 }
+
+/// Returns the user's account settings.
+pub fn settings(client: &Client, api_key: &String) -> Result<SettingsResponse, Error> {
+    let response: SettingsResponse = client
+        .get(client.api_url("/account/settings"))
+        .header("authorization", format!("Bearer {api_key}"))
+        .send_tracked()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(response)
+}
+
+/// Changes account settings. Only the given keys are updated.
+pub fn update_settings(
+    client: &Client,
+    api_key: &String,
+    changes: &Map<String, Value>,
+) -> Result<(), Error> {
+    client
+        .post(client.api_url("/account/settings"))
+        .json(changes)
+        .header("authorization", format!("Bearer {api_key}"))
+        .send_tracked()?
+        .error_for_status()?;
+
+    Ok(())
+}
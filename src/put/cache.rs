@@ -0,0 +1,89 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use blake2::{Blake2b512, Digest};
+use reqwest::blocking::RequestBuilder;
+use reqwest::header::HeaderMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::config;
+
+/// A response body saved together with the validators needed to revalidate it.
+#[derive(Serialize, Deserialize)]
+pub struct CachedResponse<T> {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub body: T,
+}
+
+/// Returns the cache file for `key`. Keys are hashed with the API token so
+/// different accounts never share entries. Listings are private, so they're
+/// kept with the user's other data rather than in the shared temp directory.
+fn cache_path(api_token: &str, key: &str) -> PathBuf {
+    let mut hasher = Blake2b512::new();
+    hasher.update(format!("{}_{}", api_token, key));
+    let hash: String = format!("{:x}", hasher.finalize());
+    config::data_file_path("cache").join(&hash[..32])
+}
+
+/// Creates the cache directory, readable only by the user on Unix.
+fn create_cache_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+/// Loads a cached response, if one exists and can still be parsed.
+pub fn load<T: DeserializeOwned>(api_token: &str, key: &str) -> Option<CachedResponse<T>> {
+    let contents = fs::read_to_string(cache_path(api_token, key)).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+/// Saves a response body along with the validators from its `headers`. Responses
+/// without an `ETag` or `Last-Modified` header are not cached since they can't be revalidated.
+pub fn store<T: Serialize>(api_token: &str, key: &str, headers: &HeaderMap, body: T) {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(String::from)
+    };
+    let cached = CachedResponse {
+        etag: header("etag"),
+        last_modified: header("last-modified"),
+        body,
+    };
+    if cached.etag.is_none() && cached.last_modified.is_none() {
+        return;
+    }
+
+    // Caching is best-effort, so failures to write are ignored
+    let path = cache_path(api_token, key);
+    if let Some(dir) = path.parent() {
+        create_cache_dir(dir).ok();
+    }
+    if let Ok(contents) = serde_json::to_string(&cached) {
+        fs::write(path, contents).ok();
+    }
+}
+
+/// Adds conditional request headers for a cached response.
+pub fn conditional<T>(
+    request: RequestBuilder,
+    cached: Option<&CachedResponse<T>>,
+) -> RequestBuilder {
+    let Some(cached) = cached else {
+        return request;
+    };
+    let mut request = request;
+    if let Some(etag) = &cached.etag {
+        request = request.header("if-none-match", etag);
+    }
+    if let Some(last_modified) = &cached.last_modified {
+        request = request.header("if-modified-since", last_modified);
+    }
+    request
+}
@@ -0,0 +1,126 @@
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use reqwest::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::put::{rate_limit::SendTracked, Client};
+
+/// Something that happened on the account, e.g. a transfer finishing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    #[serde(default)]
+    pub created_at: String,
+    #[serde(default)]
+    pub transfer_name: Option<String>,
+    #[serde(default)]
+    pub file_id: Option<i64>,
+}
+
+impl Event {
+    /// True for events that end a transfer, successfully or not.
+    pub fn ends_transfer(&self) -> bool {
+        matches!(
+            self.event_type.as_str(),
+            "transfer_completed" | "transfer_error"
+        )
+    }
+
+    /// True for events about a transfer, e.g. one starting or failing.
+    pub fn concerns_transfer(&self) -> bool {
+        self.event_type.starts_with("transfer_")
+    }
+
+    /// True for events that put new files on the account.
+    pub fn adds_files(&self) -> bool {
+        matches!(
+            self.event_type.as_str(),
+            "transfer_completed" | "upload" | "file_shared" | "zip_created"
+        )
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventsResponse {
+    pub events: Vec<Event>,
+}
+
+/// Returns the account's recent events, newest first.
+pub fn list(client: &Client, api_token: &String) -> Result<EventsResponse, Error> {
+    let response: EventsResponse = client
+        .get(client.api_url("/events/list"))
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(response)
+}
+
+/// A running watch on the account's event feed. The background thread stops
+/// as soon as this is dropped.
+pub struct Subscription {
+    events: Receiver<Event>,
+    /// Never sent on; dropping it wakes the thread up to stop
+    _stop: Sender<()>,
+}
+
+impl Subscription {
+    /// Returns an event that has arrived, without waiting.
+    pub fn try_recv(&self) -> Option<Event> {
+        self.events.try_recv().ok()
+    }
+
+    /// Waits up to `timeout` for the next event.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Event> {
+        self.events.recv_timeout(timeout).ok()
+    }
+}
+
+/// Watches the account's event feed, checking it every `interval`, and passes
+/// on each new event as it appears.
+///
+/// Put.io has no push channel for API clients, so one cheap request for the
+/// whole feed stands in for polling every transfer or folder separately. Events
+/// from before the subscription started are skipped. The token is fixed for the
+/// life of the subscription, so a new one is needed after logging in again.
+pub fn subscribe(client: &Client, api_token: &str, interval: Duration) -> Subscription {
+    let (tx, rx) = mpsc::channel();
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    let client = client.clone();
+    let api_token = api_token.to_string();
+    thread::spawn(move || {
+        let mut last_id: Option<i64> = None;
+        loop {
+            // Failures are skipped; the next check catches up on missed events
+            if let Ok(response) = list(&client, &api_token) {
+                let newest = response.events.iter().map(|e| e.id).max();
+                if let Some(seen) = last_id {
+                    let mut new: Vec<Event> = response
+                        .events
+                        .into_iter()
+                        .filter(|e| e.id > seen)
+                        .collect();
+                    new.sort_by_key(|e| e.id);
+                    for event in new {
+                        if tx.send(event).is_err() {
+                            return;
+                        }
+                    }
+                }
+                last_id = newest.max(last_id).or(Some(0));
+            }
+            if let Err(RecvTimeoutError::Disconnected) = stop_rx.recv_timeout(interval) {
+                return;
+            }
+        }
+    });
+    Subscription {
+        events: rx,
+        _stop: stop_tx,
+    }
+}
@@ -1,17 +1,28 @@
+use std::collections::HashMap;
 use std::path::Path;
 use std::process::{Command as ProcessCommand, Stdio};
+use std::time::Instant;
 use std::{fmt, fs};
 
 use reqwest::blocking::multipart::Form;
-use reqwest::blocking::Client;
-use reqwest::Error;
+use reqwest::{Error, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, DefaultOnNull};
 use tabled::Tabled;
 
-use crate::put;
-
-#[derive(Debug, Serialize, Deserialize)]
+use crate::config::PostDownloadHook;
+use crate::extract;
+use crate::history::{self, Direction, HistoryEntry};
+use crate::hooks::{self, DownloadContext};
+use crate::ignore::IgnoreRules;
+use crate::jobs::JobState;
+use crate::manifest;
+use crate::put::cache::{self, CachedResponse};
+use crate::put::rate_limit::SendTracked;
+use crate::put::walk::{self, Visit};
+use crate::put::{self, Client};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileSize(pub u64);
 
 impl fmt::Display for FileSize {
@@ -21,7 +32,7 @@ impl fmt::Display for FileSize {
 }
 
 #[serde_as]
-#[derive(Debug, Serialize, Deserialize, Tabled)]
+#[derive(Debug, Clone, Serialize, Deserialize, Tabled)]
 pub struct File {
     pub id: i64,
     pub name: String,
@@ -33,6 +44,22 @@ pub struct File {
     pub updated_at: String,
     #[serde_as(as = "DefaultOnNull")]
     pub parent_id: i64,
+    #[serde(default)]
+    #[tabled(skip)]
+    pub crc32: Option<String>,
+    /// Saved playback position of a video in seconds, 0 if not started
+    #[serde(default)]
+    #[serde_as(as = "DefaultOnNull")]
+    #[tabled(skip)]
+    pub start_from: u64,
+    #[serde(default)]
+    #[serde_as(as = "DefaultOnNull")]
+    #[tabled(skip)]
+    pub is_watched: bool,
+    /// URL of a preview image, for images and videos
+    #[serde(default)]
+    #[tabled(skip)]
+    pub screenshot: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -96,16 +123,89 @@ pub fn resolve_path(client: &Client, api_token: &String, path: &str) -> Result<i
     Ok(current_id)
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateFolderResponse {
+    pub file: File,
+}
+
+/// Creates a folder named `name` inside `parent_id`.
+pub fn create_folder(
+    client: &Client,
+    api_token: &String,
+    name: &str,
+    parent_id: i64,
+) -> Result<File, Error> {
+    let form: Form = Form::new()
+        .text("name", name.to_owned())
+        .text("parent_id", parent_id.to_string());
+
+    let response: CreateFolderResponse = client
+        .post(client.api_url("/files/create-folder"))
+        .multipart(form)
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(response.file)
+}
+
+/// Like `resolve_path`, but creates the folders of `path` that don't exist yet.
+/// Returns the ID of the last one.
+pub fn ensure_folder(client: &Client, api_token: &String, path: &str) -> Result<i64, String> {
+    let mut current_id: i64 = 0;
+
+    for part in path.split('/').map(str::trim).filter(|s| !s.is_empty()) {
+        let response = list(client, api_token, current_id).map_err(|e| e.to_string())?;
+
+        let found = response
+            .files
+            .iter()
+            .find(|f| f.name.to_lowercase() == part.to_lowercase());
+
+        current_id = match found {
+            Some(file) if file.file_type == "FOLDER" => file.id,
+            Some(_) => return Err(format!("'{}' is not a folder", part)),
+            None => {
+                create_folder(client, api_token, part, current_id)
+                    .map_err(|e| format!("creating folder '{}': {}", part, e))?
+                    .id
+            }
+        };
+    }
+
+    Ok(current_id)
+}
+
 /// Returns the user's files.
+///
+/// Listings are cached locally and revalidated with conditional request headers,
+/// so unchanged folders are served from the cache when Put.io answers `304 Not Modified`.
 pub fn list(client: &Client, api_token: &String, parent_id: i64) -> Result<FilesResponse, Error> {
     const LIST_PAGE_SIZE: i64 = 1000;
 
-    let response: FilesListPageResponse = client
-        .get("https://api.put.io/v2/files/list")
+    let cache_key = format!("files_list_{parent_id}");
+    let cached: Option<CachedResponse<FilesResponse>> = cache::load(api_token, &cache_key);
+
+    let timeout = client.timeouts.list;
+    let request = client
+        .get(client.api_url("/files/list"))
         .query(&[("parent_id", parent_id), ("per_page", LIST_PAGE_SIZE)])
-        .header("authorization", format!("Bearer {api_token}"))
-        .send()?
-        .json()?;
+        .header("authorization", format!("Bearer {api_token}"));
+    let request = put::with_timeout(request, timeout);
+
+    let http_response = cache::conditional(request, cached.as_ref())
+        .send_tracked()?
+        .error_for_status()?;
+
+    if http_response.status() == StatusCode::NOT_MODIFIED {
+        if let Some(cached) = cached {
+            return Ok(cached.body);
+        }
+    }
+
+    let validators = http_response.headers().clone();
+    let response: FilesListPageResponse = http_response.json()?;
 
     let parent = response.parent;
     let mut files = response.files;
@@ -121,18 +221,23 @@ pub fn list(client: &Client, api_token: &String, parent_id: i64) -> Result<Files
             per_page: LIST_PAGE_SIZE,
         };
 
-        let page: FilesContinueResponse = client
-            .post("https://api.put.io/v2/files/list/continue")
+        let page_request = client
+            .post(client.api_url("/files/list/continue"))
             .form(&request)
-            .header("authorization", format!("Bearer {api_token}"))
-            .send()?
+            .header("authorization", format!("Bearer {api_token}"));
+        let page: FilesContinueResponse = put::with_timeout(page_request, timeout)
+            .send_tracked()?
+            .error_for_status()?
             .json()?;
 
         files.extend(page.files);
         cursor = page.cursor;
     }
 
-    Ok(FilesResponse { files, parent })
+    let response = FilesResponse { files, parent };
+    cache::store(api_token, &cache_key, &validators, &response);
+
+    Ok(response)
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -147,10 +252,12 @@ pub fn search(
     api_token: &String,
     query: &String,
 ) -> Result<SearchResponse, Error> {
-    let response: SearchResponse = client
-        .get(format!("https://api.put.io/v2/files/search?query={query}"))
-        .header("authorization", format!("Bearer {api_token}"))
-        .send()?
+    let request = client
+        .get(client.api_url(&format!("/files/search?query={query}")))
+        .header("authorization", format!("Bearer {api_token}"));
+    let response: SearchResponse = put::with_timeout(request, client.timeouts.search)
+        .send_tracked()?
+        .error_for_status()?
         .json()?;
 
     Ok(response)
@@ -161,10 +268,11 @@ pub fn delete(client: &Client, api_token: &String, file_id: &str) -> Result<(),
     let form: Form = Form::new().text("file_ids", file_id.to_owned());
 
     client
-        .post("https://api.put.io/v2/files/delete")
+        .post(client.api_url("/files/delete"))
         .multipart(form)
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?;
+        .send_tracked()?
+        .error_for_status()?;
 
     Ok(())
 }
@@ -177,9 +285,10 @@ pub struct UrlResponse {
 /// Returns a download URL for a given file.
 pub fn url(client: &Client, api_token: &String, file_id: i64) -> Result<UrlResponse, Error> {
     let response: UrlResponse = client
-        .get(format!("https://api.put.io/v2/files/{file_id}/url"))
+        .get(client.api_url(&format!("/files/{file_id}/url")))
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?
+        .send_tracked()?
+        .error_for_status()?
         .json()?;
 
     Ok(response)
@@ -197,14 +306,82 @@ pub fn mv(
         .text("parent_id", new_parent_id.to_string());
 
     client
-        .post("https://api.put.io/v2/files/move")
+        .post(client.api_url("/files/move"))
         .multipart(form)
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?;
+        .send_tracked()?
+        .error_for_status()?;
 
     Ok(())
 }
 
+/// Copies a file or folder into another folder without it leaving Put.io.
+///
+/// Returns `Ok(false)` if Put.io has no server-side copy for the item (the
+/// endpoint is missing or refuses it), so the caller can fall back to
+/// `copy_via_local`.
+pub fn copy(
+    client: &Client,
+    api_token: &String,
+    file_id: i64,
+    new_parent_id: i64,
+) -> Result<bool, Error> {
+    let form: Form = Form::new()
+        .text("file_ids", file_id.to_string())
+        .text("parent_id", new_parent_id.to_string());
+
+    let response = client
+        .post(client.api_url("/files/copy"))
+        .multipart(form)
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?;
+
+    match response.status() {
+        StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED => {
+            Ok(false)
+        }
+        _ => response.error_for_status().map(|_| true),
+    }
+}
+
+/// Copies a file by downloading it to a temporary folder and uploading it into
+/// `new_parent_id`. Slow, so only meant for when `copy` isn't supported.
+pub fn copy_via_local(
+    client: &Client,
+    api_token: &String,
+    file_id: i64,
+    new_parent_id: i64,
+) -> Result<(), String> {
+    let file = list(client, api_token, file_id)
+        .map_err(|e| e.to_string())?
+        .parent;
+    if file.file_type == "FOLDER" {
+        return Err("folders can only be copied by Put.io itself".to_string());
+    }
+
+    let dir = std::env::temp_dir().join(format!("kaput-copy-{}", file_id));
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let path = output_path(&dir.to_string_lossy(), &file.name, false);
+    // Fetched directly rather than through `download`, since the copy isn't a
+    // download to keep in the history or the transfer statistics
+    let result = url(client, api_token, file_id)
+        .map_err(|e| e.to_string())
+        .and_then(|response| {
+            if fetch(client, &file.name, None, &path, &response.url, false) {
+                Ok(())
+            } else {
+                Err(format!("downloading {} failed", file.name))
+            }
+        })
+        .and_then(|_| {
+            let path = Path::new(&path);
+            let size = fs::metadata(path).map_err(|e| e.to_string())?.len();
+            upload_file(client, api_token, path, size, new_parent_id, &[]).map(|_| ())
+        });
+    fs::remove_dir_all(&dir).ok();
+    result
+}
+
 /// Renames a file
 pub fn rename(
     client: &Client,
@@ -217,10 +394,51 @@ pub fn rename(
         .text("name", new_name.to_owned());
 
     client
-        .post("https://api.put.io/v2/files/rename")
+        .post(client.api_url("/files/rename"))
         .multipart(form)
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?;
+        .send_tracked()?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// Marks video file(s) as watched or unwatched
+pub fn set_watch_status(
+    client: &Client,
+    api_token: &String,
+    file_ids: &str,
+    watched: bool,
+) -> Result<(), Error> {
+    let form: Form = Form::new()
+        .text("file_ids", file_ids.to_owned())
+        .text("watched", watched.to_string());
+
+    client
+        .post(client.api_url("/files/watch-status"))
+        .multipart(form)
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+/// Saves the playback position of a video in seconds
+pub fn set_start_from(
+    client: &Client,
+    api_token: &String,
+    file_id: i64,
+    seconds: u64,
+) -> Result<(), Error> {
+    let form: Form = Form::new().text("time", seconds.to_string());
+
+    client
+        .post(client.api_url(&format!("/files/{file_id}/start-from/set")))
+        .multipart(form)
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?;
 
     Ok(())
 }
@@ -230,10 +448,11 @@ pub fn extract(client: &Client, api_token: &String, file_id: i64) -> Result<(),
     let form: Form = Form::new().text("file_ids", file_id.to_string());
 
     client
-        .post("https://api.put.io/v2/files/extract")
+        .post(client.api_url("/files/extract"))
         .multipart(form)
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?;
+        .send_tracked()?
+        .error_for_status()?;
 
     Ok(())
 }
@@ -254,9 +473,10 @@ pub struct ExtractionResponse {
 /// Returns active extractions
 pub fn get_extractions(client: &Client, api_token: &String) -> Result<ExtractionResponse, Error> {
     let response: ExtractionResponse = client
-        .get("https://api.put.io/v2/files/extract")
+        .get(client.api_url("/files/extract"))
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?
+        .send_tracked()?
+        .error_for_status()?
         .json()?;
 
     Ok(response)
@@ -267,9 +487,16 @@ struct ReplaceChar<'a> {
     to: &'a str,
 }
 
-/// Replaces illegal characters in a file name
-fn replace_illegal_chars(name: &str) -> String {
-    let mut name: String = name.to_owned();
+/// Replaces illegal characters in a file name or `/`-separated path. On
+/// Windows each part of the path is also kept clear of reserved device names
+/// such as `CON` and of trailing dots and spaces, which Windows drops silently.
+pub fn replace_illegal_chars(name: &str) -> String {
+    // Keep the colon of a drive letter such as `C:`
+    let (drive, rest) = match name.as_bytes() {
+        [letter, b':', ..] if cfg!(windows) && letter.is_ascii_alphabetic() => name.split_at(2),
+        _ => ("", name),
+    };
+    let mut name: String = rest.to_owned();
 
     const ILLEGAL_CHARS: [ReplaceChar<'_>; 7] = [
         ReplaceChar { from: "<", to: "" },
@@ -291,7 +518,159 @@ fn replace_illegal_chars(name: &str) -> String {
         name = name.replace(replacement.from, replacement.to);
     }
 
-    name
+    if cfg!(windows) {
+        name = name
+            .split('/')
+            .map(windows_safe_component)
+            .collect::<Vec<String>>()
+            .join("/");
+    }
+
+    format!("{}{}", drive, name)
+}
+
+/// Names Windows reserves for devices, with or without an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Makes one part of a path usable as a Windows file name, e.g. `aux.txt`
+/// becomes `aux_.txt` and `Season 1.` becomes `Season 1`.
+fn windows_safe_component(component: &str) -> String {
+    if component == "." || component == ".." {
+        return component.to_string();
+    }
+    let trimmed = component.trim_end_matches(['.', ' ']);
+    let (stem, ext) = match trimmed.split_once('.') {
+        Some((stem, ext)) => (stem, Some(ext)),
+        None => (trimmed, None),
+    };
+    let reserved = RESERVED_NAMES
+        .iter()
+        .any(|reserved| stem.trim_end().eq_ignore_ascii_case(reserved));
+    match (reserved, ext) {
+        (true, Some(ext)) => format!("{}_.{}", stem, ext),
+        (true, None) => format!("{}_", stem),
+        (false, _) if trimmed.is_empty() && !component.is_empty() => "_".to_string(),
+        (false, _) => trimmed.to_string(),
+    }
+}
+
+/// Returns `path` with the `\\?\` prefix when it is too long for the classic
+/// Windows path limit, so files deep in a folder can still be written. Paths
+/// are left as they are on other systems.
+pub fn long_path(path: &str) -> String {
+    const MAX_PATH: usize = 260;
+    if !cfg!(windows) || path.len() < MAX_PATH || path.starts_with(r"\\?\") {
+        return path.to_string();
+    }
+    match std::path::absolute(path) {
+        Ok(absolute) => format!(r"\\?\{}", absolute.to_string_lossy().replace('/', "\\")),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// What to do when a download's target file already exists locally.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ExistingFile {
+    /// Continue a partial download, which leaves a complete file as it is
+    #[default]
+    Resume,
+    Overwrite,
+    Skip,
+    /// Save under a free name such as `Movie (1).mkv`
+    Rename,
+}
+
+/// Returns the local path a file named `name` is downloaded to in `directory`.
+pub fn output_path(directory: &str, name: &str, no_replace: bool) -> String {
+    let path = format!("{}/{}", directory, name);
+    if no_replace {
+        path
+    } else {
+        replace_illegal_chars(&path)
+    }
+}
+
+/// Returns the path to write to and whether to resume it, or `None` to skip.
+fn resolve_existing(output_path: String, existing: ExistingFile) -> Option<(String, bool)> {
+    if !Path::new(&output_path).exists() {
+        return Some((output_path, false));
+    }
+    match existing {
+        ExistingFile::Resume => Some((output_path, true)),
+        ExistingFile::Overwrite => Some((output_path, false)),
+        ExistingFile::Skip => None,
+        ExistingFile::Rename => Some((free_path(&output_path), false)),
+    }
+}
+
+/// Finds the first of `name (1).ext`, `name (2).ext`, ... that does not exist.
+pub fn free_path(path: &str) -> String {
+    let path = Path::new(path);
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .map(|candidate| candidate.to_string_lossy().into_owned())
+        .expect("finding a free file name")
+}
+
+#[derive(Default)]
+pub struct DownloadOptions<'a> {
+    /// Recursively download the folder
+    pub recursive: bool,
+    /// The path to save the file or folder to
+    pub path: Option<&'a String>,
+    /// Do not replace illegal characters in the file name
+    pub no_replace: bool,
+    /// Skip files found in the download history when downloading recursively
+    pub skip_downloaded: bool,
+    /// Files and folders to skip when downloading recursively
+    pub ignore: IgnoreRules,
+    /// What to do with files that already exist locally
+    pub existing: ExistingFile,
+    /// On Windows, write paths longer than 260 characters with the `\\?\` prefix
+    pub long_paths: bool,
+    /// Unpack downloaded archives with these commands, by file extension
+    pub extract: Option<&'a HashMap<String, Vec<String>>>,
+    /// Scripts run after each file is downloaded
+    pub post_download: &'a [PostDownloadHook],
+    /// Profile the download is for, passed on to the scripts
+    pub profile: Option<&'a str>,
+}
+
+/// Why a download stopped.
+#[derive(Debug)]
+pub enum DownloadError {
+    Api(Error),
+    /// A post-download script set to abort failed
+    Hook(String),
+    /// curl failed or the file didn't arrive whole
+    Fetch(String),
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DownloadError::Api(e) => e.fmt(f),
+            DownloadError::Hook(e) | DownloadError::Fetch(e) => f.write_str(e),
+        }
+    }
+}
+
+impl From<Error> for DownloadError {
+    fn from(e: Error) -> Self {
+        DownloadError::Api(e)
+    }
 }
 
 /// Downloads a file or folder
@@ -301,49 +680,26 @@ fn replace_illegal_chars(name: &str) -> String {
 /// * `client` - The reqwest client
 /// * `api_token` - The user's API token
 /// * `file_id` - The ID of the file or folder to download
-/// * `recursive` - Recursively download the folder
-/// * `path` - The path to save the file or folder to
-/// * `no_replace` - Do not replace illegal characters in the file name
+/// * `options` - Where and how to save the file or folder
 pub fn download(
     client: &Client,
     api_token: &String,
     file_id: i64,
-    recursive: bool,
-    path: Option<&String>,
-    no_replace: bool,
-) -> Result<(), Error> {
+    options: &DownloadOptions,
+) -> Result<(), DownloadError> {
     let files: FilesResponse =
         put::files::list(client, api_token, file_id).expect("querying files");
 
+    let parent_dir: String = match options.path {
+        Some(p) => p.clone(), // Use the provided path if there is one
+        None => ".".to_string(),
+    };
+
     match files.parent.file_type.as_str() {
         "FOLDER" => {
             // ID is for a folder
-            match recursive {
-                true => {
-                    // Recursively download the folder
-                    let mut directory_path: String = match path {
-                        Some(p) => format!("{}/{}", p, files.parent.name), // Use the provided path if there is one
-                        None => format!("./{}", files.parent.name),
-                    };
-
-                    if !no_replace {
-                        directory_path = replace_illegal_chars(&directory_path);
-                    }
-
-                    fs::create_dir_all(directory_path.clone()).expect("creating directory");
-
-                    for file in files.files {
-                        download(
-                            client,
-                            api_token,
-                            file.id,
-                            true,
-                            Some(&directory_path),
-                            no_replace,
-                        )
-                        .expect("downloading file recursively");
-                    }
-                }
+            match options.recursive {
+                true => download_folder(client, api_token, &files.parent, &parent_dir, options)?,
                 false => {
                     // Create a ZIP
                     println!("Creating ZIP for \"{}\"...", files.parent.name);
@@ -353,77 +709,438 @@ pub fn download(
 
                     println!("ZIP created!");
 
-                    let mut output_path: String = match path {
-                        Some(p) => format!("{}/{}.zip", p, files.parent.name),
-                        None => format!("./{}.zip", files.parent.name),
+                    let output_path = output_path(
+                        &parent_dir,
+                        &format!("{}.zip", files.parent.name),
+                        options.no_replace,
+                    );
+                    let Some((output_path, resume)) =
+                        resolve_existing(output_path.clone(), options.existing)
+                    else {
+                        println!("Skipping (exists): {}", output_path);
+                        return Ok(());
                     };
 
-                    if !no_replace {
-                        output_path = replace_illegal_chars(&output_path);
+                    if !fetch(
+                        client,
+                        &files.parent.name,
+                        Some(&files.parent.name),
+                        &output_path,
+                        &zip_url,
+                        resume,
+                    ) {
+                        return Err(DownloadError::Fetch(format!(
+                            "downloading {} failed",
+                            files.parent.name
+                        )));
                     }
-
-                    println!("Downloading: {}", files.parent.name);
-                    println!("Saving to: {}\n", output_path);
-
-                    // https://rust-lang-nursery.github.io/rust-cookbook/os/external.html#redirect-both-stdout-and-stderr-of-child-process-to-the-same-file
-                    ProcessCommand::new("curl")
-                        .arg("-C")
-                        .arg("-")
-                        .arg("-o")
-                        .arg(output_path)
-                        .arg(zip_url)
-                        .stdout(Stdio::piped())
-                        .spawn()
-                        .expect("failed to run CURL command")
-                        .wait_with_output()
-                        .expect("failed to run CURL command");
-
-                    println!("\nDownload finished!\n")
+                    record_download(&files.parent, &files.parent.name, &output_path);
+                    extract_download(&output_path, options);
+                    run_hooks(&files.parent, &files.parent.name, &output_path, options)?;
                 }
             }
         }
         _ => {
             // ID is for a file
-            let url_response: UrlResponse =
-                put::files::url(client, api_token, file_id).expect("creating download URL");
+            let fetched = download_file(
+                client,
+                api_token,
+                &files.parent,
+                &parent_dir,
+                &files.parent.name,
+                options,
+                None,
+            )?;
+            if !fetched {
+                return Err(DownloadError::Fetch(format!(
+                    "downloading {} failed",
+                    files.parent.name
+                )));
+            }
+        }
+    }
 
-            let mut output_path: String = match path {
-                Some(p) => format!("{}/{}", p, files.parent.name),
-                None => format!("./{}", files.parent.name),
+    Ok(())
+}
+
+/// Checks that the disk holding `directory` has room for a file or folder
+/// before downloading it. Returns how many bytes are missing, or `None` if it
+/// fits. Folders count their full size, a file that would be resumed only
+/// counts what's left of it and one that would be skipped counts nothing.
+pub fn space_shortfall(
+    client: &Client,
+    api_token: &String,
+    file_id: i64,
+    directory: &str,
+    existing: ExistingFile,
+) -> Result<Option<u64>, String> {
+    let file = list(client, api_token, file_id)
+        .map_err(|e| e.to_string())?
+        .parent;
+    let mut needed = file.size.0;
+    if file.file_type != "FOLDER" {
+        let local = fs::metadata(output_path(directory, &file.name, false)).map(|m| m.len());
+        match (existing, local) {
+            (ExistingFile::Resume, Ok(partial)) => needed = needed.saturating_sub(partial),
+            (ExistingFile::Skip, Ok(_)) => needed = 0,
+            _ => {}
+        }
+    }
+
+    // The destination may not exist yet, so ask about its nearest existing
+    // parent. Relative paths end at the working directory, which may be gone too
+    let path = Path::new(directory)
+        .ancestors()
+        .find(|p| !p.as_os_str().is_empty() && p.exists())
+        .unwrap_or(Path::new("."));
+    let available = fs2::available_space(path)
+        .map_err(|e| format!("could not read free space of {}: {}", path.display(), e))?;
+
+    Ok((needed > available).then(|| needed - available))
+}
+
+/// Recursively downloads a folder into `parent_dir`, listing subfolders in the
+/// background while files download. Progress is kept in a job state file, so an
+/// interrupted download picks up where it stopped when run again.
+fn download_folder(
+    client: &Client,
+    api_token: &String,
+    folder: &File,
+    parent_dir: &str,
+    options: &DownloadOptions,
+) -> Result<(), DownloadError> {
+    let downloaded = if options.skip_downloaded {
+        history::load().unwrap_or_else(|e| {
+            eprintln!("Warning: could not read download history: {}", e);
+            vec![]
+        })
+    } else {
+        vec![]
+    };
+
+    let root_dir = format!("{}/{}", parent_dir, folder.name);
+    let local_path = |relative: &str| {
+        let path = format!("{}/{}", root_dir, relative);
+        let path = if options.no_replace {
+            path
+        } else {
+            replace_illegal_chars(&path)
+        };
+        if options.long_paths {
+            long_path(&path)
+        } else {
+            path
+        }
+    };
+
+    fs::create_dir_all(local_path("")).expect("creating directory");
+
+    let mut job = JobState::load(folder.id, &root_dir);
+    let mut all_fetched = true;
+    let mut result: Result<(), DownloadError> = Ok(());
+    walk::walk(
+        client,
+        api_token,
+        folder.id,
+        |entry| {
+            let remote_path = format!("{}/{}", folder.name, entry.path);
+
+            // Ignore patterns are relative to the folder the download started from
+            if options.ignore.is_ignored(&entry.path) {
+                println!("Skipping (ignored): {}", remote_path);
+                return Visit::Skip;
+            }
+
+            if entry.file.file_type == "FOLDER" {
+                fs::create_dir_all(local_path(&entry.path)).expect("creating directory");
+                return Visit::Descend;
+            }
+
+            if history::contains(&downloaded, entry.file.id) {
+                println!("Skipping (already downloaded): {}", remote_path);
+                return Visit::Descend;
+            }
+
+            let directory = match entry.path.rsplit_once('/') {
+                Some((dir, _)) => local_path(dir),
+                None => local_path(""),
             };
+            result = download_file(
+                client,
+                api_token,
+                &entry.file,
+                directory.trim_end_matches('/'),
+                &remote_path,
+                options,
+                Some(&mut job),
+            )
+            .map(|fetched| all_fetched &= fetched);
+            if result.is_err() {
+                return Visit::Stop;
+            }
+            Visit::Descend
+        },
+        |_| {},
+    )?;
+
+    if result.is_ok() && all_fetched {
+        job.finish();
+    }
+    if result.is_ok() {
+        restore_sidecars(&root_dir);
+    }
+    result
+}
 
-            if !no_replace {
-                output_path = replace_illegal_chars(&output_path);
+/// Restores modification times from the sidecars uploaded with a folder, and
+/// reports files that don't match them.
+pub fn restore_sidecars(root_dir: &str) {
+    match manifest::restore_sidecars(Path::new(root_dir)) {
+        Ok((0, problems)) if problems.is_empty() => {}
+        Ok((restored, problems)) => {
+            println!("Restored modification times of {} file(s).", restored);
+            for (path, problem) in problems {
+                let problem = match problem {
+                    manifest::Problem::Missing => "missing".to_string(),
+                    manifest::Problem::SizeMismatch { expected, found } => {
+                        format!("size {} instead of {}", found, expected)
+                    }
+                    manifest::Problem::CrcMismatch { expected, found } => {
+                        format!("CRC32 {} instead of {}", found, expected)
+                    }
+                };
+                eprintln!(
+                    "Warning: {} does not match its sidecar: {}",
+                    path.display(),
+                    problem
+                );
             }
+        }
+        Err(e) => eprintln!("Warning: could not read sidecars: {}", e),
+    }
+}
+
+/// Downloads a single file into `directory`, tracking it in `job` if given.
+/// Returns false if curl failed or the file didn't arrive whole.
+fn download_file(
+    client: &Client,
+    api_token: &String,
+    file: &File,
+    directory: &str,
+    remote_path: &str,
+    options: &DownloadOptions,
+    mut job: Option<&mut JobState>,
+) -> Result<bool, DownloadError> {
+    if job.as_ref().is_some_and(|job| job.is_completed(file.id)) {
+        println!("Skipping (finished in an earlier run): {}", remote_path);
+        return Ok(true);
+    }
 
-            println!("Downloading: {}", files.parent.name);
-            println!("Saving to: {}\n", output_path);
-
-            // https://rust-lang-nursery.github.io/rust-cookbook/os/external.html#redirect-both-stdout-and-stderr-of-child-process-to-the-same-file
-            ProcessCommand::new("curl")
-                .arg("-C")
-                .arg("-")
-                .arg("-o")
-                .arg(output_path)
-                .arg(url_response.url)
-                .stdout(Stdio::piped())
-                .spawn()
-                .expect("error while spawning curl")
-                .wait_with_output()
-                .expect("running CURL command");
-
-            println!("\nDownload finished!\n")
+    let mut output_path = output_path(directory, &file.name, options.no_replace);
+    if options.long_paths {
+        output_path = long_path(&output_path);
+    }
+    let Some((output_path, resume)) = resolve_existing(output_path.clone(), options.existing)
+    else {
+        println!("Skipping (exists): {}", output_path);
+        return Ok(true);
+    };
+    // Start over if the partial file changed since the run that left it
+    let intact = job
+        .as_ref()
+        .is_none_or(|job| job.partial_is_intact(file.id));
+    if resume && !intact {
+        println!("Restarting (partial file changed): {}", remote_path);
+    }
+
+    let url_response: UrlResponse = put::files::url(client, api_token, file.id)?;
+    let folder = remote_path.rsplit_once('/').map_or("", |(dir, _)| dir);
+    let fetched = fetch(
+        client,
+        &file.name,
+        Some(folder),
+        &output_path,
+        &url_response.url,
+        resume && intact,
+    ) && is_complete(&output_path, file);
+    if fetched {
+        record_download(file, remote_path, &output_path);
+        extract_download(&output_path, options);
+    }
+    // Saved before the hooks run, so a run stopped by one doesn't download
+    // this file again
+    if let Some(job) = job.as_mut() {
+        let saved = if fetched {
+            job.record_completed(file.id, &output_path)
+        } else {
+            job.record_partial(file.id, &output_path)
+        };
+        if let Err(e) = saved {
+            eprintln!("Warning: could not save download progress: {}", e);
         }
     }
+    if fetched {
+        run_hooks(file, remote_path, &output_path, options)?;
+    }
 
-    Ok(())
+    Ok(fetched)
+}
+
+/// Downloads `url` to `output_path` with curl, continuing the existing file if
+/// `resume` is set, and counts it towards the transfer statistics of the
+/// remote `folder` it came from unless that is `None`. Only the connect
+/// deadline of `client` is used, curl does the rest. Returns true if curl
+/// succeeded; an HTTP error, e.g. from an expired link, is a failure and
+/// leaves no error page behind.
+fn fetch(
+    client: &Client,
+    name: &str,
+    folder: Option<&str>,
+    output_path: &str,
+    url: &str,
+    resume: bool,
+) -> bool {
+    let local_size = || fs::metadata(output_path).map_or(0, |m| m.len());
+    let resumed_from = if resume { local_size() } else { 0 };
+    let started = Instant::now();
+    println!("Downloading: {}", name);
+    println!("Saving to: {}\n", output_path);
+
+    let mut command = ProcessCommand::new("curl");
+    command.arg("--fail").arg("-L");
+    if resume {
+        command.arg("-C").arg("-");
+    }
+    if let Some(timeout) = client.timeouts.download_connect {
+        command
+            .arg("--connect-timeout")
+            .arg(timeout.as_secs().to_string());
+    }
+    // https://rust-lang-nursery.github.io/rust-cookbook/os/external.html#redirect-both-stdout-and-stderr-of-child-process-to-the-same-file
+    let output = command
+        .arg("-o")
+        .arg(output_path)
+        .arg(url)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("error while spawning curl")
+        .wait_with_output()
+        .expect("running CURL command");
+
+    if !output.status.success() {
+        eprintln!("\nDownload failed: curl exited with {}\n", output.status);
+        return false;
+    }
+    println!("\nDownload finished!\n");
+
+    if let Some(folder) = folder {
+        let bytes = local_size().saturating_sub(resumed_from);
+        history::count_transfer(Direction::Download, folder, bytes, started.elapsed());
+    }
+    true
+}
+
+/// Checks that a fetched file has the size Put.io reports for it.
+fn is_complete(output_path: &str, file: &File) -> bool {
+    let size = fs::metadata(output_path).map_or(0, |m| m.len());
+    if size != file.size.0 {
+        eprintln!(
+            "Download of {} is incomplete: {} of {}\n",
+            file.name,
+            bytefmt::format(size),
+            bytefmt::format(file.size.0)
+        );
+    }
+    size == file.size.0
+}
+
+/// Unpacks a finished download if extraction was asked for and it is an
+/// archive. Failures are reported but don't fail the download.
+fn extract_download(output_path: &str, options: &DownloadOptions) {
+    let Some(extractors) = options.extract else {
+        return;
+    };
+    match extract::extract(Path::new(output_path), extractors, &options.ignore) {
+        Ok(Some(folder)) => println!("Extracted to: {}\n", folder.display()),
+        Ok(None) => {}
+        Err(e) => eprintln!("Extracting {} failed: {}\n", output_path, e),
+    }
+}
+
+/// Runs the post-download scripts for a finished file. Only a failing script
+/// set to abort is an error.
+fn run_hooks(
+    file: &File,
+    remote_path: &str,
+    output_path: &str,
+    options: &DownloadOptions,
+) -> Result<(), DownloadError> {
+    if options.post_download.is_empty() {
+        return Ok(());
+    }
+    let local_path = fs::canonicalize(output_path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| output_path.to_string());
+    let context = DownloadContext {
+        file_id: file.id,
+        remote_path,
+        local_path: &local_path,
+        size: file.size.0,
+        crc32: file.crc32.as_deref(),
+        profile: options.profile,
+    };
+    hooks::run_post_download(options.post_download, &context).map_err(DownloadError::Hook)
+}
+
+/// Adds a finished download to the history. Failures only print a warning since
+/// the download itself succeeded.
+fn record_download(file: &File, remote_path: &str, output_path: &str) {
+    let local_path = fs::canonicalize(output_path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| output_path.to_string());
+
+    let entry = HistoryEntry {
+        file_id: file.id,
+        remote_path: remote_path.to_string(),
+        size: file.size.0,
+        crc32: file.crc32.clone(),
+        local_path,
+        downloaded_at: history::now(),
+    };
+
+    if let Err(e) = history::record(entry) {
+        eprintln!("Warning: could not update download history: {}", e);
+    }
+}
+
+/// Files at least this large are uploaded with the resumable tus protocol
+pub const RESUMABLE_UPLOAD_SIZE: u64 = 52_428_800;
+
+/// The file Put.io created for an upload.
+#[derive(Debug, Deserialize)]
+pub struct UploadedFile {
+    pub id: i64,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    file: Option<UploadedFile>,
 }
 
-pub fn upload(api_token: &String, path: &Path, parent_id: Option<&String>, curl_args: &[String]) {
+/// Uploads a file in one request with curl, returning the file Put.io created.
+pub fn upload(
+    api_token: &String,
+    path: &Path,
+    parent_id: Option<&String>,
+    curl_args: &[String],
+) -> Result<UploadedFile, String> {
     println!("Uploading: {}\n", path.to_string_lossy());
 
-    ProcessCommand::new("curl")
+    let started = Instant::now();
+    let output = ProcessCommand::new("curl")
         .args(curl_args)
+        .arg("--fail")
         .arg("-H")
         .arg(format!("Authorization: Bearer {}", api_token))
         .arg("-F")
@@ -439,5 +1156,121 @@ pub fn upload(api_token: &String, path: &Path, parent_id: Option<&String>, curl_
         .expect("failed to run CURL command")
         .wait_with_output()
         .expect("failed to run CURL command");
-    println!("\nUpload finished!")
+    if !output.status.success() {
+        return Err(format!("curl exited with {}", output.status));
+    }
+    let file = serde_json::from_slice::<UploadResponse>(&output.stdout)
+        .ok()
+        .and_then(|response| response.file)
+        .ok_or("Put.io did not report the uploaded file")?;
+    println!("\nUpload finished!");
+
+    let size = fs::metadata(path).map_or(0, |m| m.len());
+    history::count_transfer(
+        Direction::Upload,
+        &local_folder_name(path),
+        size,
+        started.elapsed(),
+    );
+    Ok(file)
+}
+
+/// Returns the name of the local folder a file is in, for transfer statistics.
+pub fn local_folder_name(path: &Path) -> String {
+    path.canonicalize()
+        .ok()
+        .as_deref()
+        .and_then(Path::parent)
+        .and_then(Path::file_name)
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Uploads a file, resumably if it's at least `RESUMABLE_UPLOAD_SIZE`. Returns
+/// the file Put.io created, if it said which one; resumable uploads don't.
+pub fn upload_file(
+    client: &Client,
+    api_token: &String,
+    path: &Path,
+    size: u64,
+    parent_id: i64,
+    curl_args: &[String],
+) -> Result<Option<UploadedFile>, String> {
+    let parent_id = parent_id.to_string();
+    if size >= RESUMABLE_UPLOAD_SIZE {
+        put::tus::upload(client, api_token, &path.to_path_buf(), Some(&parent_id))?;
+        Ok(None)
+    } else {
+        upload(api_token, path, Some(&parent_id), curl_args).map(Some)
+    }
+}
+
+/// Returns the size of a local file, or the total size of the files in a directory.
+pub fn local_size(path: &Path) -> std::io::Result<u64> {
+    let metadata = fs::metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in fs::read_dir(path)? {
+        total += local_size(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+/// Uploads a local directory into `parent_id` as a folder of the same name,
+/// recreating its subfolders. With `sidecars`, every folder also gets a
+/// manifest of its files' checksums and modification times, which downloads
+/// use to restore those times and check the files arrived intact.
+pub fn upload_dir(
+    client: &Client,
+    api_token: &String,
+    dir: &Path,
+    parent_id: i64,
+    sidecars: bool,
+    curl_args: &[String],
+) -> Result<(), String> {
+    let name = dir
+        .canonicalize()
+        .ok()
+        .and_then(|path| path.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .ok_or_else(|| format!("{} has no name", dir.display()))?;
+    let folder = create_folder(client, api_token, &name, parent_id)
+        .map_err(|e| format!("creating folder '{}': {}", name, e))?;
+
+    let mut entries: Vec<fs::DirEntry> = fs::read_dir(dir)
+        .and_then(|entries| entries.collect())
+        .map_err(|e| format!("reading {}: {}", dir.display(), e))?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        let path = entry.path();
+        let metadata = entry.metadata().map_err(|e| e.to_string())?;
+        if metadata.is_dir() {
+            upload_dir(client, api_token, &path, folder.id, sidecars, curl_args)?;
+        } else if metadata.is_file() && entry.file_name() != manifest::SIDECAR_NAME {
+            upload_file(
+                client,
+                api_token,
+                &path,
+                metadata.len(),
+                folder.id,
+                curl_args,
+            )
+            .map_err(|e| format!("uploading {}: {}", path.display(), e))?;
+        }
+    }
+
+    if sidecars {
+        let sidecar = manifest::build_sidecar(dir)
+            .map_err(|e| format!("reading {}: {}", dir.display(), e))?;
+        let temp_dir = std::env::temp_dir().join(format!("kaput-sidecar-{}", folder.id));
+        let path = temp_dir.join(manifest::SIDECAR_NAME);
+        fs::create_dir_all(&temp_dir)
+            .and_then(|_| fs::write(&path, serde_json::to_string_pretty(&sidecar)?))
+            .map_err(|e| format!("writing sidecar: {}", e))?;
+        let result = upload(api_token, &path, Some(&folder.id.to_string()), curl_args);
+        fs::remove_dir_all(&temp_dir).ok();
+        result.map_err(|e| format!("uploading sidecar: {}", e))?;
+    }
+    Ok(())
 }
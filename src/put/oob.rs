@@ -1,12 +1,14 @@
 use std::collections::HashMap;
 
-use reqwest::{blocking::Client, Error};
+use reqwest::Error;
+
+use crate::put::{rate_limit::SendTracked, Client};
 
 /// Returns a new OOB code.
 pub fn get(client: &Client) -> Result<String, Error> {
     let resp = client
-        .get("https://api.put.io/v2/oauth2/oob/code?app_id=4701")
-        .send()?
+        .get(client.api_url("/oauth2/oob/code?app_id=4701"))
+        .send_tracked()?
         .json::<HashMap<String, String>>()?;
 
     let code: &String = resp.get("code").expect("fetching OOB code");
@@ -14,14 +16,13 @@ pub fn get(client: &Client) -> Result<String, Error> {
     Ok(code.clone())
 }
 
-/// Returns new OAuth token if the OOB code is linked to the user's account.
+/// Returns new OAuth token if the OOB code is linked to the user's account, or an empty string otherwise.
 pub fn check(client: &Client, oob_code: &String) -> Result<String, Error> {
     let resp = client
-        .get(format!("https://api.put.io/v2/oauth2/oob/code/{oob_code}"))
-        .send()?
+        .get(client.api_url(&format!("/oauth2/oob/code/{oob_code}")))
+        .send_tracked()?
         .json::<HashMap<String, String>>()?;
 
-    let token: &String = resp.get("oauth_token").expect("fetching OAuth token");
-
-    Ok(token.clone())
+    // The token is empty or missing until the code has been linked
+    Ok(resp.get("oauth_token").cloned().unwrap_or_default())
 }
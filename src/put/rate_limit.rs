@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use reqwest::blocking::{Request, RequestBuilder, Response};
+use reqwest::header::HeaderMap;
+use reqwest::{Error, StatusCode};
+
+/// Below this share of the budget, requests are spread out until it resets
+const THROTTLE_BELOW: f64 = 0.2;
+
+/// Longest pause before a single request while throttling
+const MAX_THROTTLE_DELAY: Duration = Duration::from_secs(10);
+
+/// How often a request that hit the limit is retried
+const MAX_RETRIES: u32 = 3;
+
+/// Longest wait for the budget to reset after hitting the limit
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// The API call budget, as last reported by Put.io.
+#[derive(Clone, Copy)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    /// When the budget is refilled
+    pub reset: Option<Instant>,
+}
+
+/// The last budget seen for each account, by the `Authorization` header its
+/// requests were sent with
+static LAST_SEEN: Mutex<BTreeMap<String, RateLimit>> = Mutex::new(BTreeMap::new());
+
+/// Returns the most recently reported budget of the account `api_token`
+/// belongs to, if any of its responses carried one.
+pub fn current(api_token: &str) -> Option<RateLimit> {
+    LAST_SEEN
+        .lock()
+        .unwrap()
+        .get(&format!("Bearer {api_token}"))
+        .copied()
+}
+
+/// Sending requests through the shared rate limit.
+pub trait SendTracked {
+    /// Sends the request, pausing first if the budget is nearly used up and
+    /// waiting out the limit instead of failing when it is hit.
+    fn send_tracked(self) -> Result<Response, Error>;
+}
+
+impl SendTracked for RequestBuilder {
+    fn send_tracked(self) -> Result<Response, Error> {
+        let (client, request) = self.build_split();
+        let mut request = request?;
+        let account = account_of(&request);
+        let mut attempt = 0;
+        loop {
+            throttle(&account);
+            // Requests with streamed bodies can't be retried
+            let retry = request.try_clone();
+            let response = client.execute(request)?;
+            record(&account, response.headers());
+
+            if response.status() != StatusCode::TOO_MANY_REQUESTS || attempt == MAX_RETRIES {
+                return Ok(response);
+            }
+            let Some(next) = retry else {
+                return Ok(response);
+            };
+            thread::sleep(retry_wait(response.headers()));
+            request = next;
+            attempt += 1;
+        }
+    }
+}
+
+/// Tells accounts apart by the `Authorization` header, so each keeps its own budget.
+fn account_of(request: &Request) -> String {
+    request
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn header_u64(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Reads `X-RateLimit-Reset`, which may be seconds from now or a Unix timestamp.
+fn reset_instant(headers: &HeaderMap) -> Option<Instant> {
+    let value = header_u64(headers, "x-ratelimit-reset")?;
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs();
+    let seconds = if value > now { value - now } else { value };
+    Some(Instant::now() + Duration::from_secs(seconds))
+}
+
+fn record(account: &str, headers: &HeaderMap) {
+    let (Some(limit), Some(remaining)) = (
+        header_u64(headers, "x-ratelimit-limit"),
+        header_u64(headers, "x-ratelimit-remaining"),
+    ) else {
+        return;
+    };
+    LAST_SEEN.lock().unwrap().insert(
+        account.to_string(),
+        RateLimit {
+            limit,
+            remaining,
+            reset: reset_instant(headers),
+        },
+    );
+}
+
+/// Spreads the remaining calls evenly over the time left until the reset once
+/// less than `THROTTLE_BELOW` of the budget is left.
+fn throttle(account: &str) {
+    let Some(RateLimit {
+        limit,
+        remaining,
+        reset: Some(reset),
+    }) = LAST_SEEN.lock().unwrap().get(account).copied()
+    else {
+        return;
+    };
+    if limit == 0 || remaining as f64 >= limit as f64 * THROTTLE_BELOW {
+        return;
+    }
+    let left = reset.saturating_duration_since(Instant::now());
+    let delay = left / (remaining.max(1) as u32);
+    thread::sleep(delay.min(MAX_THROTTLE_DELAY));
+}
+
+fn retry_wait(headers: &HeaderMap) -> Duration {
+    let wait = match header_u64(headers, "retry-after") {
+        Some(seconds) => Duration::from_secs(seconds),
+        None => reset_instant(headers)
+            .map(|reset| reset.saturating_duration_since(Instant::now()))
+            .unwrap_or(Duration::from_secs(5)),
+    };
+    wait.clamp(Duration::from_secs(1), MAX_RETRY_WAIT)
+}
@@ -0,0 +1,100 @@
+use reqwest::{blocking::multipart::Form, Error};
+use serde::{Deserialize, Serialize};
+use tabled::Tabled;
+
+use crate::history;
+use crate::put::{rate_limit::SendTracked, Client};
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SharedFile {
+    #[serde(default)]
+    pub id: i64,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// A public link to a file or folder that works without a Put.io account.
+#[derive(Debug, Serialize, Deserialize, Tabled)]
+pub struct Share {
+    pub id: i64,
+    #[serde(default)]
+    #[tabled(rename = "file", display_with = "display_file")]
+    pub user_file: SharedFile,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    #[tabled(rename = "created")]
+    pub created_at: String,
+    #[serde(default)]
+    #[tabled(rename = "expires", display_with = "display_expiry")]
+    pub expiration_date: Option<String>,
+}
+
+fn display_file(file: &SharedFile) -> String {
+    format!("{} ({})", file.name, file.id)
+}
+
+fn display_expiry(expiry: &Option<String>) -> String {
+    expiry.clone().unwrap_or_else(|| "never".to_string())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ShareResponse {
+    pub public_share: Share,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListSharesResponse {
+    pub public_shares: Vec<Share>,
+}
+
+/// Creates a public link for a file or folder. With `expires_in` seconds the
+/// link stops working after that long, otherwise it stays until revoked.
+pub fn create(
+    client: &Client,
+    api_token: &String,
+    file_id: i64,
+    expires_in: Option<u64>,
+) -> Result<Share, Error> {
+    let mut form: Form = Form::new().text("file_id", file_id.to_string());
+    if let Some(seconds) = expires_in {
+        let expiry = history::format_timestamp(history::now() + seconds).replace(' ', "T");
+        form = form.text("expiration_date", format!("{}:00", expiry));
+    }
+
+    let response: ShareResponse = client
+        .post(client.api_url("/files/public/share"))
+        .multipart(form)
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(response.public_share)
+}
+
+/// Returns the account's public links.
+pub fn list(client: &Client, api_token: &String) -> Result<ListSharesResponse, Error> {
+    let response: ListSharesResponse = client
+        .get(client.api_url("/files/public/list"))
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(response)
+}
+
+/// Revokes public links so they stop working.
+pub fn revoke(client: &Client, api_token: &String, share_ids: &str) -> Result<(), Error> {
+    let form: Form = Form::new().text("ids", share_ids.to_owned());
+
+    client
+        .post(client.api_url("/files/public/delete"))
+        .multipart(form)
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?;
+
+    Ok(())
+}
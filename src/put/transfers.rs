@@ -1,10 +1,12 @@
 use reqwest::{
-    blocking::{multipart::Form, Client},
+    blocking::multipart::{Form, Part},
     Error,
 };
 use serde::{Deserialize, Deserializer, Serialize};
 use tabled::Tabled;
 
+use crate::put::{rate_limit::SendTracked, Client};
+
 // Handles potentially null fields
 // Source: https://github.com/graphql-rust/juniper/issues/735#issue-677782243
 fn unwrap_or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
@@ -27,6 +29,28 @@ pub struct Transfer {
     pub status: String,
     #[serde(default, deserialize_with = "unwrap_or_default")]
     pub percent_done: u16,
+    #[serde(default, deserialize_with = "unwrap_or_default")]
+    #[tabled(rename = "ratio", display_with = "display_ratio")]
+    pub current_ratio: f64,
+    #[serde(default, deserialize_with = "unwrap_or_default")]
+    #[tabled(rename = "seeding", display_with = "display_seconds")]
+    pub seconds_seeding: u64,
+    #[serde(default, deserialize_with = "unwrap_or_default")]
+    #[tabled(rename = "tracker")]
+    pub tracker_message: String,
+}
+
+fn display_ratio(ratio: &f64) -> String {
+    format!("{:.2}", ratio)
+}
+
+fn display_seconds(seconds: &u64) -> String {
+    match seconds {
+        0 => "—".to_string(),
+        s if *s < 3600 => format!("{}m", s / 60),
+        s if *s < 86400 => format!("{}h {}m", s / 3600, (s % 3600) / 60),
+        s => format!("{}d {}h", s / 86400, (s % 86400) / 3600),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,48 +61,159 @@ pub struct ListTransferResponse {
 /// Returns the user's transfers.
 pub fn list(client: &Client, api_token: &String) -> Result<ListTransferResponse, Error> {
     let response: ListTransferResponse = client
-        .get("https://api.put.io/v2/transfers/list")
+        .get(client.api_url("/transfers/list"))
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?
+        .send_tracked()?
+        .error_for_status()?
         .json()?;
 
     Ok(response)
 }
 
 /// Starts a new transfer on the account with the given URL.
+///
+/// If a `callback_url` is given, Put.io will POST to it when the transfer completes.
 pub fn add(
     client: &Client,
     api_token: &String,
     url: &String,
     parent_id: Option<&i64>,
+    callback_url: Option<&String>,
 ) -> Result<(), Error> {
     let parent_id: i64 = match parent_id {
         Some(id) => *id,
         None => 0,
     };
 
-    let form: Form = Form::new()
+    let mut form: Form = Form::new()
         .text("url", url.to_owned())
         .text("save_parent_id", parent_id.to_string());
 
+    if let Some(callback_url) = callback_url {
+        form = form.text("callback_url", callback_url.to_owned());
+    }
+
     client
-        .post("https://api.put.io/v2/transfers/add")
+        .post(client.api_url("/transfers/add"))
         .multipart(form)
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?;
+        .send_tracked()?
+        .error_for_status()?;
 
     Ok(())
 }
 
+/// Returns true for URLs Put.io can fetch directly, as opposed to magnet links or torrents.
+pub fn is_fetchable_url(url: &str) -> bool {
+    let url = url.trim().to_lowercase();
+    ["http://", "https://", "ftp://"]
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+}
+
+/// Guesses the name of what a transfer URL downloads: the display name of a magnet
+/// link, or else the last part of the URL's path.
+pub fn release_name(url: &str) -> Option<String> {
+    let parsed = reqwest::Url::parse(url.trim()).ok()?;
+    if parsed.scheme() == "magnet" {
+        return parsed
+            .query_pairs()
+            .find(|(key, _)| key == "dn")
+            .map(|(_, name)| name.into_owned());
+    }
+    let segment = parsed.path_segments()?.next_back()?;
+    let name = percent_decode(segment);
+    let name = name.strip_suffix(".torrent").unwrap_or(&name);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Decodes `%XX` escapes, as found in URL paths.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                out.push(byte);
+                i += 3;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransferResponse {
+    pub transfer: Transfer,
+}
+
+/// Returns a single transfer.
+pub fn get(client: &Client, api_token: &String, transfer_id: i64) -> Result<Transfer, Error> {
+    let response: TransferResponse = client
+        .get(client.api_url(&format!("/transfers/{transfer_id}")))
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(response.transfer)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct UploadTorrentResponse {
+    pub transfer: Option<Transfer>,
+}
+
+/// Starts a new transfer by uploading the contents of a `.torrent` file.
+///
+/// Returns the created transfer, if Put.io reported one.
+pub fn add_torrent(
+    client: &Client,
+    api_token: &String,
+    file_name: String,
+    data: Vec<u8>,
+    parent_id: Option<&i64>,
+) -> Result<Option<Transfer>, Error> {
+    let parent_id: i64 = match parent_id {
+        Some(id) => *id,
+        None => 0,
+    };
+
+    let form: Form = Form::new()
+        .part("file", Part::bytes(data).file_name(file_name))
+        .text("parent_id", parent_id.to_string());
+
+    let response: UploadTorrentResponse = client
+        .post("https://upload.put.io/v2/files/upload")
+        .multipart(form)
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?
+        .json()?;
+
+    Ok(response.transfer)
+}
+
 /// Cancels or removes transfers
 pub fn cancel(client: &Client, api_token: &String, transfer_id: i64) -> Result<(), Error> {
     let form: Form = Form::new().text("transfer_ids", transfer_id.to_string());
 
     client
-        .post("https://api.put.io/v2/transfers/cancel")
+        .post(client.api_url("/transfers/cancel"))
         .multipart(form)
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?;
+        .send_tracked()?
+        .error_for_status()?;
 
     Ok(())
 }
@@ -86,9 +221,10 @@ pub fn cancel(client: &Client, api_token: &String, transfer_id: i64) -> Result<(
 /// Clears all finished transfers
 pub fn clean(client: &Client, api_token: &String) -> Result<(), Error> {
     client
-        .post("https://api.put.io/v2/transfers/clean")
+        .post(client.api_url("/transfers/clean"))
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?;
+        .send_tracked()?
+        .error_for_status()?;
 
     Ok(())
 }
@@ -98,10 +234,11 @@ pub fn retry(client: &Client, api_token: &String, transfer_id: i64) -> Result<()
     let form: Form = Form::new().text("id", transfer_id.to_string());
 
     client
-        .post("https://api.put.io/v2/transfers/retry")
+        .post(client.api_url("/transfers/retry"))
         .multipart(form)
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?;
+        .send_tracked()?
+        .error_for_status()?;
 
     Ok(())
 }
@@ -111,10 +248,11 @@ pub fn remove(client: &Client, api_token: &String, transfer_id: i64) -> Result<(
     let form: Form = Form::new().text("transfer_ids", transfer_id.to_string());
 
     client
-        .post("https://api.put.io/v2/transfers/remove")
+        .post(client.api_url("/transfers/remove"))
         .multipart(form)
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?;
+        .send_tracked()?
+        .error_for_status()?;
 
     Ok(())
 }
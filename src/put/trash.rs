@@ -0,0 +1,77 @@
+use reqwest::{blocking::multipart::Form, Error};
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DefaultOnNull};
+use tabled::Tabled;
+
+use crate::history;
+use crate::put::{files::FileSize, rate_limit::SendTracked, Client};
+
+/// A deleted file or folder waiting in the trash.
+#[serde_as]
+#[derive(Debug, Clone, Serialize, Deserialize, Tabled)]
+pub struct TrashedFile {
+    pub id: i64,
+    pub name: String,
+    pub file_type: String,
+    pub size: FileSize,
+    #[serde(default)]
+    #[serde_as(as = "DefaultOnNull")]
+    #[tabled(rename = "deleted")]
+    pub deleted_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct TrashPage {
+    files: Vec<TrashedFile>,
+    cursor: Option<String>,
+}
+
+/// Returns everything in the trash.
+pub fn list(client: &Client, api_token: &String) -> Result<Vec<TrashedFile>, Error> {
+    let mut page: TrashPage = client
+        .get(client.api_url("/trash/list"))
+        .query(&[("per_page", "1000")])
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?
+        .json()?;
+
+    let mut files = std::mem::take(&mut page.files);
+    while let Some(cursor) = page.cursor.take().filter(|c| !c.is_empty()) {
+        let form: Form = Form::new().text("cursor", cursor);
+        page = client
+            .post(client.api_url("/trash/list/continue"))
+            .multipart(form)
+            .header("authorization", format!("Bearer {api_token}"))
+            .send_tracked()?
+            .error_for_status()?
+            .json()?;
+        files.append(&mut page.files);
+    }
+
+    Ok(files)
+}
+
+/// Returns the items that were deleted more than `max_age` seconds ago.
+pub fn older_than(files: Vec<TrashedFile>, max_age: u64) -> Vec<TrashedFile> {
+    // Both are `YYYY-MM-DD HH:MM` in UTC once the `T` is dropped, so they sort as text
+    let cutoff = history::format_timestamp(history::now().saturating_sub(max_age));
+    files
+        .into_iter()
+        .filter(|f| !f.deleted_at.is_empty() && f.deleted_at.replacen('T', " ", 1) < cutoff)
+        .collect()
+}
+
+/// Removes items from the trash for good.
+pub fn delete(client: &Client, api_token: &String, file_ids: &str) -> Result<(), Error> {
+    let form: Form = Form::new().text("file_ids", file_ids.to_owned());
+
+    client
+        .post(client.api_url("/trash/delete"))
+        .multipart(form)
+        .header("authorization", format!("Bearer {api_token}"))
+        .send_tracked()?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -6,12 +6,20 @@ use std::{
 };
 
 use base64::{engine::general_purpose, Engine as _};
-use reqwest::blocking::Client;
 
-pub fn upload(client: &Client, api_token: &String, path: &PathBuf, parent_id: Option<&String>) {
+use crate::history::{self, Direction};
+use crate::put::{self, Client};
+
+/// Uploads a file with the resumable tus protocol. An upload that fails can be
+/// resumed by uploading the same file again.
+pub fn upload(
+    client: &Client,
+    api_token: &String,
+    path: &PathBuf,
+    parent_id: Option<&String>,
+) -> Result<(), String> {
     if !path.is_file() {
-        println!("{} is not a file", path.to_string_lossy());
-        return;
+        return Err(format!("{} is not a file", path.to_string_lossy()));
     }
 
     let file_name: String = path.file_name().unwrap().to_string_lossy().to_string();
@@ -32,6 +40,7 @@ pub fn upload(client: &Client, api_token: &String, path: &PathBuf, parent_id: Op
         .as_secs();
 
     println!("Uploading: {}", file_name);
+    let started = Instant::now();
 
     let file: std::fs::File = std::fs::File::open(path).expect("opening file");
 
@@ -52,7 +61,7 @@ pub fn upload(client: &Client, api_token: &String, path: &PathBuf, parent_id: Op
     } else {
         // Get a new upload location and write it to the temp directory
         location = create_upload(client, api_token, file_size, file_name.clone(), parent_id)
-            .expect("creating upload location");
+            .ok_or("creating the upload failed, try again in a few seconds")?;
 
         std::fs::write(&temp_file_path, location.clone()).expect("writing temp file");
     }
@@ -76,7 +85,13 @@ pub fn upload(client: &Client, api_token: &String, path: &PathBuf, parent_id: Op
             std::fs::remove_file(&temp_file_path).expect("deleting temp file");
 
             println!("Upload finished!");
-            break;
+            history::count_transfer(
+                Direction::Upload,
+                &put::files::local_folder_name(path),
+                file_size - resume_offset.min(file_size),
+                started.elapsed(),
+            );
+            return Ok(());
         }
 
         // The start and end offsets of the current chunk
@@ -120,14 +135,12 @@ pub fn upload(client: &Client, api_token: &String, path: &PathBuf, parent_id: Op
         let elapsed_time: f64 = start_time.elapsed().as_secs_f64();
         let upload_speed: f64 = bytes_read as f64 / elapsed_time / 1_048_576.0; // Speed in MB/s
 
-        if let Ok(response) = res {
-            if response.status() != 204 {
-                println!("Error: {}", response.status());
-                panic!("Upload failed, try again in a few seconds.");
+        match res {
+            Ok(response) if response.status() != 204 => {
+                return Err(format!("{}, try again in a few seconds", response.status()));
             }
-        } else {
-            println!("Error: {:?}", res);
-            panic!("Upload failed, try again in a few seconds.");
+            Ok(_) => {}
+            Err(e) => return Err(format!("{}, try again in a few seconds", e)),
         }
 
         let percentage_completed: f64 = (total_bytes_read as f64 / file_size as f64) * 100.0;
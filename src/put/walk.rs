@@ -0,0 +1,138 @@
+use std::collections::HashSet;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+use reqwest::Error;
+
+use crate::put::files::{self, File};
+use crate::put::Client;
+
+/// Maximum number of folder listings requested at the same time
+const MAX_CONCURRENT_REQUESTS: usize = 4;
+
+/// A file or folder found while walking a folder tree.
+pub struct WalkEntry {
+    pub file: File,
+    /// Path relative to the folder the walk started from, separated by `/`
+    pub path: String,
+}
+
+/// What to do after visiting an entry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Visit {
+    /// Go on, and list the entry's contents if it is a folder
+    Descend,
+    /// Go on, but leave out the contents of this folder
+    Skip,
+    /// End the walk without listing any more folders
+    Stop,
+}
+
+pub struct WalkProgress {
+    pub folders_listed: usize,
+    pub folders_pending: usize,
+}
+
+/// Walks the folder tree below `root_id`, listing up to `MAX_CONCURRENT_REQUESTS`
+/// folders in parallel. Each folder is listed at most once.
+///
+/// `visit` is called on the calling thread for every entry as soon as its folder has
+/// been listed, so results can be streamed. Returning `Visit::Skip` for a folder skips
+/// its contents, and `Visit::Stop` ends the walk early. `progress` is called after
+/// every listing.
+///
+/// Returns the root folder itself.
+pub fn walk<V, P>(
+    client: &Client,
+    api_token: &String,
+    root_id: i64,
+    mut visit: V,
+    mut progress: P,
+) -> Result<File, Error>
+where
+    V: FnMut(WalkEntry) -> Visit,
+    P: FnMut(WalkProgress),
+{
+    let root = files::list(client, api_token, root_id)?;
+
+    let (job_tx, job_rx) = mpsc::channel::<(i64, String)>();
+    let job_rx = Arc::new(Mutex::new(job_rx));
+    let (result_tx, result_rx) = mpsc::channel::<(String, Result<Vec<File>, Error>)>();
+
+    let workers: Vec<thread::JoinHandle<()>> = (0..MAX_CONCURRENT_REQUESTS)
+        .map(|_| {
+            let client = client.clone();
+            let api_token = api_token.clone();
+            let job_rx = Arc::clone(&job_rx);
+            let result_tx = result_tx.clone();
+            thread::spawn(move || loop {
+                // The lock is released as soon as a job is received
+                let job = job_rx.lock().unwrap().recv();
+                let Ok((folder_id, prefix)) = job else {
+                    break;
+                };
+                let result = files::list(&client, &api_token, folder_id).map(|r| r.files);
+                if result_tx.send((prefix, result)).is_err() {
+                    break;
+                }
+            })
+        })
+        .collect();
+    drop(result_tx);
+
+    let mut visited: HashSet<i64> = HashSet::from([root_id]);
+    let mut folders_listed: usize = 1;
+    let mut folders_pending: usize = 0;
+    let mut batch: Option<(String, Vec<File>)> = Some((String::new(), root.files));
+    let mut outcome: Result<(), Error> = Ok(());
+    let mut stopped = false;
+
+    while let Some((prefix, listing)) = batch.take() {
+        for file in listing {
+            let path = format!("{}{}", prefix, file.name);
+            let folder_id = (file.file_type == "FOLDER").then_some(file.id);
+            let next = visit(WalkEntry {
+                file,
+                path: path.clone(),
+            });
+            if next == Visit::Stop {
+                stopped = true;
+                break;
+            }
+            if let Some(folder_id) = folder_id {
+                if next == Visit::Descend && visited.insert(folder_id) {
+                    job_tx.send((folder_id, format!("{}/", path))).ok();
+                    folders_pending += 1;
+                }
+            }
+        }
+
+        progress(WalkProgress {
+            folders_listed,
+            folders_pending,
+        });
+
+        if stopped || folders_pending == 0 {
+            break;
+        }
+        match result_rx.recv() {
+            Ok((prefix, Ok(listing))) => {
+                folders_pending -= 1;
+                folders_listed += 1;
+                batch = Some((prefix, listing));
+            }
+            Ok((_, Err(e))) => outcome = Err(e),
+            Err(_) => break,
+        }
+    }
+
+    // Dropping the queued folders and closing the job channel stops the workers
+    // once their current listing finishes
+    drop(job_tx);
+    while job_rx.lock().unwrap().try_recv().is_ok() {}
+    for worker in workers {
+        worker.join().ok();
+    }
+
+    outcome.map(|_| root.parent)
+}
@@ -1,11 +1,10 @@
 use std::{thread, time};
 
-use reqwest::{
-    blocking::{multipart::Form, Client},
-    Error,
-};
+use reqwest::{blocking::multipart::Form, Error};
 use serde::{Deserialize, Serialize};
 
+use crate::put::{rate_limit::SendTracked, Client};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateZipResponse {
     pub zip_id: i64,
@@ -19,10 +18,11 @@ pub fn create(client: &Client, api_token: &String, file_id: i64) -> Result<Strin
     let form: Form = Form::new().text("file_ids", file_id.to_string());
 
     let response: CreateZipResponse = client
-        .post("https://api.put.io/v2/zips/create")
+        .post(client.api_url("/zips/create"))
         .multipart(form)
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?
+        .send_tracked()?
+        .error_for_status()?
         .json()?;
 
     // Wait for ZIP job to finish
@@ -47,9 +47,10 @@ pub struct CheckZipResponse {
 /// Checks the status of a given zip job
 pub fn get(client: &Client, api_token: &String, zip_id: i64) -> Result<CheckZipResponse, Error> {
     let response: CheckZipResponse = client
-        .get(format!("https://api.put.io/v2/zips/{zip_id}"))
+        .get(client.api_url(&format!("/zips/{zip_id}")))
         .header("authorization", format!("Bearer {api_token}"))
-        .send()?
+        .send_tracked()?
+        .error_for_status()?
         .json()?;
 
     Ok(response)
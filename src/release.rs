@@ -0,0 +1,244 @@
+/// What could be read from a scene-style release name such as
+/// `Show.Name.S02E05.1080p.WEB-DL.x264-GROUP`.
+#[derive(Debug, Default, PartialEq)]
+pub struct Release {
+    pub title: String,
+    pub year: Option<u16>,
+    pub season: Option<u32>,
+    pub episode: Option<u32>,
+    /// e.g. `1080p`
+    pub resolution: Option<String>,
+    /// The release group after the last `-`, e.g. `GROUP`
+    pub group: Option<String>,
+}
+
+/// Parses a release or file name. Everything before the first season, episode,
+/// year or resolution marker is taken as the title.
+pub fn parse(name: &str) -> Release {
+    let name = strip_extension(name);
+    let group = name
+        .rsplit_once('-')
+        .map(|(_, group)| group.trim())
+        .filter(|group| {
+            !group.is_empty()
+                && group.len() <= 20
+                && group.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        .map(String::from);
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c == '.' || c == '_' { ' ' } else { c })
+        .collect();
+    let tokens: Vec<&str> = cleaned.split_whitespace().collect();
+
+    let mut release = Release {
+        group,
+        ..Release::default()
+    };
+    let mut title_end = tokens.len();
+    let mut i = 0;
+    while i < tokens.len() {
+        let start = i;
+        let token = tokens[i].trim_matches(|c| matches!(c, '(' | ')' | '[' | ']'));
+        let lower = token.to_lowercase();
+        let mut is_marker = true;
+
+        if let Some(resolution) = resolution(&lower) {
+            release.resolution.get_or_insert(resolution);
+        } else if release.season.is_some() {
+            // Past the episode marker only the resolution is of interest
+            is_marker = false;
+        } else if let Some((season, episode)) = season_episode(&lower) {
+            release.season = Some(season);
+            release.episode = episode;
+        } else if lower == "season" && i + 1 < tokens.len() {
+            match tokens[i + 1].parse() {
+                Ok(season) => {
+                    release.season = Some(season);
+                    i += 1;
+                }
+                Err(_) => is_marker = false,
+            }
+        } else if let (Some(year), true) = (year(token), i > 0) {
+            // A leading year is part of the title, e.g. `2012`
+            release.year = Some(year);
+        } else {
+            is_marker = false;
+        }
+
+        if is_marker && title_end == tokens.len() {
+            title_end = start;
+        }
+        i += 1;
+    }
+
+    if title_end == tokens.len() {
+        // Without any markers a `-` is more likely part of the name, e.g. `Spider-Man`
+        release.group = None;
+    }
+    release.title = tokens[..title_end]
+        .join(" ")
+        .trim_end_matches(|c: char| c == '-' || c == '(' || c == '[' || c.is_whitespace())
+        .to_string();
+    release
+}
+
+/// Removes a file extension like `.mkv`, but not a trailing year or tag.
+fn strip_extension(name: &str) -> &str {
+    match name.rsplit_once('.') {
+        Some((stem, ext))
+            if (2..=4).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+                && !ext.chars().all(|c| c.is_ascii_digit()) =>
+        {
+            stem
+        }
+        _ => name,
+    }
+}
+
+/// Reads `s02e05`, `s02` or `2x05`.
+fn season_episode(token: &str) -> Option<(u32, Option<u32>)> {
+    if let Some(rest) = token.strip_prefix('s') {
+        let (season, rest) = split_number(rest)?;
+        if rest.is_empty() {
+            return Some((season, None));
+        }
+        let (episode, _) = split_number(rest.strip_prefix('e')?)?;
+        return Some((season, Some(episode)));
+    }
+    let (season, rest) = split_number(token)?;
+    let (episode, rest) = split_number(rest.strip_prefix('x')?)?;
+    rest.is_empty().then_some((season, Some(episode)))
+}
+
+/// Splits leading digits off `s` and parses them.
+fn split_number(s: &str) -> Option<(u32, &str)> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    if end == 0 || end > 4 {
+        return None;
+    }
+    Some((s[..end].parse().ok()?, &s[end..]))
+}
+
+/// Reads a resolution tag like `720p` or `4k`, normalised to lowercase.
+fn resolution(token: &str) -> Option<String> {
+    match token {
+        "4k" | "uhd" => Some("2160p".to_string()),
+        _ => {
+            let digits = token
+                .strip_suffix('p')
+                .or_else(|| token.strip_suffix('i'))?;
+            let lines: u32 = digits.parse().ok()?;
+            [480, 576, 720, 1080, 2160]
+                .contains(&lines)
+                .then(|| token.to_string())
+        }
+    }
+}
+
+fn year(token: &str) -> Option<u16> {
+    if token.len() != 4 {
+        return None;
+    }
+    token
+        .parse()
+        .ok()
+        .filter(|year| (1900..=2099).contains(year))
+}
+
+impl Release {
+    /// Returns a readable name such as `Show Name S02E05 [1080p]` or
+    /// `Movie Name (2019) [2160p]`, or `None` if nothing besides a title was found.
+    pub fn nice_name(&self) -> Option<String> {
+        if self.title.is_empty()
+            || (self.season.is_none() && self.year.is_none() && self.resolution.is_none())
+        {
+            return None;
+        }
+        let mut name = self.title.clone();
+        if let Some(year) = self.year {
+            name.push_str(&format!(" ({})", year));
+        }
+        match (self.season, self.episode) {
+            (Some(season), Some(episode)) => {
+                name.push_str(&format!(" S{:02}E{:02}", season, episode))
+            }
+            (Some(season), None) => name.push_str(&format!(" Season {}", season)),
+            _ => {}
+        }
+        if let Some(resolution) = &self.resolution {
+            name.push_str(&format!(" [{}]", resolution));
+        }
+        Some(name)
+    }
+}
+
+/// Returns the cleaned-up name of a file, keeping its extension, or the name
+/// itself if it doesn't look like a release.
+pub fn pretty_name(name: &str) -> String {
+    let extension = &name[strip_extension(name).len()..];
+    match parse(name).nice_name() {
+        Some(nice) => format!("{}{}", nice, extension),
+        None => name.to_string(),
+    }
+}
+
+/// Fills `{title}`, `{year}`, `{season}` and `{episode}` in a folder template such
+/// as `TV/{title}/Season {season}`. Fails if a used placeholder wasn't found.
+pub fn fill_template(template: &str, release: &Release) -> Result<String, String> {
+    fill(
+        template,
+        &[
+            (
+                "title",
+                Some(release.title.clone()).filter(|t| !t.is_empty()),
+            ),
+            ("year", release.year.map(|y| y.to_string())),
+            ("season", release.season.map(|s| s.to_string())),
+            ("episode", release.episode.map(|e| e.to_string())),
+        ],
+    )
+}
+
+/// Builds a new file name from a pattern such as `{title} - S{season}E{episode}.{ext}`.
+/// Besides the template placeholders, `{resolution}`, `{group}`, `{ext}` and the
+/// original `{name}` can be used. Seasons and episodes are padded to two digits.
+pub fn rename(pattern: &str, name: &str) -> Result<String, String> {
+    let release = parse(name);
+    let stem = strip_extension(name);
+    let extension = name[stem.len()..].trim_start_matches('.');
+    let renamed = fill(
+        pattern,
+        &[
+            (
+                "title",
+                Some(release.title.clone()).filter(|t| !t.is_empty()),
+            ),
+            ("year", release.year.map(|y| y.to_string())),
+            ("season", release.season.map(|s| format!("{:02}", s))),
+            ("episode", release.episode.map(|e| format!("{:02}", e))),
+            ("resolution", release.resolution.clone()),
+            ("group", release.group.clone()),
+            ("ext", Some(extension.to_string())),
+            ("name", Some(stem.to_string())),
+        ],
+    )?;
+    // Folders have no extension, so don't leave `Name.` behind
+    Ok(renamed.trim_end_matches('.').trim().to_string())
+}
+
+fn fill(template: &str, values: &[(&str, Option<String>)]) -> Result<String, String> {
+    let mut out = template.to_string();
+    for (key, value) in values {
+        let placeholder = format!("{{{}}}", key);
+        if !out.contains(&placeholder) {
+            continue;
+        }
+        match value {
+            Some(value) => out = out.replace(&placeholder, value),
+            None => return Err(format!("no {} found in the release name", key)),
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,292 @@
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream, UdpSocket};
+use std::sync::Arc;
+use std::thread;
+
+use blake2::digest::Mac;
+use blake2::Blake2bMac512;
+
+use crate::put::{self, Client};
+
+/// Starts a local HTTP server that lists folders and proxies file streams,
+/// so devices on the LAN can play files without their own OAuth token.
+///
+/// Routes:
+/// * `/` and `/folders/{id}` - HTML listing of a folder
+/// * `/files/{id}?sig=...` - the file's stream, with `Range` requests forwarded
+///   to Put.io. Only links from a listing of this run are accepted
+pub fn run(client: &Client, api_token: &str, bind: &str, port: u16) -> io::Result<()> {
+    let listener = TcpListener::bind((bind, port))?;
+
+    let mut key = [0u8; 32];
+    getrandom::getrandom(&mut key).map_err(|e| io::Error::other(e.to_string()))?;
+    let signer = Arc::new(Signer { key });
+
+    let host = if bind == "0.0.0.0" || bind == "::" {
+        local_ip()
+    } else {
+        bind.to_string()
+    };
+    println!("Serving on http://{}:{}/", host, port);
+    println!("Press Ctrl-C to stop.");
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(e) => {
+                eprintln!("Connection failed: {}", e);
+                continue;
+            }
+        };
+        let client = client.clone();
+        let api_token = api_token.to_string();
+        let signer = Arc::clone(&signer);
+        thread::spawn(move || {
+            if let Err(e) = handle_connection(&client, &api_token, &signer, stream) {
+                eprintln!("Request failed: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Signs file links with a key made for this run, so streams can't be
+/// requested by guessing file IDs.
+struct Signer {
+    key: [u8; 32],
+}
+
+impl Signer {
+    fn mac(&self, file_id: i64) -> Blake2bMac512 {
+        let mut mac = <Blake2bMac512 as Mac>::new_from_slice(&self.key).expect("key fits Blake2b");
+        mac.update(&file_id.to_be_bytes());
+        mac
+    }
+
+    fn sign(&self, file_id: i64) -> String {
+        self.mac(file_id)
+            .finalize()
+            .into_bytes()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    fn verify(&self, file_id: i64, signature: &str) -> bool {
+        let bytes: Option<Vec<u8>> = (0..signature.len())
+            .step_by(2)
+            .map(|i| {
+                signature
+                    .get(i..i + 2)
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            })
+            .collect();
+        bytes.is_some_and(|bytes| self.mac(file_id).verify_slice(&bytes).is_ok())
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    range: Option<String>,
+}
+
+fn read_request(stream: &TcpStream) -> io::Result<Request> {
+    let mut reader = BufReader::new(stream);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+
+    let mut range = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("range") {
+                range = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    Ok(Request {
+        method,
+        path,
+        range,
+    })
+}
+
+fn handle_connection(
+    client: &Client,
+    api_token: &String,
+    signer: &Signer,
+    mut stream: TcpStream,
+) -> io::Result<()> {
+    let request = read_request(&stream)?;
+
+    if request.method != "GET" && request.method != "HEAD" {
+        return write_response(&mut stream, "405 Method Not Allowed", "text/plain", "");
+    }
+
+    let (path, query) = request.path.split_once('?').unwrap_or((&request.path, ""));
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    let signature = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("sig="))
+        .unwrap_or_default();
+
+    match segments.as_slice() {
+        [""] => serve_folder(client, api_token, signer, &mut stream, 0),
+        ["folders", id] => match id.parse::<i64>() {
+            Ok(id) => serve_folder(client, api_token, signer, &mut stream, id),
+            Err(_) => write_response(&mut stream, "404 Not Found", "text/plain", "Not found"),
+        },
+        ["files", id] => match id.parse::<i64>() {
+            Ok(id) if signer.verify(id, signature) => {
+                serve_file(client, api_token, &mut stream, &request, id)
+            }
+            Ok(_) => write_response(&mut stream, "403 Forbidden", "text/plain", "Forbidden"),
+            Err(_) => write_response(&mut stream, "404 Not Found", "text/plain", "Not found"),
+        },
+        _ => write_response(&mut stream, "404 Not Found", "text/plain", "Not found"),
+    }
+}
+
+fn serve_folder(
+    client: &Client,
+    api_token: &String,
+    signer: &Signer,
+    stream: &mut TcpStream,
+    folder_id: i64,
+) -> io::Result<()> {
+    let files = match put::files::list(client, api_token, folder_id) {
+        Ok(files) => files,
+        Err(e) => {
+            return write_response(stream, "502 Bad Gateway", "text/plain", &e.to_string());
+        }
+    };
+
+    let mut body = format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{0}</title></head><body>\n<h1>{0}</h1>\n<ul>\n",
+        escape_html(&files.parent.name)
+    );
+    if folder_id != 0 {
+        body.push_str(&format!(
+            "<li><a href=\"/folders/{}\">..</a></li>\n",
+            files.parent.parent_id
+        ));
+    }
+    for file in &files.files {
+        let href = if file.file_type == "FOLDER" {
+            format!("/folders/{}", file.id)
+        } else {
+            format!("/files/{}?sig={}", file.id, signer.sign(file.id))
+        };
+        body.push_str(&format!(
+            "<li><a href=\"{}\">{}</a></li>\n",
+            href,
+            escape_html(&file.name)
+        ));
+    }
+    body.push_str("</ul>\n</body></html>\n");
+
+    write_response(stream, "200 OK", "text/html; charset=utf-8", &body)
+}
+
+fn serve_file(
+    client: &Client,
+    api_token: &String,
+    stream: &mut TcpStream,
+    request: &Request,
+    file_id: i64,
+) -> io::Result<()> {
+    let mut upstream = if request.method == "HEAD" {
+        client.head(client.api_url(&format!("/files/{file_id}/stream")))
+    } else {
+        client.get(client.api_url(&format!("/files/{file_id}/stream")))
+    }
+    .header("authorization", format!("Bearer {api_token}"));
+
+    if let Some(range) = &request.range {
+        upstream = upstream.header("range", range);
+    }
+
+    let mut response = match upstream.send() {
+        Ok(r) => r,
+        Err(e) => {
+            return write_response(stream, "502 Bad Gateway", "text/plain", &e.to_string());
+        }
+    };
+
+    let status = response.status();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nConnection: close\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or_default()
+    );
+    for name in [
+        "content-type",
+        "content-length",
+        "content-range",
+        "accept-ranges",
+    ] {
+        if let Some(value) = response.headers().get(name) {
+            if let Ok(value) = value.to_str() {
+                head.push_str(&format!("{}: {}\r\n", name, value));
+            }
+        }
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+
+    if request.method == "GET" {
+        // Clients routinely hang up mid-stream when seeking, which is not an error
+        if let Err(e) = io::copy(&mut response, stream) {
+            if e.kind() != io::ErrorKind::BrokenPipe && e.kind() != io::ErrorKind::ConnectionReset {
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    body: &str,
+) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Returns the LAN address of this machine, used only for the startup message.
+/// No packets are sent; connecting a UDP socket just selects the outbound interface.
+fn local_ip() -> String {
+    UdpSocket::bind("0.0.0.0:0")
+        .and_then(|socket| {
+            socket.connect("8.8.8.8:80")?;
+            socket.local_addr()
+        })
+        .map(|addr| addr.ip().to_string())
+        .unwrap_or_else(|_| "localhost".to_string())
+}
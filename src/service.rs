@@ -0,0 +1,154 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Name of the systemd unit, or label of the launchd agent, for a mode
+fn service_name(mode: &str) -> String {
+    if cfg!(target_os = "macos") {
+        format!("com.kaput-cli.{}", mode)
+    } else {
+        format!("kaput-{}", mode)
+    }
+}
+
+fn home_dir() -> io::Result<PathBuf> {
+    std::env::var_os("HOME")
+        .map(PathBuf::from)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "HOME is not set"))
+}
+
+/// Where the unit file or plist for a mode is written: a systemd user unit on
+/// Linux, a launch agent on macOS.
+pub fn service_path(mode: &str) -> io::Result<PathBuf> {
+    let home = home_dir()?;
+    if cfg!(target_os = "macos") {
+        Ok(home
+            .join("Library/LaunchAgents")
+            .join(format!("{}.plist", service_name(mode))))
+    } else {
+        let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| home.join(".config"));
+        Ok(config_dir
+            .join("systemd/user")
+            .join(format!("{}.service", service_name(mode))))
+    }
+}
+
+fn systemd_unit(mode: &str, command: &[String]) -> String {
+    let exec = command
+        .iter()
+        .map(|arg| {
+            if arg.contains([' ', '"', '\\']) {
+                format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+            } else {
+                arg.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ");
+    format!(
+        "[Unit]\n\
+        Description=kaput {mode}\n\
+        After=network-online.target\n\
+        Wants=network-online.target\n\
+        \n\
+        [Service]\n\
+        ExecStart={exec}\n\
+        Restart=on-failure\n\
+        RestartSec=10\n\
+        \n\
+        [Install]\n\
+        WantedBy=default.target\n"
+    )
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn launchd_plist(mode: &str, command: &[String]) -> String {
+    let args: String = command
+        .iter()
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(arg)))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+        <plist version=\"1.0\">\n\
+        <dict>\n\
+        \x20   <key>Label</key>\n\
+        \x20   <string>{label}</string>\n\
+        \x20   <key>ProgramArguments</key>\n\
+        \x20   <array>\n\
+        {args}\
+        \x20   </array>\n\
+        \x20   <key>RunAtLoad</key>\n\
+        \x20   <true/>\n\
+        \x20   <key>KeepAlive</key>\n\
+        \x20   <dict>\n\
+        \x20       <key>SuccessfulExit</key>\n\
+        \x20       <false/>\n\
+        \x20   </dict>\n\
+        \x20   <key>ThrottleInterval</key>\n\
+        \x20   <integer>10</integer>\n\
+        </dict>\n\
+        </plist>\n",
+        label = service_name(mode),
+    )
+}
+
+/// Writes a service definition that runs `kaput <mode> <args>` in the
+/// background, restarting it if it fails. Returns the path written to.
+pub fn install(mode: &str, args: &[String]) -> io::Result<PathBuf> {
+    let exe = std::env::current_exe()?;
+    let mut command = vec![exe.to_string_lossy().to_string(), mode.to_string()];
+    command.extend(args.iter().cloned());
+
+    let path = service_path(mode)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let contents = if cfg!(target_os = "macos") {
+        launchd_plist(mode, &command)
+    } else {
+        systemd_unit(mode, &command)
+    };
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+/// Returns the command that loads and starts an installed service.
+pub fn start_hint(mode: &str) -> String {
+    if cfg!(target_os = "macos") {
+        format!(
+            "launchctl load -w {}",
+            service_path(mode)
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default()
+        )
+    } else {
+        format!(
+            "systemctl --user daemon-reload && systemctl --user enable --now {}",
+            service_name(mode)
+        )
+    }
+}
+
+/// Prints the service manager's view of an installed service. Returns false if
+/// it isn't running.
+pub fn status(mode: &str) -> io::Result<bool> {
+    let status = if cfg!(target_os = "macos") {
+        Command::new("launchctl")
+            .args(["list", &service_name(mode)])
+            .status()?
+    } else {
+        Command::new("systemctl")
+            .args(["--user", "status", "--no-pager", &service_name(mode)])
+            .status()?
+    };
+    Ok(status.success())
+}
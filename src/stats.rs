@@ -0,0 +1,104 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::history::{DailyTotal, Direction};
+
+/// Bytes and files moved in one direction, and how long that took.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Totals {
+    pub bytes: u64,
+    pub files: u64,
+    pub seconds: f64,
+    /// Average speed while transferring
+    pub bytes_per_second: u64,
+}
+
+impl Totals {
+    fn add(&mut self, total: &DailyTotal) {
+        self.bytes += total.bytes;
+        self.files += total.files;
+        self.seconds += total.seconds;
+        self.bytes_per_second = if self.seconds > 0.0 {
+            (self.bytes as f64 / self.seconds) as u64
+        } else {
+            0
+        };
+    }
+
+    /// Describes the totals as e.g. `1.2 GB in 3 files at 25 MB/s`.
+    pub fn describe(&self) -> String {
+        if self.files == 0 {
+            return "nothing".to_string();
+        }
+        format!(
+            "{} in {} file(s) at {}/s",
+            bytefmt::format(self.bytes),
+            self.files,
+            bytefmt::format(self.bytes_per_second)
+        )
+    }
+}
+
+/// Both directions for one day or one folder.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Row {
+    pub name: String,
+    pub downloaded: Totals,
+    pub uploaded: Totals,
+}
+
+impl Row {
+    fn add(&mut self, total: &DailyTotal) {
+        match total.direction {
+            Direction::Download => self.downloaded.add(total),
+            Direction::Upload => self.uploaded.add(total),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Summary {
+    /// First day counted, `YYYY-MM-DD`, or `None` for everything recorded
+    pub since: Option<String>,
+    pub downloaded: Totals,
+    pub uploaded: Totals,
+    /// Oldest first
+    pub days: Vec<Row>,
+    /// Busiest first
+    pub folders: Vec<Row>,
+}
+
+/// Adds up the daily totals from `since` (`YYYY-MM-DD`) on.
+pub fn summarize(totals: &[DailyTotal], since: Option<&str>) -> Summary {
+    let mut all = Row::default();
+    let mut days: BTreeMap<&str, Row> = BTreeMap::new();
+    let mut folders: BTreeMap<&str, Row> = BTreeMap::new();
+    for total in totals
+        .iter()
+        .filter(|t| since.is_none_or(|since| t.day.as_str() >= since))
+    {
+        all.add(total);
+        days.entry(&total.day).or_default().add(total);
+        folders.entry(&total.folder).or_default().add(total);
+    }
+
+    let named = |(name, row): (&str, Row)| Row {
+        name: if name.is_empty() {
+            "(single files)".to_string()
+        } else {
+            name.to_string()
+        },
+        ..row
+    };
+    let mut folders: Vec<Row> = folders.into_iter().map(named).collect();
+    folders.sort_by_key(|row| std::cmp::Reverse(row.downloaded.bytes + row.uploaded.bytes));
+
+    Summary {
+        since: since.map(String::from),
+        downloaded: all.downloaded,
+        uploaded: all.uploaded,
+        days: days.into_iter().map(named).collect(),
+        folders,
+    }
+}
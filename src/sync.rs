@@ -0,0 +1,571 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::history::HistoryEntry;
+use crate::ignore::IgnoreRules;
+use crate::put::files::{self, DownloadOptions, ExistingFile, File, UploadedFile};
+use crate::put::walk::{self, Visit};
+use crate::put::Client;
+use crate::{config, history, manifest};
+
+/// Which side wins a conflict when not asking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Prefer {
+    Remote,
+    Local,
+    /// Whichever side was changed last
+    Newest,
+}
+
+impl Prefer {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "remote" => Some(Prefer::Remote),
+            "local" => Some(Prefer::Local),
+            "newest" => Some(Prefer::Newest),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Resolution {
+    KeepLocal,
+    KeepRemote,
+    /// Keep the remote file under its name and the local one renamed next to it
+    KeepBoth,
+    Skip,
+}
+
+/// A file as it was on both sides after the last sync.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Synced {
+    size: u64,
+    crc32: Option<String>,
+    local_modified: u64,
+}
+
+/// What the last sync of a folder pair left behind, so changes since then can
+/// be told apart from changes on both sides.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    #[serde(skip)]
+    path: PathBuf,
+    files: HashMap<String, Synced>,
+    /// Remote paths by the local path they are saved under, for names that had
+    /// to be changed to be valid on this system
+    #[serde(default)]
+    names: HashMap<String, String>,
+}
+
+impl SyncState {
+    fn load(folder_id: i64, local_root: &Path) -> Self {
+        let local_root = fs::canonicalize(local_root).unwrap_or_else(|_| local_root.to_path_buf());
+        let key = crc32fast::hash(local_root.to_string_lossy().as_bytes());
+        let path = config::data_file_path("sync").join(format!("{}-{:08x}.json", folder_id, key));
+        let mut state: SyncState = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        state.path = path;
+        state
+    }
+
+    fn save(&self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.path, serde_json::to_string(self)?)
+    }
+}
+
+struct LocalFile {
+    size: u64,
+    modified: u64,
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub downloaded: usize,
+    pub uploaded: usize,
+    pub conflicts: usize,
+    pub skipped: usize,
+}
+
+/// Everything a sync needs while it works through the files.
+struct Sync<'a> {
+    client: &'a Client,
+    api_token: &'a String,
+    local_root: &'a Path,
+    /// Remote folders by path relative to the synced folder, `""` being the root
+    folders: HashMap<String, i64>,
+    state: SyncState,
+    prefer: Option<Prefer>,
+    long_paths: bool,
+    /// Download history, so files downloaded before aren't fetched again
+    downloaded: Vec<HistoryEntry>,
+    /// Sidecars uploaded with the remote folders, by local folder path. They
+    /// aren't synced themselves, only used to restore the files downloaded
+    sidecars: HashMap<String, File>,
+    /// Files downloaded in this run
+    fetched: Vec<String>,
+    /// Set when the user picks "always" at a conflict
+    always: Option<Resolution>,
+    summary: Summary,
+}
+
+/// Brings a remote folder and a local directory up to date with each other.
+/// Files changed on one side since the last sync are copied to the other;
+/// files changed on both are resolved with `prefer`, or by asking. Nothing is
+/// ever deleted, so a file removed on one side is left alone on the other.
+/// Paths matching `ignore` are left out on both sides. Downloaded files get
+/// back the modification times kept in the sidecars of their remote folder.
+pub fn run(
+    client: &Client,
+    api_token: &String,
+    folder_id: i64,
+    local_root: &Path,
+    prefer: Option<Prefer>,
+    long_paths: bool,
+    ignore: &IgnoreRules,
+) -> Result<Summary, String> {
+    fs::create_dir_all(local_root).map_err(|e| e.to_string())?;
+
+    let mut state = SyncState::load(folder_id, local_root);
+    state.names.clear();
+    let mut remote: HashMap<String, File> = HashMap::new();
+    let mut sidecars: HashMap<String, File> = HashMap::new();
+    let mut folders = HashMap::from([(String::new(), folder_id)]);
+    walk::walk(
+        client,
+        api_token,
+        folder_id,
+        |entry| {
+            if ignore.is_ignored(&entry.path) {
+                println!("Skipping (ignored): {}", entry.path);
+                return Visit::Skip;
+            }
+            if entry.file.file_type == "FOLDER" {
+                folders.insert(entry.path, entry.file.id);
+            } else if entry.file.name == manifest::SIDECAR_NAME {
+                let dir = files::replace_illegal_chars(parent_of(&entry.path));
+                sidecars.insert(dir, entry.file);
+            } else {
+                // Compare under the name the file is saved with locally
+                let local_path = files::replace_illegal_chars(&entry.path);
+                if let Some(taken) = state
+                    .names
+                    .get(&local_path)
+                    .or_else(|| remote.contains_key(&local_path).then_some(&local_path))
+                {
+                    eprintln!(
+                        "Skipping (saved under the same name as {}): {}",
+                        taken, entry.path
+                    );
+                    return Visit::Descend;
+                }
+                if local_path != entry.path {
+                    state.names.insert(local_path.clone(), entry.path);
+                }
+                remote.insert(local_path, entry.file);
+            }
+            Visit::Descend
+        },
+        |_| {},
+    )
+    .map_err(|e| e.to_string())?;
+
+    let mut local = HashMap::new();
+    list_local(local_root, "", ignore, &mut local).map_err(|e| e.to_string())?;
+
+    let downloaded = history::load().unwrap_or_else(|e| {
+        eprintln!("Warning: could not read download history: {}", e);
+        vec![]
+    });
+
+    let mut sync = Sync {
+        client,
+        api_token,
+        local_root,
+        folders,
+        state,
+        prefer,
+        long_paths,
+        downloaded,
+        sidecars,
+        fetched: vec![],
+        always: None,
+        summary: Summary::default(),
+    };
+    let paths: BTreeSet<&String> = remote.keys().chain(local.keys()).collect();
+    for path in paths {
+        sync.file(path, remote.get(path), local.get(path))?;
+    }
+    sync.restore_sidecars()?;
+    Ok(sync.summary)
+}
+
+/// Returns the folder part of a `/`-separated path, `""` for the root.
+fn parent_of(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// Collects the files below `dir` by their path relative to the sync root,
+/// leaving out those matching `ignore`.
+fn list_local(
+    dir: &Path,
+    prefix: &str,
+    ignore: &IgnoreRules,
+    out: &mut HashMap<String, LocalFile>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = if prefix.is_empty() {
+            name
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        if ignore.is_ignored(&path) || entry.file_name() == manifest::SIDECAR_NAME {
+            continue;
+        }
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            list_local(&entry.path(), &path, ignore, out)?;
+        } else if metadata.is_file() {
+            out.insert(
+                path,
+                LocalFile {
+                    size: metadata.len(),
+                    modified: modified_secs(&metadata),
+                },
+            );
+        }
+    }
+    Ok(())
+}
+
+fn modified_secs(metadata: &fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+impl Sync<'_> {
+    fn file(
+        &mut self,
+        path: &str,
+        remote: Option<&File>,
+        local: Option<&LocalFile>,
+    ) -> Result<(), String> {
+        let base = self.state.files.get(path).cloned();
+        match (remote, local) {
+            (Some(remote), None) if base.is_none() => {
+                // Only new files: one synced before is in the history too
+                if history::contains(&self.downloaded, remote.id) {
+                    println!("Skipping (already downloaded): {}", path);
+                    self.summary.skipped += 1;
+                    return Ok(());
+                }
+                self.download(path, remote)
+            }
+            (None, Some(_)) if base.is_none() => self.upload(path, None),
+            (Some(_), None) | (None, Some(_)) => {
+                println!("Skipping (deleted on one side): {}", path);
+                self.summary.skipped += 1;
+                Ok(())
+            }
+            (Some(remote), Some(local)) => {
+                let (remote_changed, local_changed) = match &base {
+                    Some(base) => (
+                        remote.size.0 != base.size
+                            || (remote.crc32.is_some() && remote.crc32 != base.crc32),
+                        local.size != base.size || local.modified != base.local_modified,
+                    ),
+                    // Never synced: the same contents on both sides need nothing
+                    None if self.same_contents(path, remote, local) => {
+                        return self.record(path, remote.size.0, remote.crc32.clone());
+                    }
+                    None => (true, true),
+                };
+                match (remote_changed, local_changed) {
+                    (false, false) => Ok(()),
+                    (true, false) => self.download(path, remote),
+                    (false, true) => self.upload(path, Some(remote)),
+                    (true, true) => self.conflict(path, remote, local),
+                }
+            }
+            (None, None) => Ok(()),
+        }
+    }
+
+    fn same_contents(&self, path: &str, remote: &File, local: &LocalFile) -> bool {
+        remote.size.0 == local.size
+            && remote.crc32.as_ref().is_some_and(|crc32| {
+                manifest::crc32_file(&self.local_root.join(path))
+                    .is_ok_and(|found| found.eq_ignore_ascii_case(crc32))
+            })
+    }
+
+    fn conflict(&mut self, path: &str, remote: &File, local: &LocalFile) -> Result<(), String> {
+        self.summary.conflicts += 1;
+        let remote_changed_at = if remote.updated_at.is_empty() {
+            &remote.created_at
+        } else {
+            &remote.updated_at
+        };
+        let remote_time: String = remote_changed_at
+            .replacen('T', " ", 1)
+            .chars()
+            .take(16)
+            .collect();
+        let local_time = history::format_timestamp(local.modified);
+
+        let resolution = match (self.always, self.prefer) {
+            (Some(always), _) => always,
+            (None, Some(Prefer::Remote)) => Resolution::KeepRemote,
+            (None, Some(Prefer::Local)) => Resolution::KeepLocal,
+            (None, Some(Prefer::Newest)) if remote_time >= local_time => Resolution::KeepRemote,
+            (None, Some(Prefer::Newest)) => Resolution::KeepLocal,
+            (None, None) if !io::stdin().is_terminal() => {
+                eprintln!(
+                    "Skipping (changed on both sides, use --prefer to decide): {}",
+                    path
+                );
+                Resolution::Skip
+            }
+            (None, None) => {
+                println!("\nChanged on both sides: {}", path);
+                println!(
+                    "  local:  {}, modified {}",
+                    bytefmt::format(local.size),
+                    local_time
+                );
+                println!(
+                    "  remote: {}, modified {}",
+                    bytefmt::format(remote.size.0),
+                    remote_time
+                );
+                self.ask()
+            }
+        };
+
+        match resolution {
+            Resolution::KeepRemote => self.download(path, remote),
+            Resolution::KeepLocal => self.upload(path, Some(remote)),
+            Resolution::KeepBoth => {
+                let renamed = both_name(path);
+                fs::rename(self.local_root.join(path), self.local_root.join(&renamed))
+                    .map_err(|e| format!("renaming {}: {}", path, e))?;
+                println!("Kept the local copy as {}", renamed);
+                self.upload(&renamed, None)?;
+                self.download(path, remote)
+            }
+            Resolution::Skip => {
+                self.summary.skipped += 1;
+                Ok(())
+            }
+        }
+    }
+
+    /// Asks how to resolve a conflict. A capital letter applies the answer to
+    /// every conflict left in this run.
+    fn ask(&mut self) -> Resolution {
+        loop {
+            print!("[l] keep local  [r] keep remote  [b] keep both  [s] skip  (L/R/B/S: same for all) ");
+            io::stdout().flush().ok();
+            let mut answer = String::new();
+            if io::stdin().read_line(&mut answer).unwrap_or(0) == 0 {
+                return Resolution::Skip;
+            }
+            let answer = answer.trim();
+            let resolution = match answer.to_lowercase().as_str() {
+                "l" => Resolution::KeepLocal,
+                "r" => Resolution::KeepRemote,
+                "b" => Resolution::KeepBoth,
+                "s" => Resolution::Skip,
+                _ => continue,
+            };
+            if answer.chars().all(|c| c.is_ascii_uppercase()) {
+                self.always = Some(resolution);
+            }
+            return resolution;
+        }
+    }
+
+    fn download(&mut self, path: &str, remote: &File) -> Result<(), String> {
+        if let Some(remote_path) = self.state.names.get(path) {
+            println!("Saving {} as {}", remote_path, path);
+        }
+        let local_path = self.local_root.join(path);
+        let dir = local_path
+            .parent()
+            .unwrap_or(self.local_root)
+            .to_string_lossy()
+            .into_owned();
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        let options = DownloadOptions {
+            path: Some(&dir),
+            existing: ExistingFile::Overwrite,
+            long_paths: self.long_paths,
+            ..Default::default()
+        };
+        files::download(self.client, self.api_token, remote.id, &options)
+            .map_err(|e| format!("downloading {}: {}", path, e))?;
+        self.summary.downloaded += 1;
+        self.fetched.push(path.to_string());
+        self.record(path, remote.size.0, remote.crc32.clone())
+    }
+
+    /// Uploads a local file, replacing `existing` on Put.io if given.
+    fn upload(&mut self, path: &str, existing: Option<&File>) -> Result<(), String> {
+        let local_path = self.local_root.join(path);
+        let size = fs::metadata(&local_path)
+            .map_err(|e| format!("reading {}: {}", path, e))?
+            .len();
+        let parent_id = match path.rsplit_once('/') {
+            Some((dir, _)) => self.remote_folder(dir)?,
+            None => self.folders[""],
+        };
+        // Files already there, to tell the new copy apart when Put.io doesn't say
+        let before: HashSet<i64> = match existing {
+            Some(_) => files::list(self.client, self.api_token, parent_id)
+                .map_err(|e| format!("listing the folder of {}: {}", path, e))?
+                .files
+                .iter()
+                .map(|f| f.id)
+                .collect(),
+            None => HashSet::new(),
+        };
+        let uploaded = files::upload_file(
+            self.client,
+            self.api_token,
+            &local_path,
+            size,
+            parent_id,
+            &[],
+        )
+        .map_err(|e| format!("uploading {}: {}", path, e))?;
+
+        // The old copy is only removed once the new one is known to be up
+        if let Some(existing) = existing {
+            let new_copy = match uploaded {
+                Some(file) => file,
+                None => files::list(self.client, self.api_token, parent_id)
+                    .map_err(|e| format!("listing the folder of {}: {}", path, e))?
+                    .files
+                    .into_iter()
+                    .find(|f| !before.contains(&f.id) && f.size.0 == size)
+                    .map(|f| UploadedFile {
+                        id: f.id,
+                        name: f.name,
+                    })
+                    .ok_or_else(|| {
+                        format!(
+                            "the new copy of {} didn't show up on Put.io, the old one is kept",
+                            path
+                        )
+                    })?,
+            };
+            files::delete(self.client, self.api_token, &existing.id.to_string())
+                .map_err(|e| format!("replacing {}: {}", path, e))?;
+            // In case Put.io gave the upload another name since this one was taken
+            if new_copy.name != existing.name {
+                files::rename(self.client, self.api_token, new_copy.id, &existing.name)
+                    .map_err(|e| format!("renaming the new copy of {}: {}", path, e))?;
+            }
+        }
+        self.summary.uploaded += 1;
+        let crc32 = manifest::crc32_file(&local_path).ok();
+        self.record(path, size, crc32)
+    }
+
+    /// Puts the sidecars of the folders files were downloaded into in place
+    /// for as long as it takes to restore those files' modification times and
+    /// check their contents against them.
+    fn restore_sidecars(&mut self) -> Result<(), String> {
+        let dirs: BTreeSet<&str> = self.fetched.iter().map(|p| parent_of(p)).collect();
+        let mut written = vec![];
+        for dir in dirs {
+            let Some(sidecar) = self.sidecars.get(dir) else {
+                continue;
+            };
+            let path = self.local_root.join(dir).join(manifest::SIDECAR_NAME);
+            let contents = files::url(self.client, self.api_token, sidecar.id)
+                .and_then(|response| self.client.get(response.url).send())
+                .and_then(|response| response.error_for_status())
+                .and_then(|response| response.bytes())
+                .map_err(|e| format!("downloading sidecar of '{}': {}", dir, e))?;
+            fs::write(&path, contents).map_err(|e| format!("writing sidecar: {}", e))?;
+            written.push(path);
+        }
+        if written.is_empty() {
+            return Ok(());
+        }
+
+        files::restore_sidecars(&self.local_root.to_string_lossy());
+        for path in written {
+            fs::remove_file(path).ok();
+        }
+        // A restored time isn't a local change to upload next time
+        for path in std::mem::take(&mut self.fetched) {
+            let modified = fs::metadata(self.local_root.join(&path)).map(|m| modified_secs(&m));
+            if let (Some(synced), Ok(modified)) = (self.state.files.get_mut(&path), modified) {
+                synced.local_modified = modified;
+            }
+        }
+        self.state
+            .save()
+            .map_err(|e| format!("saving sync state: {}", e))
+    }
+
+    /// Returns the ID of a remote folder, creating it and its parents if needed.
+    fn remote_folder(&mut self, dir: &str) -> Result<i64, String> {
+        if let Some(id) = self.folders.get(dir) {
+            return Ok(*id);
+        }
+        let (parent, name) = dir.rsplit_once('/').unwrap_or(("", dir));
+        let parent_id = self.remote_folder(parent)?;
+        let folder = files::create_folder(self.client, self.api_token, name, parent_id)
+            .map_err(|e| format!("creating folder '{}': {}", dir, e))?;
+        self.folders.insert(dir.to_string(), folder.id);
+        Ok(folder.id)
+    }
+
+    fn record(&mut self, path: &str, size: u64, crc32: Option<String>) -> Result<(), String> {
+        let local_modified = fs::metadata(self.local_root.join(path))
+            .map(|m| modified_secs(&m))
+            .unwrap_or(0);
+        self.state.files.insert(
+            path.to_string(),
+            Synced {
+                size,
+                crc32,
+                local_modified,
+            },
+        );
+        self.state
+            .save()
+            .map_err(|e| format!("saving sync state: {}", e))
+    }
+}
+
+/// Returns the name a local file is kept under when both copies are kept,
+/// e.g. `Show/Episode (local).mkv`.
+fn both_name(path: &str) -> String {
+    let (dir, name) = match path.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), path),
+    };
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}{} (local).{}", dir, stem, ext),
+        _ => format!("{}{} (local)", dir, name),
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+
+/// A decoded bencode value.
+enum Value {
+    Int(i64),
+    Bytes(Vec<u8>),
+    List(Vec<Value>),
+    Dict(BTreeMap<Vec<u8>, Value>),
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(map) => map.get(key.as_bytes()),
+            _ => None,
+        }
+    }
+
+    fn as_int(&self) -> Option<i64> {
+        match self {
+            Value::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<String> {
+        match self {
+            Value::Bytes(b) => Some(String::from_utf8_lossy(b).to_string()),
+            _ => None,
+        }
+    }
+
+    fn as_list(&self) -> Option<&[Value]> {
+        match self {
+            Value::List(l) => Some(l),
+            _ => None,
+        }
+    }
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn peek(&self) -> Result<u8, String> {
+        self.data
+            .get(self.pos)
+            .copied()
+            .ok_or_else(|| "unexpected end of torrent data".to_string())
+    }
+
+    /// Reads ASCII digits (with an optional leading minus) up to `end`.
+    fn number(&mut self, end: u8) -> Result<i64, String> {
+        let start = self.pos;
+        while self.peek()? != end {
+            self.pos += 1;
+        }
+        let digits = std::str::from_utf8(&self.data[start..self.pos]).unwrap_or_default();
+        self.pos += 1;
+        digits
+            .parse()
+            .map_err(|_| format!("invalid number '{}' in torrent data", digits))
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        match self.peek()? {
+            b'i' => {
+                self.pos += 1;
+                Ok(Value::Int(self.number(b'e')?))
+            }
+            b'l' => {
+                self.pos += 1;
+                let mut list = vec![];
+                while self.peek()? != b'e' {
+                    list.push(self.value()?);
+                }
+                self.pos += 1;
+                Ok(Value::List(list))
+            }
+            b'd' => {
+                self.pos += 1;
+                let mut dict = BTreeMap::new();
+                while self.peek()? != b'e' {
+                    let Value::Bytes(key) = self.value()? else {
+                        return Err("dictionary key is not a string".to_string());
+                    };
+                    dict.insert(key, self.value()?);
+                }
+                self.pos += 1;
+                Ok(Value::Dict(dict))
+            }
+            b'0'..=b'9' => {
+                let len = self.number(b':')?;
+                let end = usize::try_from(len)
+                    .ok()
+                    .and_then(|len| self.pos.checked_add(len))
+                    .filter(|end| *end <= self.data.len())
+                    .ok_or_else(|| "unexpected end of torrent data".to_string())?;
+                let bytes = self.data[self.pos..end].to_vec();
+                self.pos = end;
+                Ok(Value::Bytes(bytes))
+            }
+            c => Err(format!("unexpected byte '{}' in torrent data", c as char)),
+        }
+    }
+}
+
+pub struct TorrentFile {
+    /// Path inside the torrent, separated by `/`. Does not include the torrent name.
+    pub path: String,
+    pub length: u64,
+}
+
+/// The metadata of a `.torrent` file.
+pub struct Torrent {
+    pub name: String,
+    pub files: Vec<TorrentFile>,
+    /// Size of each piece in bytes
+    pub piece_length: u64,
+    /// Announce URLs, the main tracker first
+    pub trackers: Vec<String>,
+}
+
+impl Torrent {
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.length).sum()
+    }
+
+    /// Returns true if the torrent contains a folder rather than a single file.
+    pub fn is_multi_file(&self) -> bool {
+        !(self.files.len() == 1 && self.files[0].path == self.name)
+    }
+}
+
+/// Parses the contents of a `.torrent` file.
+pub fn parse(data: &[u8]) -> Result<Torrent, String> {
+    let root = Decoder { data, pos: 0 }.value()?;
+    let info = root
+        .get("info")
+        .ok_or_else(|| "torrent is missing its info dictionary".to_string())?;
+
+    let name = info
+        .get("name")
+        .and_then(Value::as_str)
+        .ok_or_else(|| "torrent is missing a name".to_string())?;
+
+    let files = match info.get("files").and_then(Value::as_list) {
+        Some(entries) => entries
+            .iter()
+            .map(|entry| {
+                let path = entry
+                    .get("path")
+                    .and_then(Value::as_list)
+                    .unwrap_or_default()
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .collect::<Vec<String>>()
+                    .join("/");
+                TorrentFile {
+                    path,
+                    length: entry.get("length").and_then(Value::as_int).unwrap_or(0) as u64,
+                }
+            })
+            .collect(),
+        None => vec![TorrentFile {
+            path: name.clone(),
+            length: info.get("length").and_then(Value::as_int).unwrap_or(0) as u64,
+        }],
+    };
+
+    // `announce-list` is a list of tiers, each a list of URLs
+    let mut trackers: Vec<String> = root
+        .get("announce")
+        .and_then(Value::as_str)
+        .into_iter()
+        .collect();
+    for tier in root
+        .get("announce-list")
+        .and_then(Value::as_list)
+        .unwrap_or_default()
+    {
+        for url in tier
+            .as_list()
+            .unwrap_or_default()
+            .iter()
+            .filter_map(Value::as_str)
+        {
+            if !trackers.contains(&url) {
+                trackers.push(url);
+            }
+        }
+    }
+
+    Ok(Torrent {
+        name,
+        files,
+        piece_length: info
+            .get("piece length")
+            .and_then(Value::as_int)
+            .unwrap_or(0) as u64,
+        trackers,
+    })
+}
+
+/// What a magnet link tells about a torrent before its metadata is fetched.
+pub struct Magnet {
+    pub info_hash: String,
+    pub name: Option<String>,
+    /// Total size, if the link includes it
+    pub size: Option<u64>,
+    pub trackers: Vec<String>,
+}
+
+/// Parses a `magnet:?xt=urn:btih:...` link.
+pub fn parse_magnet(uri: &str) -> Result<Magnet, String> {
+    let url = reqwest::Url::parse(uri.trim()).map_err(|e| format!("invalid magnet link: {}", e))?;
+    if url.scheme() != "magnet" {
+        return Err("not a magnet link".to_string());
+    }
+
+    let mut magnet = Magnet {
+        info_hash: String::new(),
+        name: None,
+        size: None,
+        trackers: vec![],
+    };
+    for (key, value) in url.query_pairs() {
+        match key.as_ref() {
+            "xt" => {
+                if let Some(hash) = value.strip_prefix("urn:btih:") {
+                    magnet.info_hash = hash.to_lowercase();
+                }
+            }
+            "dn" => magnet.name = Some(value.into_owned()),
+            "xl" => magnet.size = value.parse().ok(),
+            "tr" => magnet.trackers.push(value.into_owned()),
+            _ => {}
+        }
+    }
+    if magnet.info_hash.is_empty() {
+        return Err("magnet link has no BitTorrent info hash".to_string());
+    }
+    Ok(magnet)
+}